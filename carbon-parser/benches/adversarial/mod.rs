@@ -0,0 +1,47 @@
+//! Expressions that make a PEG parser backtrack, shared by the
+//! `backtracking` benchmark and the timing tests in `integration_tests`.
+//!
+//! Every generator returns an expression for `parse_expression` that grows
+//! with `n`.
+
+/// A generator together with the name it is reported under.
+pub type Generator = (&'static str, fn(usize) -> String);
+
+/// Generators whose parse time grows linearly with `n`.
+pub const LINEAR: &[Generator] = &[
+    ("member_chain", member_chain),
+    ("binary_chain", binary_chain),
+    ("index_chain", index_chain),
+    ("nested_parens", nested_parens),
+    ("nested_calls", nested_calls),
+];
+
+/// `a.b.b.b`, with `n` member accesses.
+pub fn member_chain(n: usize) -> String {
+    format!("a{}", ".b".repeat(n))
+}
+
+/// `a + a * a - a`, with `n` operators.
+pub fn binary_chain(n: usize) -> String {
+    let ops = ["+", "*", "-", "/"];
+    let mut expr = String::from("a");
+    for op in ops.iter().cycle().take(n) {
+        expr.push_str(&format!(" {op} a"));
+    }
+    expr
+}
+
+/// `a[i][i][i]`, with `n` indexes.
+pub fn index_chain(n: usize) -> String {
+    format!("a{}", "[i]".repeat(n))
+}
+
+/// `((1))`, nested `n` deep.
+pub fn nested_parens(n: usize) -> String {
+    format!("{}1{}", "(".repeat(n), ")".repeat(n))
+}
+
+/// `f(f(1))`, nested `n` deep.
+pub fn nested_calls(n: usize) -> String {
+    format!("{}1{}", "f(".repeat(n), ")".repeat(n))
+}
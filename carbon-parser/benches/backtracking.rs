@@ -0,0 +1,28 @@
+//! Parse times for inputs that make the grammar backtrack.
+//!
+//! Run with `cargo bench --bench backtracking`. The generators are measured
+//! at sizes a factor of four apart, so their times should also grow about
+//! four times per step, for deep nesting as much as for long chains.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use carbon_parser::parse_expression;
+
+mod adversarial;
+
+fn linear(c: &mut Criterion) {
+    for (name, generate) in adversarial::LINEAR {
+        let mut group = c.benchmark_group(*name);
+        for n in [100, 400, 1600] {
+            let input = generate(n);
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(n), &input, |b, input| {
+                b.iter(|| parse_expression(input).expect("adversarial input parses"))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, linear);
+criterion_main!(benches);
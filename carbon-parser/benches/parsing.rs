@@ -0,0 +1,31 @@
+//! Parse throughput on synthetic programs.
+//!
+//! Run with `cargo bench --bench parsing`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use carbon_parser::{parse_carbon, parse_expression};
+
+mod synthetic;
+
+fn programs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_carbon");
+    for &(name, functions) in synthetic::SIZES {
+        let source = synthetic::program(functions);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new(name, functions), &source, |b, source| {
+            b.iter(|| parse_carbon(source).expect("synthetic program parses"))
+        });
+    }
+    group.finish();
+}
+
+fn nested_expression(c: &mut Criterion) {
+    let expr = synthetic::nested_arithmetic(5);
+    c.bench_function("parse_expression/nested_arithmetic", |b| {
+        b.iter(|| parse_expression(&expr).expect("nested expression parses"))
+    });
+}
+
+criterion_group!(benches, programs, nested_expression);
+criterion_main!(benches);
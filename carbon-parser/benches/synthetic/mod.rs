@@ -0,0 +1,45 @@
+//! Synthetic Carbon sources for the `parsing` benchmark.
+//!
+//! The generators are deterministic, so the same sizes always produce the
+//! same text and results can be compared between runs.
+
+/// Functions in the small, medium and large programs.
+pub const SIZES: &[(&str, usize)] = &[("small", 10), ("medium", 100), ("large", 1000)];
+
+/// A program of `functions` functions, each declaring, looping over and
+/// returning a few variables, followed by a `main` that calls the first one.
+pub fn program(functions: usize) -> String {
+    let mut source = String::new();
+    for i in 0..functions {
+        source.push_str(&format!(
+            "fn compute_{i}(x: i32, y: i32) -> i32 {{
+    var total: i32 = x * {i} + y;
+    let limit: i32 = {};
+    while (total < limit) {{
+        total += step(x, {i}) % 7;
+    }}
+    if (total > y) {{
+        return total - y;
+    }} else {{
+        return total;
+    }}
+}}
+
+",
+            i + 10
+        ));
+    }
+    source.push_str("fn main() -> i32 {\n    return compute_0(1, 2);\n}\n");
+    source
+}
+
+/// Arithmetic nested `depth` levels deep, such as `1 + (2 * (3 - (4)))` for
+/// a depth of 3.
+pub fn nested_arithmetic(depth: usize) -> String {
+    let ops = ["+", "*", "-", "/"];
+    let mut expr = (depth + 1).to_string();
+    for level in (0..depth).rev() {
+        expr = format!("{} {} ({expr})", level + 1, ops[level % ops.len()]);
+    }
+    expr
+}
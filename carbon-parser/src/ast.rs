@@ -0,0 +1,2782 @@
+//! Typed abstract syntax tree.
+//!
+//! The functions in the crate root return raw Pest pairs, which mirror the
+//! grammar one-to-one. This module converts them into owned Rust types so
+//! downstream tools can pattern-match on [`Stmt`] or [`Expr`] instead of
+//! walking [`Rule`] variants by hand.
+//!
+//! Every node carries the [`Span`] of the source text it was built from.
+//! Conversions are provided as `From<Pair<Rule>>` implementations; they expect
+//! a pair produced by the corresponding grammar rule and panic otherwise, which
+//! cannot happen for pairs obtained from a successful parse.
+//!
+//! Nodes are plain data with public fields, so refactoring tools can edit a
+//! tree in place, for example through [`Program::iter_functions_mut`]. Spans
+//! are not updated by such edits: they keep pointing at the original source
+//! and are stale until the program is printed (its `Display` output is the
+//! [canonical form](crate::format)) and parsed again.
+//!
+//! Declarations and statements also have a `diagnostics` list. Parsing leaves
+//! it empty; [`annotate`](crate::semantics::annotate) fills it in from the
+//! results of the semantic checks.
+//!
+//! With the `serde` feature enabled, every node (and [`Span`], which
+//! serializes as `{"start": .., "end": ..}`) implements `Serialize` and
+//! `Deserialize`, so parsed programs can be cached or sent between processes.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use carbon_parser::ast::{Item, Stmt};
+//! use carbon_parser::parse_to_ast;
+//!
+//! let program = parse_to_ast("fn main() -> i32 { return 0; }").unwrap();
+//! let Item::Function(main) = &program.items[0] else {
+//!     panic!("expected a function");
+//! };
+//! assert_eq!(main.name.name, "main");
+//! assert!(matches!(main.body.stmts[0], Stmt::Return(_)));
+//! ```
+
+use crate::consteval::eval_const_i64;
+use crate::expr::build_expr_tree;
+use crate::semantics::SemanticError;
+use crate::{Rule, Span};
+use pest::iterators::Pair;
+use std::collections::BTreeSet;
+
+/// A complete Carbon source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Program {
+    /// The `package` declaration the file starts with, if any.
+    pub package: Option<PackageDecl>,
+    /// `import` declarations in source order.
+    pub imports: Vec<ImportDecl>,
+    /// Top-level declarations in source order.
+    ///
+    /// Add and remove declarations with [`Program::push_item`] and
+    /// [`Program::remove_item`], which keep [`Program::top_level`] in step.
+    pub items: Vec<Item>,
+    /// Declarations and, in [script mode](crate::ParseOptions::script_mode),
+    /// statements in source order.
+    ///
+    /// Each declaration appears here as its index in [`Program::items`].
+    /// Printing and walking the program go through this list, so an index
+    /// that no longer matches `items` panics there.
+    pub top_level: Vec<TopItem>,
+    /// `//! pragma` directives in source order, wherever they appear between
+    /// the declarations.
+    pub pragmas: Vec<Pragma>,
+    pub span: Span,
+}
+
+/// `package Name api;`, naming the package a file belongs to.
+///
+/// A library within the package can be named too:
+/// `package Geometry library "Shapes" impl;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackageDecl {
+    pub name: Ident,
+    /// The library name, without its quotes.
+    pub library: Option<String>,
+    pub kind: PackageKind,
+    pub span: Span,
+}
+
+/// Whether a file declares the API of a package or implements it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PackageKind {
+    Api,
+    Impl,
+}
+
+impl PackageKind {
+    /// The keyword as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageKind::Api => "api",
+            PackageKind::Impl => "impl",
+        }
+    }
+}
+
+/// `import Name;` or `import Name library "Lib";`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportDecl {
+    /// The imported package.
+    pub name: Ident,
+    /// The library name, without its quotes.
+    pub library: Option<String>,
+    pub span: Span,
+}
+
+/// An entry of [`Program::top_level`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TopItem {
+    /// The declaration at this index of [`Program::items`]. The index stays
+    /// valid as long as declarations are added and removed with
+    /// [`Program::push_item`] and [`Program::remove_item`].
+    Item(usize),
+    /// A statement outside any function, only produced in script mode.
+    Stmt(Box<Stmt>),
+}
+
+/// A directive for tools, written as a line of its own at the top level:
+/// `//! pragma optimize(2)`.
+///
+/// The parser accepts any pragma name; [`crate::check_ast`] warns about names
+/// it does not know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pragma {
+    pub name: Ident,
+    /// Arguments as written, such as `2`, `fast` or `"O3"`.
+    pub args: Vec<String>,
+    pub span: Span,
+}
+
+/// A top-level declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Item {
+    Function(FunctionDecl),
+    Class(ClassDecl),
+    Choice(ChoiceDecl),
+    Var(VarDecl),
+    Let(LetDecl),
+}
+
+/// A class declaration: `class Name[T:! type](N:! i32) where T impls Bound { members }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassDecl {
+    pub modifier: Option<ClassModifier>,
+    pub name: Ident,
+    /// Parameters in square brackets, deduced rather than passed explicitly.
+    pub deduced_params: Vec<GenericParam>,
+    /// Parameters in parentheses.
+    pub generic_params: Vec<GenericParam>,
+    /// The base class named after `extends`.
+    pub extends: Option<TypeName>,
+    /// Constraints from the `where` clause.
+    pub constraints: Vec<WhereConstraint>,
+    /// Fields and methods in source order.
+    pub members: Vec<ClassMember>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// Restricts which classes may extend a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClassModifier {
+    /// No class may extend it.
+    Final,
+    /// Only classes in the same file may extend it.
+    Sealed,
+}
+
+impl ClassModifier {
+    /// The modifier as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClassModifier::Final => "final",
+            ClassModifier::Sealed => "sealed",
+        }
+    }
+}
+
+/// A choice type: `choice Name { A, B = 2 }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChoiceDecl {
+    pub name: Ident,
+    pub variants: Vec<ChoiceVariant>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+impl ChoiceDecl {
+    /// The integer value of every variant, in declaration order.
+    ///
+    /// An explicit discriminant is folded with [`eval_const_i64`]; an omitted
+    /// one is the previous value plus one, starting from zero. The entry is
+    /// `None` when the explicit value is not a compile-time constant or when
+    /// it follows such a value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::ast::Item;
+    /// use carbon_parser::parse_to_ast;
+    ///
+    /// let program = parse_to_ast("choice Level { Low, High = 2 * 5, Max }").unwrap();
+    /// let Item::Choice(level) = &program.items[0] else { unreachable!() };
+    /// assert_eq!(level.discriminants(), [Some(0), Some(10), Some(11)]);
+    /// ```
+    pub fn discriminants(&self) -> Vec<Option<i64>> {
+        let mut next = Some(0);
+        self.variants
+            .iter()
+            .map(|variant| {
+                let value = match &variant.value {
+                    Some(expr) => eval_const_i64(expr),
+                    None => next,
+                };
+                next = value.and_then(|value| value.checked_add(1));
+                value
+            })
+            .collect()
+    }
+}
+
+/// A variant of a choice type, with an optional explicit discriminant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChoiceVariant {
+    pub name: Ident,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+/// A compile-time parameter of a generic class: `T:! type`.
+///
+/// In a list parsed by [`parse_type_params`](crate::parse_type_params), a
+/// bare `T` is short for `T:! type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenericParam {
+    pub name: Ident,
+    pub ty: TypeName,
+    pub span: Span,
+}
+
+/// A `where` constraint: `T impls Bound`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhereConstraint {
+    /// The constrained type parameter.
+    pub param: Ident,
+    pub bound: TypeName,
+    pub span: Span,
+}
+
+impl ClassDecl {
+    /// Deduced and explicit parameters, in source order.
+    pub fn type_params(&self) -> impl Iterator<Item = &GenericParam> {
+        self.deduced_params.iter().chain(&self.generic_params)
+    }
+}
+
+/// A member of a class body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClassMember {
+    Field(VarDecl),
+    Method(FunctionDecl),
+    IndexOperator(IndexOperatorDecl),
+}
+
+/// An index operator overload: `fn (self: Self) [index: i32] -> T { body }`.
+///
+/// It is what `container[i]` calls when `container` is an instance of the
+/// class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexOperatorDecl {
+    /// The parameter in parentheses, normally `self: Self`.
+    pub receiver: Param,
+    /// The parameters in square brackets; a valid overload has exactly one.
+    pub index_params: Vec<Param>,
+    pub return_type: Option<TypeName>,
+    pub body: Block,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// A function declaration: `fn name(params) throws -> ReturnType { body }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionDecl {
+    pub name: Ident,
+    pub params: Vec<Param>,
+    /// `true` if the function is declared with the `async` marker.
+    pub is_async: bool,
+    /// `true` if the function is declared with the `throws` marker.
+    pub throws: bool,
+    pub return_type: Option<TypeName>,
+    pub body: Block,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// A function parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Param {
+    /// `name: Type`, `name:! Type`, optionally followed by `= default`.
+    Regular {
+        /// `out` or `inout` written before the name; [`ParamDirection::In`]
+        /// otherwise.
+        direction: ParamDirection,
+        name: Ident,
+        ty: TypeName,
+        /// `true` for compile-time parameters bound with `:!`.
+        comptime: bool,
+        default: Option<Expr>,
+        span: Span,
+    },
+    /// A trailing `...` or `name: ...Type` accepting any number of arguments.
+    Variadic {
+        name: Option<Ident>,
+        ty: Option<TypeName>,
+        span: Span,
+    },
+    /// `(x, y): (i32, i32)` or `Point(x, y): Point`, binding the parts of the
+    /// argument instead of the argument itself.
+    Pattern {
+        /// A [`Pattern::Tuple`] or [`Pattern::Constructor`].
+        pattern: Box<Pattern>,
+        ty: TypeName,
+        span: Span,
+    },
+}
+
+/// How an argument is passed to a [`Param::Regular`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParamDirection {
+    /// The function reads the argument. This is the default, with no modifier.
+    #[default]
+    In,
+    /// `out`: the function assigns the argument before reading it.
+    Out,
+    /// `inout`: the function reads and may assign the argument.
+    InOut,
+}
+
+impl ParamDirection {
+    /// The modifier as written in source, or `None` for [`ParamDirection::In`].
+    pub fn keyword(&self) -> Option<&'static str> {
+        match self {
+            ParamDirection::In => None,
+            ParamDirection::Out => Some("out"),
+            ParamDirection::InOut => Some("inout"),
+        }
+    }
+}
+
+/// A variable declaration: `var name: Type = value;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VarDecl {
+    pub name: Ident,
+    /// The declared type; `auto` when it is omitted, as in `var x = 42;`.
+    pub ty: TypeName,
+    pub init: Option<Expr>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// An immutable binding: `let name: Type = value;`. Unlike a [`VarDecl`], it
+/// always has an initializer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LetDecl {
+    pub name: Ident,
+    /// The declared type; `auto` when it is omitted, as in `let x = 42;`.
+    pub ty: TypeName,
+    pub init: Expr,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// A braced sequence of statements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+    /// Statements in source order. A trailing expression without a semicolon
+    /// is stored as the last [`Stmt::Expr`] with `has_semicolon == false`.
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
+
+/// A statement inside a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stmt {
+    Var(VarDecl),
+    Let(LetDecl),
+    Return(ReturnStmt),
+    Throw(ThrowStmt),
+    Fallthrough(FallthroughStmt),
+    Break(BreakStmt),
+    Continue(ContinueStmt),
+    If(IfStmt),
+    While(WhileStmt),
+    For(ForStmt),
+    CFor(CForStmt),
+    Match(MatchStmt),
+    Assign(AssignStmt),
+    MultiAssign(MultiAssignStmt),
+    Expr(ExprStmt),
+}
+
+/// `return value;` or `return;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReturnStmt {
+    pub value: Option<Expr>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `throw error;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThrowStmt {
+    pub value: Expr,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `fallthrough;`, continuing with the body of the next match arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FallthroughStmt {
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `break;`, leaving the innermost loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakStmt {
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `continue;`, starting the next iteration of the innermost loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContinueStmt {
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `if (condition) { ... } else { ... }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfStmt {
+    pub condition: Condition,
+    pub then_block: Block,
+    pub else_branch: Option<ElseBranch>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+impl IfStmt {
+    /// Iterates over this statement and every `else if` chained to it.
+    pub fn chain(&self) -> impl Iterator<Item = &IfStmt> {
+        std::iter::successors(Some(self), |if_stmt| match &if_stmt.else_branch {
+            Some(ElseBranch::If(next)) => Some(next),
+            _ => None,
+        })
+    }
+
+    /// The final `else { ... }` block of the chain, if there is one.
+    pub fn final_else(&self) -> Option<&Block> {
+        match &self.chain().last()?.else_branch {
+            Some(ElseBranch::Block(block)) => Some(block),
+            _ => None,
+        }
+    }
+}
+
+/// What follows `else` in an [`IfStmt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElseBranch {
+    /// `else { ... }`.
+    Block(Block),
+    /// `else if (...) { ... }`.
+    If(Box<IfStmt>),
+}
+
+/// `while (condition) { ... }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhileStmt {
+    pub condition: Expr,
+    pub body: Block,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `for (var name: Type in iterable) { ... }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForStmt {
+    /// The loop variable, visible only in the body.
+    pub name: Ident,
+    pub ty: TypeName,
+    pub iterable: Expr,
+    pub body: Block,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `for (init; condition; update) { ... }`, where each clause may be empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CForStmt {
+    /// A [`Stmt::Var`], [`Stmt::Assign`] or [`Stmt::Expr`] run once before
+    /// the loop. A variable it declares is visible only in the loop.
+    pub init: Option<Box<Stmt>>,
+    /// Checked before every iteration; the loop runs forever without one.
+    pub condition: Option<Expr>,
+    /// A [`Stmt::Assign`] or [`Stmt::Expr`] run after every iteration. Its
+    /// span does not include a semicolon, as the clause has none.
+    pub update: Option<Box<Stmt>>,
+    pub body: Block,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// The condition of an `if` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// A plain boolean expression.
+    Expr(Expr),
+    /// `var name: Type = value`, visible only in the then-branch.
+    Binding(Binding),
+}
+
+/// A variable introduced by an `if` condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Binding {
+    pub name: Ident,
+    pub ty: TypeName,
+    pub value: Expr,
+    pub span: Span,
+}
+
+/// `match (value) { case pattern if guard => { ... } default => { ... } }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchStmt {
+    pub scrutinee: Expr,
+    pub arms: Vec<MatchArm>,
+    /// Body of the `default =>` arm, if present.
+    pub default: Option<Block>,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// A single `case` arm of a [`MatchStmt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    /// Optional `if condition` that must also hold for the arm to match.
+    pub guard: Option<Expr>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// A pattern in a `case` arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    /// `_`, matching anything without binding it.
+    Wildcard(Span),
+    /// A literal that the value must equal.
+    Literal(Literal),
+    /// `name` or `name: Type`, binding the matched value.
+    Binding {
+        name: Ident,
+        ty: Option<TypeName>,
+        span: Span,
+    },
+    /// `Name(patterns)`, matching a constructed value and its fields.
+    Constructor {
+        name: Ident,
+        args: Vec<Pattern>,
+        span: Span,
+    },
+    /// `(first, second)`, matching a tuple element by element.
+    Tuple { elements: Vec<Pattern>, span: Span },
+}
+
+/// `target = value;` or a compound form such as `target += value;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssignStmt {
+    pub target: Ident,
+    pub op: AssignOp,
+    pub value: Expr,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// `first, second = value;`, assigning each element of a tuple value to one
+/// target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiAssignStmt {
+    /// At least two targets, in source order.
+    pub targets: Vec<Ident>,
+    pub value: Expr,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// Assignment operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssignOp {
+    Assign,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl AssignOp {
+    /// The operator as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignOp::Assign => "=",
+            AssignOp::Add => "+=",
+            AssignOp::Sub => "-=",
+            AssignOp::Mul => "*=",
+            AssignOp::Div => "/=",
+        }
+    }
+
+    /// The binary operator a compound assignment applies, or `None` for `=`.
+    pub fn binary_op(&self) -> Option<BinaryOp> {
+        match self {
+            AssignOp::Assign => None,
+            AssignOp::Add => Some(BinaryOp::Add),
+            AssignOp::Sub => Some(BinaryOp::Sub),
+            AssignOp::Mul => Some(BinaryOp::Mul),
+            AssignOp::Div => Some(BinaryOp::Div),
+        }
+    }
+
+    fn from_rule(rule: Rule) -> Self {
+        match rule {
+            Rule::assign => AssignOp::Assign,
+            Rule::add_assign => AssignOp::Add,
+            Rule::sub_assign => AssignOp::Sub,
+            Rule::mul_assign => AssignOp::Mul,
+            Rule::div_assign => AssignOp::Div,
+            other => unreachable!("not an assignment operator: {other:?}"),
+        }
+    }
+}
+
+/// An expression used as a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprStmt {
+    pub expr: Expr,
+    /// `false` for a block's trailing expression written without `;`.
+    pub has_semicolon: bool,
+    pub span: Span,
+    pub diagnostics: Vec<SemanticError>,
+}
+
+/// An expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expr {
+    Literal(Literal),
+    Ident(Ident),
+    Call(CallExpr),
+    Binary(BinaryExpr),
+    Paren(ParenExpr),
+    Await(AwaitExpr),
+    Try(TryExpr),
+    Member(MemberExpr),
+    MethodCall(MethodCallExpr),
+    TupleIndex(TupleIndexExpr),
+    Index(IndexExpr),
+    /// A built-in type used as a value, as in `var t: Type = i32;`.
+    TypeValue(TypeName),
+    Convert(ConvertExpr),
+    Conditional(ConditionalExpr),
+}
+
+/// A function call: `callee(args)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallExpr {
+    pub callee: Ident,
+    pub args: Vec<Expr>,
+    pub span: Span,
+}
+
+/// An explicit conversion to a built-in type: `i64(x)`.
+///
+/// Only built-in types convert this way; `MyType(x)` is a [`CallExpr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConvertExpr {
+    pub ty: TypeName,
+    pub arg: Box<Expr>,
+    pub span: Span,
+}
+
+/// A conditional expression: `if condition then then_branch else else_branch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConditionalExpr {
+    pub condition: Box<Expr>,
+    pub then_branch: Box<Expr>,
+    pub else_branch: Box<Expr>,
+    pub span: Span,
+}
+
+/// A binary operation: `lhs op rhs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryExpr {
+    pub op: BinaryOp,
+    pub lhs: Box<Expr>,
+    pub rhs: Box<Expr>,
+    pub span: Span,
+}
+
+/// A parenthesized expression, kept so source grouping is not lost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParenExpr {
+    pub inner: Box<Expr>,
+    pub span: Span,
+}
+
+/// `await operand`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AwaitExpr {
+    pub operand: Box<Expr>,
+    pub span: Span,
+}
+
+/// `operand?`, propagating an error from `operand` to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TryExpr {
+    pub operand: Box<Expr>,
+    pub span: Span,
+}
+
+/// Named field access: `base.member`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemberExpr {
+    pub base: Box<Expr>,
+    pub member: Ident,
+    pub span: Span,
+}
+
+/// A method call: `receiver.method(args)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodCallExpr {
+    pub receiver: Box<Expr>,
+    pub method: Ident,
+    pub args: Vec<Expr>,
+    pub span: Span,
+}
+
+/// Positional tuple element access: `base.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TupleIndexExpr {
+    pub base: Box<Expr>,
+    pub index: u32,
+    pub span: Span,
+}
+
+/// Indexing: `base[index]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexExpr {
+    pub base: Box<Expr>,
+    pub index: Box<Expr>,
+    pub span: Span,
+}
+
+/// Binary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// Returns `true` for `+`, `-`, `*`, `/` and `%`.
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem
+        )
+    }
+
+    /// The operator as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::Le => "<=",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+
+    pub(crate) fn from_rule(rule: Rule) -> Self {
+        match rule {
+            Rule::add => BinaryOp::Add,
+            Rule::sub => BinaryOp::Sub,
+            Rule::mul => BinaryOp::Mul,
+            Rule::div => BinaryOp::Div,
+            Rule::rem => BinaryOp::Rem,
+            Rule::eq => BinaryOp::Eq,
+            Rule::ne => BinaryOp::Ne,
+            Rule::lt => BinaryOp::Lt,
+            Rule::gt => BinaryOp::Gt,
+            Rule::le => BinaryOp::Le,
+            Rule::ge => BinaryOp::Ge,
+            Rule::and => BinaryOp::And,
+            Rule::or => BinaryOp::Or,
+            other => unreachable!("not a binary operator: {other:?}"),
+        }
+    }
+}
+
+/// A literal value, kept as written in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Literal {
+    pub kind: LiteralKind,
+    /// The literal's source text, including quotes for strings and characters.
+    pub text: String,
+    pub span: Span,
+}
+
+/// The kind of a [`Literal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LiteralKind {
+    Integer,
+    Float,
+    Bool,
+    String,
+    Char,
+}
+
+/// An identifier together with its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A type annotation such as `i32`, `CustomType`, `Vector(i32)`, `[i32; 4]`
+/// or `const i32*mut`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeName {
+    /// The base type, without qualifiers, generic arguments or pointer
+    /// suffixes. For arrays and
+    /// slices this is the normalized form, such as `[i32; 4]`.
+    pub name: String,
+    /// Set when the base type is an array or a slice.
+    pub array: Option<Box<ArrayType>>,
+    /// Generic arguments of the base type, as in `Vector(i32)`.
+    pub args: Vec<TypeName>,
+    /// Set when the base type is a tuple.
+    pub tuple: Option<Box<TupleType>>,
+    /// Qualifiers written before the base type.
+    pub qualifiers: Qualifiers,
+    /// One entry per `*`, innermost first, holding the qualifiers written
+    /// after it.
+    pub pointers: Vec<Qualifiers>,
+    pub span: Span,
+}
+
+impl TypeName {
+    /// A type that is just a name, without qualifiers, arguments or pointers.
+    pub(crate) fn bare(name: &str, span: Span) -> Self {
+        TypeName {
+            name: name.to_string(),
+            array: None,
+            args: Vec::new(),
+            tuple: None,
+            qualifiers: Qualifiers::default(),
+            pointers: Vec::new(),
+            span,
+        }
+    }
+
+    /// Whether this is `auto`, the type left for the compiler to infer.
+    pub fn is_auto(&self) -> bool {
+        self.canonical() == "auto" && self.array.is_none() && self.tuple.is_none()
+    }
+
+    /// Renders the type in a normalized form.
+    ///
+    /// Leading qualifiers come before the base type, pointer qualifiers
+    /// directly after their `*`, `const` always before `mut`, and whitespace is
+    /// dropped between the base type and its pointer suffixes. Types that differ
+    /// only in spelling therefore have the same canonical form.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::ast::Item;
+    /// use carbon_parser::parse_to_ast;
+    ///
+    /// let program = parse_to_ast("var p: mut const i32 * const;").unwrap();
+    /// let Item::Var(decl) = &program.items[0] else { unreachable!() };
+    /// assert_eq!(decl.ty.canonical(), "const mut i32*const");
+    /// ```
+    pub fn canonical(&self) -> String {
+        let mut out = String::new();
+        for qualifier in self.qualifiers.keywords() {
+            out.push_str(qualifier);
+            out.push(' ');
+        }
+        out.push_str(&self.name);
+        if !self.args.is_empty() {
+            let args: Vec<String> = self.args.iter().map(TypeName::canonical).collect();
+            out.push('(');
+            out.push_str(&args.join(", "));
+            out.push(')');
+        }
+        for pointer in &self.pointers {
+            out.push('*');
+            out.push_str(&pointer.keywords().collect::<Vec<_>>().join(" "));
+        }
+        out
+    }
+}
+
+/// A fixed-size array type `[element; size]`, or a slice `[element]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArrayType {
+    pub element: TypeName,
+    /// The size expression; `None` for a slice.
+    pub size: Option<Expr>,
+    pub span: Span,
+}
+
+/// A tuple type `(first, second, ...)` with at least two elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TupleType {
+    pub elements: Vec<TypeName>,
+    pub span: Span,
+}
+
+/// `const` and `mut` flags attached to a type or a pointer level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_mut: bool,
+}
+
+impl Qualifiers {
+    /// The qualifier keywords that are set, in canonical order.
+    pub(crate) fn keywords(&self) -> impl Iterator<Item = &'static str> {
+        [(self.is_const, "const"), (self.is_mut, "mut")]
+            .into_iter()
+            .filter_map(|(set, keyword)| set.then_some(keyword))
+    }
+
+    fn add(&mut self, qualifier: &str) {
+        match qualifier {
+            "const" => self.is_const = true,
+            "mut" => self.is_mut = true,
+            other => unreachable!("not a type qualifier: {other}"),
+        }
+    }
+}
+
+impl Expr {
+    /// Source span of the whole expression.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal(literal) => literal.span,
+            Expr::Ident(ident) => ident.span,
+            Expr::Call(call) => call.span,
+            Expr::Binary(binary) => binary.span,
+            Expr::Paren(paren) => paren.span,
+            Expr::Await(await_expr) => await_expr.span,
+            Expr::Try(try_expr) => try_expr.span,
+            Expr::Member(member) => member.span,
+            Expr::MethodCall(call) => call.span,
+            Expr::TupleIndex(tuple_index) => tuple_index.span,
+            Expr::Index(index) => index.span,
+            Expr::TypeValue(ty) => ty.span,
+            Expr::Convert(convert) => convert.span,
+            Expr::Conditional(conditional) => conditional.span,
+        }
+    }
+
+    /// Names of the variables the expression reads, sorted and deduplicated.
+    ///
+    /// Called function names and the names after a `.` are not variables and
+    /// are left out. No expression form binds a name of its own, so every
+    /// identifier in the expression is free.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_expression_ast;
+    ///
+    /// let expr = parse_expression_ast("scale(x) + p.x * x - offset").unwrap();
+    /// let names: Vec<_> = expr.free_variables().into_iter().collect();
+    /// assert_eq!(names, ["offset", "p", "x"]);
+    /// ```
+    pub fn free_variables(&self) -> BTreeSet<String> {
+        struct FreeVariables(BTreeSet<String>);
+
+        impl<'ast> Visitor<'ast> for FreeVariables {
+            fn visit_expr(&mut self, expr: &'ast Expr) {
+                if let Expr::Ident(ident) = expr {
+                    self.0.insert(ident.name.clone());
+                }
+                walk_expr(self, expr);
+            }
+        }
+
+        let mut free = FreeVariables(BTreeSet::new());
+        free.visit_expr(self);
+        free.0
+    }
+}
+
+impl Stmt {
+    /// Source span of the whole statement.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Var(decl) => decl.span,
+            Stmt::Let(decl) => decl.span,
+            Stmt::Return(stmt) => stmt.span,
+            Stmt::Throw(stmt) => stmt.span,
+            Stmt::Fallthrough(stmt) => stmt.span,
+            Stmt::Break(stmt) => stmt.span,
+            Stmt::Continue(stmt) => stmt.span,
+            Stmt::If(stmt) => stmt.span,
+            Stmt::While(stmt) => stmt.span,
+            Stmt::For(stmt) => stmt.span,
+            Stmt::CFor(stmt) => stmt.span,
+            Stmt::Match(stmt) => stmt.span,
+            Stmt::Assign(stmt) => stmt.span,
+            Stmt::MultiAssign(stmt) => stmt.span,
+            Stmt::Expr(stmt) => stmt.span,
+        }
+    }
+}
+
+impl Pattern {
+    /// Source span of the whole pattern.
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(span) => *span,
+            Pattern::Literal(literal) => literal.span,
+            Pattern::Binding { span, .. }
+            | Pattern::Constructor { span, .. }
+            | Pattern::Tuple { span, .. } => *span,
+        }
+    }
+}
+
+impl Program {
+    /// Every function in the program, including class methods, in source
+    /// order.
+    ///
+    /// Spans of edited nodes become stale; see the [module documentation](self).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_to_ast;
+    ///
+    /// let mut program = parse_to_ast("class C { fn get() {} } fn main() {}").unwrap();
+    /// for function in program.iter_functions_mut() {
+    ///     function.name.name = function.name.name.to_uppercase();
+    /// }
+    /// assert_eq!(program.to_string(), "class C {\n  fn GET() {}\n}\n\nfn MAIN() {}\n");
+    /// ```
+    pub fn iter_functions_mut(&mut self) -> impl Iterator<Item = &mut FunctionDecl> {
+        self.items.iter_mut().flat_map(|item| {
+            let (function, class) = match item {
+                Item::Function(function) => (Some(function), None),
+                Item::Class(class) => (None, Some(class)),
+                _ => (None, None),
+            };
+            let methods = class.into_iter().flat_map(|class| class.methods_mut());
+            function.into_iter().chain(methods)
+        })
+    }
+
+    /// Every class declared at the top level, in source order.
+    pub fn iter_classes_mut(&mut self) -> impl Iterator<Item = &mut ClassDecl> {
+        self.items.iter_mut().filter_map(|item| match item {
+            Item::Class(class) => Some(class),
+            _ => None,
+        })
+    }
+
+    /// Every top-level `var` declaration, in source order.
+    pub fn iter_vars_mut(&mut self) -> impl Iterator<Item = &mut VarDecl> {
+        self.items.iter_mut().filter_map(|item| match item {
+            Item::Var(decl) => Some(decl),
+            _ => None,
+        })
+    }
+
+    /// Adds `item` as the last top-level declaration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_to_ast;
+    ///
+    /// let mut program = parse_to_ast("fn main() {}").unwrap();
+    /// let helper = parse_to_ast("fn helper() {}").unwrap().remove_item(0);
+    /// program.push_item(helper);
+    /// assert_eq!(program.to_string(), "fn main() {}\n\nfn helper() {}\n");
+    /// ```
+    pub fn push_item(&mut self, item: Item) {
+        self.top_level.push(TopItem::Item(self.items.len()));
+        self.items.push(item);
+    }
+
+    /// Removes and returns the declaration at `index` of [`Program::items`],
+    /// together with its entry in [`Program::top_level`]. The entries of the
+    /// declarations after it are renumbered to match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_item(&mut self, index: usize) -> Item {
+        let item = self.items.remove(index);
+        self.top_level.retain_mut(|top| match top {
+            TopItem::Item(at) if *at == index => false,
+            TopItem::Item(at) => {
+                if *at > index {
+                    *at -= 1;
+                }
+                true
+            }
+            TopItem::Stmt(_) => true,
+        });
+        item
+    }
+
+    /// The declaration an entry of [`Program::top_level`] refers to.
+    pub(crate) fn top_level_item(&self, index: usize) -> &Item {
+        self.items.get(index).unwrap_or_else(|| {
+            panic!(
+                "top_level refers to declaration {index} but there are only {}; \
+                 edit items with Program::push_item and Program::remove_item",
+                self.items.len()
+            )
+        })
+    }
+}
+
+/// A hash of the structure of `program` that ignores spans, comments,
+/// diagnostics and layout.
+///
+/// Programs that differ only in formatting hash equal, so the hash can key a
+/// cache of analysis results. It is computed with 64-bit FNV-1a over the
+/// [canonical form](crate::format), which makes it the same on every platform
+/// and in every run.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::ast_hash;
+/// use carbon_parser::parse_to_ast;
+///
+/// let compact = parse_to_ast("fn main(){var x:i32=1;}").unwrap();
+/// let spaced = parse_to_ast("fn main() {\n    var x: i32 = 1; // one\n}\n").unwrap();
+/// assert_eq!(ast_hash(&compact), ast_hash(&spaced));
+/// ```
+pub fn ast_hash(program: &Program) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    program
+        .to_string()
+        .bytes()
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+impl ClassDecl {
+    /// The class's methods, in source order.
+    pub fn methods_mut(&mut self) -> impl Iterator<Item = &mut FunctionDecl> {
+        self.members.iter_mut().filter_map(|member| match member {
+            ClassMember::Method(method) => Some(method),
+            _ => None,
+        })
+    }
+}
+
+impl Item {
+    /// Source span of the whole declaration.
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Function(function) => function.span,
+            Item::Class(class) => class.span,
+            Item::Choice(choice) => choice.span,
+            Item::Var(var) => var.span,
+            Item::Let(decl) => decl.span,
+        }
+    }
+}
+
+/// Read-only traversal of the AST.
+///
+/// Every method has a default implementation that calls the matching `walk_*`
+/// function, which visits the node's children. Override only the nodes you
+/// care about, and call the `walk_*` function from the override to keep
+/// descending into that node's children.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::{walk_program, Expr, Visitor};
+/// use carbon_parser::parse_to_ast;
+///
+/// #[derive(Default)]
+/// struct Calls(Vec<String>);
+///
+/// impl<'ast> Visitor<'ast> for Calls {
+///     fn visit_expr(&mut self, expr: &'ast Expr) {
+///         if let Expr::Call(call) = expr {
+///             self.0.push(call.callee.name.clone());
+///         }
+///         carbon_parser::ast::walk_expr(self, expr);
+///     }
+/// }
+///
+/// let program = parse_to_ast("fn main() { print(square(2)); }").unwrap();
+/// let mut calls = Calls::default();
+/// walk_program(&mut calls, &program);
+/// assert_eq!(calls.0, ["print", "square"]);
+/// ```
+pub trait Visitor<'ast> {
+    fn visit_item(&mut self, item: &'ast Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_function(&mut self, function: &'ast FunctionDecl) {
+        walk_function(self, function);
+    }
+
+    fn visit_class(&mut self, class: &'ast ClassDecl) {
+        walk_class(self, class);
+    }
+
+    fn visit_index_operator(&mut self, operator: &'ast IndexOperatorDecl) {
+        walk_index_operator(self, operator);
+    }
+
+    fn visit_choice(&mut self, choice: &'ast ChoiceDecl) {
+        walk_choice(self, choice);
+    }
+
+    fn visit_var_decl(&mut self, decl: &'ast VarDecl) {
+        walk_var_decl(self, decl);
+    }
+
+    fn visit_let_decl(&mut self, decl: &'ast LetDecl) {
+        walk_let_decl(self, decl);
+    }
+
+    fn visit_param(&mut self, param: &'ast Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_type_name(&mut self, ty: &'ast TypeName) {
+        walk_type_name(self, ty);
+    }
+}
+
+/// Visits every entry of [`Program::top_level`] in source order.
+pub fn walk_program<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, program: &'ast Program) {
+    for top in &program.top_level {
+        match top {
+            TopItem::Item(index) => visitor.visit_item(program.top_level_item(*index)),
+            TopItem::Stmt(stmt) => visitor.visit_stmt(stmt),
+        }
+    }
+}
+
+/// Visits the declaration inside `item`.
+pub fn walk_item<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, item: &'ast Item) {
+    match item {
+        Item::Function(function) => visitor.visit_function(function),
+        Item::Class(class) => visitor.visit_class(class),
+        Item::Choice(choice) => visitor.visit_choice(choice),
+        Item::Var(decl) => visitor.visit_var_decl(decl),
+        Item::Let(decl) => visitor.visit_let_decl(decl),
+    }
+}
+
+/// Visits the parameters, return type and body of `function`.
+pub fn walk_function<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    function: &'ast FunctionDecl,
+) {
+    for param in &function.params {
+        visitor.visit_param(param);
+    }
+    if let Some(return_type) = &function.return_type {
+        visitor.visit_type_name(return_type);
+    }
+    visitor.visit_block(&function.body);
+}
+
+/// Visits the type parameters, base class, constraints and members of `class`.
+pub fn walk_class<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, class: &'ast ClassDecl) {
+    for param in class.type_params() {
+        visitor.visit_type_name(&param.ty);
+    }
+    if let Some(extends) = &class.extends {
+        visitor.visit_type_name(extends);
+    }
+    for constraint in &class.constraints {
+        visitor.visit_type_name(&constraint.bound);
+    }
+    for member in &class.members {
+        match member {
+            ClassMember::Field(decl) => visitor.visit_var_decl(decl),
+            ClassMember::Method(method) => visitor.visit_function(method),
+            ClassMember::IndexOperator(operator) => visitor.visit_index_operator(operator),
+        }
+    }
+}
+
+/// Visits the receiver, index parameters, return type and body of `operator`.
+pub fn walk_index_operator<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    operator: &'ast IndexOperatorDecl,
+) {
+    visitor.visit_param(&operator.receiver);
+    for param in &operator.index_params {
+        visitor.visit_param(param);
+    }
+    if let Some(return_type) = &operator.return_type {
+        visitor.visit_type_name(return_type);
+    }
+    visitor.visit_block(&operator.body);
+}
+
+/// Visits the explicit discriminants of `choice`.
+pub fn walk_choice<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, choice: &'ast ChoiceDecl) {
+    for value in choice
+        .variants
+        .iter()
+        .filter_map(|variant| variant.value.as_ref())
+    {
+        visitor.visit_expr(value);
+    }
+}
+
+/// Visits the type and initializer of `decl`.
+pub fn walk_var_decl<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, decl: &'ast VarDecl) {
+    visitor.visit_type_name(&decl.ty);
+    if let Some(init) = &decl.init {
+        visitor.visit_expr(init);
+    }
+}
+
+/// Visits the type and initializer of `decl`.
+pub fn walk_let_decl<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, decl: &'ast LetDecl) {
+    visitor.visit_type_name(&decl.ty);
+    visitor.visit_expr(&decl.init);
+}
+
+/// Visits the type and default value of `param`.
+pub fn walk_param<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, param: &'ast Param) {
+    match param {
+        Param::Regular { ty, default, .. } => {
+            visitor.visit_type_name(ty);
+            if let Some(default) = default {
+                visitor.visit_expr(default);
+            }
+        }
+        Param::Pattern { pattern, ty, .. } => {
+            visitor.visit_pattern(pattern);
+            visitor.visit_type_name(ty);
+        }
+        Param::Variadic { ty, .. } => {
+            if let Some(ty) = ty {
+                visitor.visit_type_name(ty);
+            }
+        }
+    }
+}
+
+/// Visits the statements of `block`.
+pub fn walk_block<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, block: &'ast Block) {
+    for stmt in &block.stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+/// Visits the expressions, types and blocks directly inside `stmt`.
+pub fn walk_stmt<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, stmt: &'ast Stmt) {
+    match stmt {
+        Stmt::Var(decl) => visitor.visit_var_decl(decl),
+        Stmt::Let(decl) => visitor.visit_let_decl(decl),
+        Stmt::Return(ret) => {
+            if let Some(value) = &ret.value {
+                visitor.visit_expr(value);
+            }
+        }
+        Stmt::Throw(throw) => visitor.visit_expr(&throw.value),
+        Stmt::If(if_stmt) => {
+            for link in if_stmt.chain() {
+                match &link.condition {
+                    Condition::Expr(condition) => visitor.visit_expr(condition),
+                    Condition::Binding(binding) => {
+                        visitor.visit_type_name(&binding.ty);
+                        visitor.visit_expr(&binding.value);
+                    }
+                }
+                visitor.visit_block(&link.then_block);
+            }
+            if let Some(block) = if_stmt.final_else() {
+                visitor.visit_block(block);
+            }
+        }
+        Stmt::While(while_stmt) => {
+            visitor.visit_expr(&while_stmt.condition);
+            visitor.visit_block(&while_stmt.body);
+        }
+        Stmt::For(for_stmt) => {
+            visitor.visit_type_name(&for_stmt.ty);
+            visitor.visit_expr(&for_stmt.iterable);
+            visitor.visit_block(&for_stmt.body);
+        }
+        Stmt::CFor(for_stmt) => {
+            if let Some(init) = &for_stmt.init {
+                visitor.visit_stmt(init);
+            }
+            if let Some(condition) = &for_stmt.condition {
+                visitor.visit_expr(condition);
+            }
+            if let Some(update) = &for_stmt.update {
+                visitor.visit_stmt(update);
+            }
+            visitor.visit_block(&for_stmt.body);
+        }
+        Stmt::Match(match_stmt) => {
+            visitor.visit_expr(&match_stmt.scrutinee);
+            for arm in &match_stmt.arms {
+                visitor.visit_pattern(&arm.pattern);
+                if let Some(guard) = &arm.guard {
+                    visitor.visit_expr(guard);
+                }
+                visitor.visit_block(&arm.body);
+            }
+            if let Some(default) = &match_stmt.default {
+                visitor.visit_block(default);
+            }
+        }
+        Stmt::Assign(assign) => visitor.visit_expr(&assign.value),
+        Stmt::MultiAssign(assign) => visitor.visit_expr(&assign.value),
+        Stmt::Expr(stmt) => visitor.visit_expr(&stmt.expr),
+        Stmt::Fallthrough(_) | Stmt::Break(_) | Stmt::Continue(_) => {}
+    }
+}
+
+/// Visits the nested patterns and type annotations of `pattern`.
+pub fn walk_pattern<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, pattern: &'ast Pattern) {
+    match pattern {
+        Pattern::Binding { ty: Some(ty), .. } => visitor.visit_type_name(ty),
+        Pattern::Constructor { args: parts, .. }
+        | Pattern::Tuple {
+            elements: parts, ..
+        } => {
+            for part in parts {
+                visitor.visit_pattern(part);
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_) | Pattern::Binding { ty: None, .. } => {}
+    }
+}
+
+/// Visits the operands of `expr`.
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast Expr) {
+    match expr {
+        Expr::Call(call) => {
+            for arg in &call.args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::Binary(binary) => {
+            visitor.visit_expr(&binary.lhs);
+            visitor.visit_expr(&binary.rhs);
+        }
+        Expr::Paren(paren) => visitor.visit_expr(&paren.inner),
+        Expr::Await(await_expr) => visitor.visit_expr(&await_expr.operand),
+        Expr::Try(try_expr) => visitor.visit_expr(&try_expr.operand),
+        Expr::Member(member) => visitor.visit_expr(&member.base),
+        Expr::MethodCall(call) => {
+            visitor.visit_expr(&call.receiver);
+            for arg in &call.args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::TupleIndex(tuple_index) => visitor.visit_expr(&tuple_index.base),
+        Expr::Index(index) => {
+            visitor.visit_expr(&index.base);
+            visitor.visit_expr(&index.index);
+        }
+        Expr::TypeValue(ty) => visitor.visit_type_name(ty),
+        Expr::Convert(convert) => {
+            visitor.visit_type_name(&convert.ty);
+            visitor.visit_expr(&convert.arg);
+        }
+        Expr::Conditional(conditional) => {
+            visitor.visit_expr(&conditional.condition);
+            visitor.visit_expr(&conditional.then_branch);
+            visitor.visit_expr(&conditional.else_branch);
+        }
+        Expr::Literal(_) | Expr::Ident(_) => {}
+    }
+}
+
+/// Visits the array element, array size and type arguments of `ty`.
+pub fn walk_type_name<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, ty: &'ast TypeName) {
+    if let Some(array) = &ty.array {
+        visitor.visit_type_name(&array.element);
+        if let Some(size) = &array.size {
+            visitor.visit_expr(size);
+        }
+    }
+    for arg in &ty.args {
+        visitor.visit_type_name(arg);
+    }
+    if let Some(tuple) = &ty.tuple {
+        for element in &tuple.elements {
+            visitor.visit_type_name(element);
+        }
+    }
+}
+
+/// Calls `f` on every outermost expression of `program`: initializers,
+/// conditions, statement operands, parameter defaults, discriminants and array
+/// sizes. Expressions nested inside another expression are left to `f`.
+pub(crate) fn for_each_root_expr_mut(program: &mut Program, f: &mut impl FnMut(&mut Expr)) {
+    fn type_exprs(ty: &mut TypeName, f: &mut impl FnMut(&mut Expr)) {
+        if let Some(array) = &mut ty.array {
+            type_exprs(&mut array.element, f);
+            if let Some(size) = &mut array.size {
+                f(size);
+            }
+        }
+        if let Some(tuple) = &mut ty.tuple {
+            for element in &mut tuple.elements {
+                type_exprs(element, f);
+            }
+        }
+        for arg in &mut ty.args {
+            type_exprs(arg, f);
+        }
+    }
+
+    fn param_exprs<'a>(
+        params: impl IntoIterator<Item = &'a mut Param>,
+        f: &mut impl FnMut(&mut Expr),
+    ) {
+        for param in params {
+            match param {
+                Param::Regular { ty, default, .. } => {
+                    type_exprs(ty, f);
+                    if let Some(default) = default {
+                        f(default);
+                    }
+                }
+                Param::Variadic { ty, .. } => {
+                    if let Some(ty) = ty {
+                        type_exprs(ty, f);
+                    }
+                }
+                Param::Pattern { ty, .. } => type_exprs(ty, f),
+            }
+        }
+    }
+
+    fn var_exprs(decl: &mut VarDecl, f: &mut impl FnMut(&mut Expr)) {
+        type_exprs(&mut decl.ty, f);
+        if let Some(init) = &mut decl.init {
+            f(init);
+        }
+    }
+
+    fn function_exprs(function: &mut FunctionDecl, f: &mut impl FnMut(&mut Expr)) {
+        param_exprs(&mut function.params, f);
+        if let Some(return_type) = &mut function.return_type {
+            type_exprs(return_type, f);
+        }
+        block_exprs(&mut function.body, f);
+    }
+
+    fn block_exprs(block: &mut Block, f: &mut impl FnMut(&mut Expr)) {
+        for stmt in &mut block.stmts {
+            stmt_exprs(stmt, f);
+        }
+    }
+
+    fn stmt_exprs(stmt: &mut Stmt, f: &mut impl FnMut(&mut Expr)) {
+        match stmt {
+            Stmt::Var(decl) => var_exprs(decl, f),
+            Stmt::Let(decl) => {
+                type_exprs(&mut decl.ty, f);
+                f(&mut decl.init);
+            }
+            Stmt::Return(ret) => {
+                if let Some(value) = &mut ret.value {
+                    f(value);
+                }
+            }
+            Stmt::Throw(throw) => f(&mut throw.value),
+            Stmt::If(if_stmt) => if_exprs(if_stmt, f),
+            Stmt::While(while_stmt) => {
+                f(&mut while_stmt.condition);
+                block_exprs(&mut while_stmt.body, f);
+            }
+            Stmt::For(for_stmt) => {
+                type_exprs(&mut for_stmt.ty, f);
+                f(&mut for_stmt.iterable);
+                block_exprs(&mut for_stmt.body, f);
+            }
+            Stmt::CFor(for_stmt) => {
+                if let Some(init) = &mut for_stmt.init {
+                    stmt_exprs(init, f);
+                }
+                if let Some(condition) = &mut for_stmt.condition {
+                    f(condition);
+                }
+                if let Some(update) = &mut for_stmt.update {
+                    stmt_exprs(update, f);
+                }
+                block_exprs(&mut for_stmt.body, f);
+            }
+            Stmt::Match(match_stmt) => {
+                f(&mut match_stmt.scrutinee);
+                for arm in &mut match_stmt.arms {
+                    if let Some(guard) = &mut arm.guard {
+                        f(guard);
+                    }
+                    block_exprs(&mut arm.body, f);
+                }
+                if let Some(default) = &mut match_stmt.default {
+                    block_exprs(default, f);
+                }
+            }
+            Stmt::Assign(assign) => f(&mut assign.value),
+            Stmt::MultiAssign(assign) => f(&mut assign.value),
+            Stmt::Expr(stmt) => f(&mut stmt.expr),
+            Stmt::Fallthrough(_) | Stmt::Break(_) | Stmt::Continue(_) => {}
+        }
+    }
+
+    fn if_exprs(if_stmt: &mut IfStmt, f: &mut impl FnMut(&mut Expr)) {
+        match &mut if_stmt.condition {
+            Condition::Expr(condition) => f(condition),
+            Condition::Binding(binding) => {
+                type_exprs(&mut binding.ty, f);
+                f(&mut binding.value);
+            }
+        }
+        block_exprs(&mut if_stmt.then_block, f);
+        match &mut if_stmt.else_branch {
+            Some(ElseBranch::Block(else_block)) => block_exprs(else_block, f),
+            Some(ElseBranch::If(next)) => if_exprs(next, f),
+            None => {}
+        }
+    }
+
+    for item in &mut program.items {
+        match item {
+            Item::Function(decl) => function_exprs(decl, f),
+            Item::Class(class) => {
+                for param in class
+                    .deduced_params
+                    .iter_mut()
+                    .chain(&mut class.generic_params)
+                {
+                    type_exprs(&mut param.ty, f);
+                }
+                if let Some(extends) = &mut class.extends {
+                    type_exprs(extends, f);
+                }
+                for constraint in &mut class.constraints {
+                    type_exprs(&mut constraint.bound, f);
+                }
+                for member in &mut class.members {
+                    match member {
+                        ClassMember::Field(field) => var_exprs(field, f),
+                        ClassMember::Method(method) => function_exprs(method, f),
+                        ClassMember::IndexOperator(operator) => {
+                            param_exprs(
+                                std::iter::once(&mut operator.receiver)
+                                    .chain(&mut operator.index_params),
+                                f,
+                            );
+                            if let Some(return_type) = &mut operator.return_type {
+                                type_exprs(return_type, f);
+                            }
+                            block_exprs(&mut operator.body, f);
+                        }
+                    }
+                }
+            }
+            Item::Choice(choice) => {
+                for value in choice.variants.iter_mut().filter_map(|v| v.value.as_mut()) {
+                    f(value);
+                }
+            }
+            Item::Var(decl) => var_exprs(decl, f),
+            Item::Let(decl) => {
+                type_exprs(&mut decl.ty, f);
+                f(&mut decl.init);
+            }
+        }
+    }
+    for top in &mut program.top_level {
+        if let TopItem::Stmt(stmt) = top {
+            stmt_exprs(stmt, f);
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Program {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        if pair.as_rule() != Rule::script {
+            expect_rule(&pair, Rule::program);
+        }
+        let span = pair.as_span().into();
+        let mut package = None;
+        let mut imports = Vec::new();
+        let mut items = Vec::new();
+        let mut top_level = Vec::new();
+        let mut pragmas = Vec::new();
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::pragma => pragmas.push(part.into()),
+                Rule::package_decl => package = Some(part.into()),
+                Rule::import_decl => imports.push(part.into()),
+                Rule::statement => top_level.push(TopItem::Stmt(Box::new(part.into()))),
+                Rule::EOI => {}
+                _ => {
+                    top_level.push(TopItem::Item(items.len()));
+                    items.push(part.into());
+                }
+            }
+        }
+        Program {
+            package,
+            imports,
+            items,
+            top_level,
+            pragmas,
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for PackageDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::package_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let name = inner.next().expect("package name").into();
+        let mut library = None;
+        let mut kind = PackageKind::Api;
+        for part in inner {
+            match part.as_rule() {
+                Rule::library_name => library = Some(library_name(part)),
+                Rule::package_kind if part.as_str() == "impl" => kind = PackageKind::Impl,
+                Rule::package_kind => kind = PackageKind::Api,
+                other => unreachable!("unexpected rule in package_decl: {other:?}"),
+            }
+        }
+        PackageDecl {
+            name,
+            library,
+            kind,
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ImportDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::import_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        ImportDecl {
+            name: inner.next().expect("imported package").into(),
+            library: inner.next().map(library_name),
+            span,
+        }
+    }
+}
+
+/// The contents of the string in a `library "Name"` clause.
+fn library_name(pair: Pair<'_, Rule>) -> String {
+    let quoted = pair.into_inner().next().expect("library string").as_str();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+impl From<Pair<'_, Rule>> for Pragma {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::pragma);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let name = inner.next().expect("pragma name").into();
+        let args = inner
+            .next()
+            .map(|args| {
+                args.into_inner()
+                    .map(|arg| arg.as_str().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Pragma { name, args, span }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Item {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        match pair.as_rule() {
+            Rule::function_decl => Item::Function(pair.into()),
+            Rule::class_decl => Item::Class(pair.into()),
+            Rule::choice_decl => Item::Choice(pair.into()),
+            Rule::var_decl => Item::Var(pair.into()),
+            Rule::let_decl => Item::Let(pair.into()),
+            other => unreachable!("not a top-level declaration: {other:?}"),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ClassDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::class_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner().peekable();
+        let modifier = inner
+            .next_if(|part| part.as_rule() == Rule::class_modifier)
+            .map(|part| match part.as_str() {
+                "final" => ClassModifier::Final,
+                _ => ClassModifier::Sealed,
+            });
+        let name = Ident::from(inner.next().expect("class name"));
+
+        let mut deduced_params = Vec::new();
+        let mut generic_params = Vec::new();
+        let mut extends = None;
+        let mut constraints = Vec::new();
+        let mut members = Vec::new();
+        for part in inner {
+            match part.as_rule() {
+                Rule::deduced_params => {
+                    deduced_params = part.into_inner().map(GenericParam::from).collect()
+                }
+                Rule::generic_params => {
+                    generic_params = part.into_inner().map(GenericParam::from).collect()
+                }
+                Rule::where_clause => {
+                    constraints = part.into_inner().map(WhereConstraint::from).collect()
+                }
+                Rule::type_name => extends = Some(part.into()),
+                Rule::var_decl | Rule::function_decl | Rule::index_operator_decl => {
+                    members.push(part.into())
+                }
+                other => unreachable!("unexpected rule in class_decl: {other:?}"),
+            }
+        }
+
+        ClassDecl {
+            modifier,
+            name,
+            deduced_params,
+            generic_params,
+            extends,
+            constraints,
+            members,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ClassMember {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        match pair.as_rule() {
+            Rule::var_decl => ClassMember::Field(pair.into()),
+            Rule::function_decl => ClassMember::Method(pair.into()),
+            Rule::index_operator_decl => ClassMember::IndexOperator(pair.into()),
+            other => unreachable!("not a class member: {other:?}"),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ChoiceDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::choice_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let name = Ident::from(inner.next().expect("choice name"));
+        let variants = inner
+            .map(|variant| {
+                expect_rule(&variant, Rule::choice_variant);
+                let start = variant.as_span().start();
+                let mut parts = variant.into_inner();
+                let name = Ident::from(parts.next().expect("variant name"));
+                let value = parts.next().map(Expr::from);
+                // The pair of a rule ending in an expression also covers the
+                // whitespace after it, so the span ends where its last part does.
+                let end = value
+                    .as_ref()
+                    .map_or(name.span.end, |value| value.span().end);
+                ChoiceVariant {
+                    name,
+                    value,
+                    span: Span::new(start, end),
+                }
+            })
+            .collect();
+        ChoiceDecl {
+            name,
+            variants,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for GenericParam {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        if pair.as_rule() == Rule::identifier {
+            let name = Ident::from(pair);
+            let ty = TypeName::bare("type", Span::new(name.span.end, name.span.end));
+            let span = name.span;
+            return GenericParam { name, ty, span };
+        }
+        expect_rule(&pair, Rule::generic_param);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        GenericParam {
+            name: inner.next().expect("generic parameter name").into(),
+            ty: inner.next().expect("generic parameter type").into(),
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for WhereConstraint {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::where_constraint);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        WhereConstraint {
+            param: inner.next().expect("constrained parameter").into(),
+            bound: inner.next().expect("constraint bound").into(),
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for FunctionDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::function_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner().peekable();
+        let is_async = inner
+            .next_if(|part| part.as_rule() == Rule::async_marker)
+            .is_some();
+        let name = Ident::from(inner.next().expect("function name"));
+
+        let mut params = Vec::new();
+        let mut throws = false;
+        let mut return_type = None;
+        let mut body = None;
+        for part in inner {
+            match part.as_rule() {
+                Rule::parameter_list => params = part.into_inner().map(Param::from).collect(),
+                Rule::throws_marker => throws = true,
+                Rule::type_name => return_type = Some(part.into()),
+                Rule::block => body = Some(part.into()),
+                other => unreachable!("unexpected rule in function_decl: {other:?}"),
+            }
+        }
+
+        FunctionDecl {
+            name,
+            params,
+            is_async,
+            throws,
+            return_type,
+            body: body.expect("function body"),
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for IndexOperatorDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::index_operator_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let receiver = Param::from(inner.next().expect("index operator receiver"));
+
+        let mut index_params = Vec::new();
+        let mut return_type = None;
+        let mut body = None;
+        for part in inner {
+            match part.as_rule() {
+                Rule::parameter_list => index_params = part.into_inner().map(Param::from).collect(),
+                Rule::type_name => return_type = Some(part.into()),
+                Rule::block => body = Some(part.into()),
+                other => unreachable!("unexpected rule in index_operator_decl: {other:?}"),
+            }
+        }
+
+        IndexOperatorDecl {
+            receiver,
+            index_params,
+            return_type,
+            body: body.expect("index operator body"),
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Param {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let span = pair.as_span().into();
+        match pair.as_rule() {
+            Rule::parameter => {
+                let mut inner = pair.into_inner().peekable();
+                let direction = inner
+                    .next_if(|part| part.as_rule() == Rule::param_direction)
+                    .map_or(ParamDirection::In, |part| match part.as_str() {
+                        "out" => ParamDirection::Out,
+                        _ => ParamDirection::InOut,
+                    });
+                let name = Ident::from(inner.next().expect("parameter name"));
+                let mut comptime = false;
+                let mut ty = None;
+                let mut default = None;
+                for part in inner {
+                    match part.as_rule() {
+                        Rule::comptime_marker => comptime = true,
+                        Rule::type_name => ty = Some(part.into()),
+                        Rule::expression => default = Some(part.into()),
+                        other => unreachable!("unexpected rule in parameter: {other:?}"),
+                    }
+                }
+                Param::Regular {
+                    direction,
+                    name,
+                    ty: ty.expect("parameter type"),
+                    comptime,
+                    span: default.as_ref().map_or(span, |default: &Expr| {
+                        Span::new(span.start, default.span().end)
+                    }),
+                    default,
+                }
+            }
+            Rule::variadic_param => {
+                let mut name = None;
+                let mut ty = None;
+                for part in pair.into_inner() {
+                    match part.as_rule() {
+                        Rule::identifier => name = Some(part.into()),
+                        Rule::type_name => ty = Some(part.into()),
+                        other => unreachable!("unexpected rule in variadic_param: {other:?}"),
+                    }
+                }
+                Param::Variadic { name, ty, span }
+            }
+            Rule::pattern_param => {
+                let mut inner = pair.into_inner();
+                Param::Pattern {
+                    pattern: Box::new(inner.next().expect("parameter pattern").into()),
+                    ty: inner.next().expect("parameter type").into(),
+                    span,
+                }
+            }
+            other => unreachable!("not a parameter: {other:?}"),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for VarDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::var_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let name = Ident::from(inner.next().expect("variable name"));
+        let mut inner = inner.peekable();
+        let ty = match inner.next_if(|pair| pair.as_rule() == Rule::type_name) {
+            Some(ty) => TypeName::from(ty),
+            None => omitted_type(&name),
+        };
+        let init = inner.next().map(Expr::from);
+        VarDecl {
+            name,
+            ty,
+            init,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for LetDecl {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::let_decl);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner().peekable();
+        let name = Ident::from(inner.next().expect("binding name"));
+        let ty = match inner.next_if(|pair| pair.as_rule() == Rule::type_name) {
+            Some(ty) => TypeName::from(ty),
+            None => omitted_type(&name),
+        };
+        LetDecl {
+            name,
+            ty,
+            init: inner.next().expect("binding value").into(),
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Block {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::block);
+        let span = pair.as_span().into();
+        let stmts = pair.into_inner().map(Stmt::from).collect();
+        Block { stmts, span }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Stmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let span = pair.as_span().into();
+        match pair.as_rule() {
+            Rule::statement => Stmt::from(pair.into_inner().next().expect("statement body")),
+            Rule::var_decl => Stmt::Var(pair.into()),
+            Rule::let_decl => Stmt::Let(pair.into()),
+            Rule::return_stmt => Stmt::Return(ReturnStmt {
+                value: pair.into_inner().next().map(Expr::from),
+                span,
+                diagnostics: Vec::new(),
+            }),
+            Rule::throw_stmt => Stmt::Throw(ThrowStmt {
+                value: Expr::from(pair.into_inner().next().expect("thrown value")),
+                span,
+                diagnostics: Vec::new(),
+            }),
+            Rule::fallthrough_stmt => Stmt::Fallthrough(FallthroughStmt {
+                span,
+                diagnostics: Vec::new(),
+            }),
+            Rule::break_stmt => Stmt::Break(BreakStmt {
+                span,
+                diagnostics: Vec::new(),
+            }),
+            Rule::continue_stmt => Stmt::Continue(ContinueStmt {
+                span,
+                diagnostics: Vec::new(),
+            }),
+            Rule::if_statement => Stmt::If(pair.into()),
+            Rule::while_statement => Stmt::While(pair.into()),
+            Rule::for_statement => Stmt::For(pair.into()),
+            Rule::for_c_statement => Stmt::CFor(pair.into()),
+            Rule::match_statement => Stmt::Match(pair.into()),
+            Rule::assignment => {
+                let mut inner = pair.into_inner();
+                Stmt::Assign(AssignStmt {
+                    target: inner.next().expect("assignment target").into(),
+                    op: AssignOp::from_rule(inner.next().expect("assignment operator").as_rule()),
+                    value: inner.next().expect("assigned value").into(),
+                    span,
+                    diagnostics: Vec::new(),
+                })
+            }
+            Rule::multi_assignment => {
+                let mut targets = Vec::new();
+                let mut value = None;
+                for part in pair.into_inner() {
+                    match part.as_rule() {
+                        Rule::identifier => targets.push(Ident::from(part)),
+                        _ => value = Some(Expr::from(part)),
+                    }
+                }
+                Stmt::MultiAssign(MultiAssignStmt {
+                    targets,
+                    value: value.expect("assigned value"),
+                    span,
+                    diagnostics: Vec::new(),
+                })
+            }
+            Rule::expression_stmt | Rule::tail_expr => {
+                let has_semicolon = pair.as_rule() == Rule::expression_stmt;
+                let expr = Expr::from(pair.into_inner().next().expect("expression"));
+                Stmt::Expr(ExprStmt {
+                    // Without the semicolon, the statement is just its expression.
+                    span: if has_semicolon { span } else { expr.span() },
+                    expr,
+                    has_semicolon,
+                    diagnostics: Vec::new(),
+                })
+            }
+            other => unreachable!("not a statement: {other:?}"),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for IfStmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::if_statement);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let condition = Condition::from(inner.next().expect("if condition"));
+        let then_block = Block::from(inner.next().expect("then block"));
+        let else_branch = inner.next().map(|pair| match pair.as_rule() {
+            Rule::if_statement => ElseBranch::If(Box::new(pair.into())),
+            _ => ElseBranch::Block(pair.into()),
+        });
+        IfStmt {
+            condition,
+            then_block,
+            else_branch,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for WhileStmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::while_statement);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let condition = Expr::from(inner.next().expect("while condition"));
+        let body = Block::from(inner.next().expect("while body"));
+        WhileStmt {
+            condition,
+            body,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ForStmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::for_statement);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        ForStmt {
+            name: inner.next().expect("loop variable").into(),
+            ty: inner.next().expect("loop variable type").into(),
+            iterable: inner.next().expect("iterable").into(),
+            body: inner.next().expect("for body").into(),
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for CForStmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::for_c_statement);
+        let span = pair.as_span().into();
+        let mut init = None;
+        let mut condition = None;
+        let mut update = None;
+        let mut body = None;
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::var_decl | Rule::assignment | Rule::expression_stmt => {
+                    init = Some(Box::new(part.into()))
+                }
+                Rule::expression => condition = Some(part.into()),
+                Rule::for_update => update = Some(Box::new(for_update(part))),
+                Rule::block => body = Some(part.into()),
+                other => unreachable!("unexpected rule in for_c_statement: {other:?}"),
+            }
+        }
+        CForStmt {
+            init,
+            condition,
+            update,
+            body: body.expect("for body"),
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+/// The update clause of a C-style `for`, which has no semicolon.
+fn for_update(pair: Pair<'_, Rule>) -> Stmt {
+    expect_rule(&pair, Rule::for_update);
+    let start = pair.as_span().start();
+    let mut inner = pair.into_inner();
+    let first = inner.next().expect("update clause");
+    match inner.next() {
+        Some(op) => {
+            let value = Expr::from(inner.next().expect("assigned value"));
+            Stmt::Assign(AssignStmt {
+                target: first.into(),
+                op: AssignOp::from_rule(op.as_rule()),
+                span: Span::new(start, value.span().end),
+                value,
+                diagnostics: Vec::new(),
+            })
+        }
+        None => {
+            let expr = Expr::from(first);
+            Stmt::Expr(ExprStmt {
+                span: expr.span(),
+                expr,
+                has_semicolon: false,
+                diagnostics: Vec::new(),
+            })
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for MatchStmt {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::match_statement);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let scrutinee = Expr::from(inner.next().expect("match scrutinee"));
+        let mut arms = Vec::new();
+        let mut default = None;
+        for part in inner {
+            match part.as_rule() {
+                Rule::match_arm => arms.push(part.into()),
+                Rule::default_arm => {
+                    default = Some(Block::from(part.into_inner().next().expect("default body")))
+                }
+                other => unreachable!("unexpected rule in match_statement: {other:?}"),
+            }
+        }
+        MatchStmt {
+            scrutinee,
+            arms,
+            default,
+            span,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for MatchArm {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::match_arm);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        let pattern = Pattern::from(inner.next().expect("arm pattern"));
+        let mut guard = None;
+        let mut body = None;
+        for part in inner {
+            match part.as_rule() {
+                Rule::match_guard => {
+                    guard = Some(Expr::from(part.into_inner().next().expect("guard")))
+                }
+                Rule::block => body = Some(part.into()),
+                other => unreachable!("unexpected rule in match_arm: {other:?}"),
+            }
+        }
+        MatchArm {
+            pattern,
+            guard,
+            body: body.expect("arm body"),
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Pattern {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let pair = if pair.as_rule() == Rule::pattern {
+            pair.into_inner().next().expect("pattern body")
+        } else {
+            pair
+        };
+        let span = pair.as_span().into();
+        match pair.as_rule() {
+            Rule::wildcard_pattern => Pattern::Wildcard(span),
+            Rule::literal => Pattern::Literal(pair.into()),
+            Rule::binding_pattern => {
+                let mut inner = pair.into_inner();
+                Pattern::Binding {
+                    name: inner.next().expect("binding name").into(),
+                    ty: inner.next().map(TypeName::from),
+                    span,
+                }
+            }
+            Rule::constructor_pattern => {
+                let mut inner = pair.into_inner();
+                Pattern::Constructor {
+                    name: inner.next().expect("constructor name").into(),
+                    args: inner.map(Pattern::from).collect(),
+                    span,
+                }
+            }
+            Rule::tuple_pattern => Pattern::Tuple {
+                elements: pair.into_inner().map(Pattern::from).collect(),
+                span,
+            },
+            other => unreachable!("not a pattern: {other:?}"),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Condition {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::condition);
+        let inner = pair.into_inner().next().expect("condition body");
+        match inner.as_rule() {
+            Rule::binding_condition => {
+                let start = inner.as_span().start();
+                let mut parts = inner.into_inner();
+                let name = Ident::from(parts.next().expect("binding name"));
+                let ty = TypeName::from(parts.next().expect("binding type"));
+                let value = Expr::from(parts.next().expect("binding value"));
+                Condition::Binding(Binding {
+                    name,
+                    ty,
+                    span: Span::new(start, value.span().end),
+                    value,
+                })
+            }
+            _ => Condition::Expr(inner.into()),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Expr {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let span: Span = pair.as_span().into();
+        match pair.as_rule() {
+            Rule::expression => build_expr_tree(pair.into_inner()),
+            Rule::operand => {
+                let inner = pair.into_inner().next().expect("operand body");
+                if inner.as_rule() == Rule::expression {
+                    // `( expression )`: the outer pair's span includes the parentheses.
+                    Expr::Paren(ParenExpr {
+                        inner: Box::new(inner.into()),
+                        span,
+                    })
+                } else {
+                    inner.into()
+                }
+            }
+            Rule::conditional_expr => {
+                let mut inner = pair.into_inner().map(|part| Box::new(Expr::from(part)));
+                let mut next = |what| inner.next().expect(what);
+                let (condition, then_branch, else_branch) =
+                    (next("condition"), next("then branch"), next("else branch"));
+                Expr::Conditional(ConditionalExpr {
+                    span: Span::new(span.start, else_branch.span().end),
+                    condition,
+                    then_branch,
+                    else_branch,
+                })
+            }
+            Rule::primary => {
+                let mut inner = pair.into_inner().peekable();
+                let awaited = inner.next_if(|part| part.as_rule() == Rule::await_op);
+                let mut operand = Expr::from(inner.next().expect("primary operand"));
+                let start = operand.span().start;
+                // `a.b[0]` is `(a.b)[0]`: each access wraps the one before it.
+                while let Some(suffix) = inner.next_if(|part| part.as_rule() != Rule::try_op) {
+                    operand = postfix(operand, start, suffix);
+                }
+                if awaited.is_some() {
+                    operand = Expr::Await(AwaitExpr {
+                        span: Span::new(span.start, operand.span().end),
+                        operand: Box::new(operand),
+                    });
+                }
+                // `await f()?` is `(await f())?`: each `?` wraps everything before it.
+                inner.fold(operand, |operand, op| {
+                    let span = Span::new(span.start, op.as_span().end());
+                    Expr::Try(TryExpr {
+                        operand: Box::new(operand),
+                        span,
+                    })
+                })
+            }
+            Rule::function_call => {
+                let mut inner = pair.into_inner();
+                let callee = Ident::from(inner.next().expect("callee"));
+                let args = inner
+                    .next()
+                    .map(|list| list.into_inner().map(Expr::from).collect())
+                    .unwrap_or_default();
+                Expr::Call(CallExpr { callee, args, span })
+            }
+            Rule::type_value => {
+                Expr::TypeValue(primitive_type(pair.into_inner().next().expect("type")))
+            }
+            Rule::conversion => {
+                let mut inner = pair.into_inner();
+                let ty = primitive_type(inner.next().expect("conversion type"));
+                let arg = Box::new(Expr::from(inner.next().expect("conversion argument")));
+                Expr::Convert(ConvertExpr { ty, arg, span })
+            }
+            Rule::literal => Expr::Literal(pair.into()),
+            Rule::identifier => Expr::Ident(pair.into()),
+            other => unreachable!("not an expression: {other:?}"),
+        }
+    }
+}
+
+/// Wraps `base` in the member access, method call, tuple index or index
+/// written by `suffix`. The result spans from `start`, where `base` begins,
+/// to the end of the suffix.
+fn postfix(base: Expr, start: usize, suffix: Pair<'_, Rule>) -> Expr {
+    let span = Span::new(start, suffix.as_span().end());
+    let base = Box::new(base);
+    match suffix.as_rule() {
+        Rule::tuple_index => Expr::TupleIndex(TupleIndexExpr {
+            base,
+            index: suffix.as_str().parse().expect("tuple index fits in u32"),
+            span,
+        }),
+        Rule::index_suffix => Expr::Index(IndexExpr {
+            base,
+            index: Box::new(suffix.into_inner().next().expect("index").into()),
+            span,
+        }),
+        Rule::function_call => {
+            let mut call = suffix.into_inner();
+            let method = Ident::from(call.next().expect("method name"));
+            let args = call
+                .next()
+                .map(|list| list.into_inner().map(Expr::from).collect())
+                .unwrap_or_default();
+            Expr::MethodCall(MethodCallExpr {
+                receiver: base,
+                method,
+                args,
+                span,
+            })
+        }
+        _ => Expr::Member(MemberExpr {
+            base,
+            member: suffix.into(),
+            span,
+        }),
+    }
+}
+
+/// A bare built-in type, such as the `i64` of `i64(x)`.
+fn primitive_type(pair: Pair<'_, Rule>) -> TypeName {
+    expect_rule(&pair, Rule::primitive_type);
+    TypeName::bare(pair.as_str(), pair.as_span().into())
+}
+
+/// The `auto` type of a declaration written without one, located right after
+/// the declared name.
+fn omitted_type(name: &Ident) -> TypeName {
+    TypeName::bare("auto", Span::new(name.span.end, name.span.end))
+}
+
+impl From<Pair<'_, Rule>> for Literal {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::literal);
+        let inner = pair.into_inner().next().expect("literal body");
+        let kind = match inner.as_rule() {
+            Rule::integer_literal => LiteralKind::Integer,
+            Rule::float_literal => LiteralKind::Float,
+            Rule::bool_literal => LiteralKind::Bool,
+            Rule::string_literal => LiteralKind::String,
+            Rule::char_literal => LiteralKind::Char,
+            other => unreachable!("not a literal: {other:?}"),
+        };
+        Literal {
+            kind,
+            text: inner.as_str().to_string(),
+            span: inner.as_span().into(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for Ident {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::identifier);
+        Ident {
+            name: pair.as_str().to_string(),
+            span: pair.as_span().into(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for TypeName {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::type_name);
+        let span = pair.as_span().into();
+        let mut name = String::new();
+        let mut array = None;
+        let mut args = Vec::new();
+        let mut tuple = None;
+        let mut qualifiers = Qualifiers::default();
+        let mut pointers = Vec::new();
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::type_qualifier => qualifiers.add(part.as_str()),
+                Rule::base_type => {
+                    let mut inner = part.into_inner();
+                    let base = inner.next().expect("base type body");
+                    if let Some(type_args) = inner.next() {
+                        args = type_args.into_inner().map(TypeName::from).collect();
+                    }
+                    if base.as_rule() == Rule::array_type {
+                        let size_text = base.clone().into_inner().nth(1).map(|size| size.as_str());
+                        let array_type = ArrayType::from(base);
+                        let element = array_type.element.canonical();
+                        name = match size_text {
+                            Some(size) => format!("[{element}; {size}]"),
+                            None => format!("[{element}]"),
+                        };
+                        array = Some(Box::new(array_type));
+                    } else if base.as_rule() == Rule::tuple_type {
+                        let tuple_type = TupleType::from(base);
+                        let elements: Vec<String> = tuple_type
+                            .elements
+                            .iter()
+                            .map(TypeName::canonical)
+                            .collect();
+                        name = format!("({})", elements.join(", "));
+                        tuple = Some(Box::new(tuple_type));
+                    } else {
+                        name = base.as_str().to_string();
+                    }
+                }
+                Rule::pointer_suffix => {
+                    let mut pointer = Qualifiers::default();
+                    for qualifier in part.into_inner() {
+                        pointer.add(qualifier.as_str());
+                    }
+                    pointers.push(pointer);
+                }
+                other => unreachable!("unexpected rule in type_name: {other:?}"),
+            }
+        }
+        TypeName {
+            name,
+            array,
+            args,
+            tuple,
+            qualifiers,
+            pointers,
+            span,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for TupleType {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::tuple_type);
+        TupleType {
+            span: pair.as_span().into(),
+            elements: pair.into_inner().map(TypeName::from).collect(),
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ArrayType {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        expect_rule(&pair, Rule::array_type);
+        let span = pair.as_span().into();
+        let mut inner = pair.into_inner();
+        ArrayType {
+            element: inner.next().expect("array element type").into(),
+            size: inner.next().map(Expr::from),
+            span,
+        }
+    }
+}
+
+fn expect_rule(pair: &Pair<'_, Rule>, rule: Rule) {
+    assert_eq!(
+        pair.as_rule(),
+        rule,
+        "expected a `{rule:?}` pair, found `{:?}`",
+        pair.as_rule()
+    );
+}
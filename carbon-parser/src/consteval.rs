@@ -0,0 +1,155 @@
+//! Compile-time evaluation of constant expressions.
+//!
+//! These helpers fold expressions built only from literals and operators. They
+//! return `None` as soon as anything is not known at compile time (a variable,
+//! a call) or the arithmetic would fail (overflow, division by zero), so callers
+//! can treat `None` as "not a constant" without special cases.
+//!
+//! [`fold_constants`] applies them to every subexpression, rewriting the
+//! constant parts of an expression into literals.
+
+use crate::ast::{BinaryOp, Expr, Literal, LiteralKind};
+use crate::expr::for_each_child_mut;
+
+/// Evaluates an integer constant expression.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::consteval::eval_const_i64;
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::ast::Item;
+///
+/// let program = parse_to_ast("var x: i32 = (2 + 3) * 4;").unwrap();
+/// let Item::Var(decl) = &program.items[0] else { unreachable!() };
+/// assert_eq!(eval_const_i64(decl.init.as_ref().unwrap()), Some(20));
+/// ```
+pub fn eval_const_i64(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(literal) if literal.kind == LiteralKind::Integer => {
+            integer_value(&literal.text)
+        }
+        Expr::Paren(paren) => eval_const_i64(&paren.inner),
+        Expr::Binary(binary) => {
+            let lhs = eval_const_i64(&binary.lhs)?;
+            let rhs = eval_const_i64(&binary.rhs)?;
+            match binary.op {
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Sub => lhs.checked_sub(rhs),
+                BinaryOp::Mul => lhs.checked_mul(rhs),
+                BinaryOp::Div => lhs.checked_div(rhs),
+                BinaryOp::Rem => lhs.checked_rem(rhs),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The value of an integer literal written in decimal, hexadecimal (`0x`) or
+/// binary (`0b`), with optional `_` digit separators.
+fn integer_value(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let digits = text.replace('_', "");
+    let magnitude = if let Some(hex) = digits.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i128::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    i64::try_from(if negative { -magnitude } else { magnitude }).ok()
+}
+
+/// Evaluates a boolean constant expression.
+///
+/// Boolean literals, `&&`, `||`, and comparisons between integer or boolean
+/// constants are supported.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::consteval::eval_const_bool;
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::ast::Item;
+///
+/// let program = parse_to_ast("var b: bool = 1 < 2 && true;").unwrap();
+/// let Item::Var(decl) = &program.items[0] else { unreachable!() };
+/// assert_eq!(eval_const_bool(decl.init.as_ref().unwrap()), Some(true));
+/// ```
+pub fn eval_const_bool(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Literal(literal) if literal.kind == LiteralKind::Bool => Some(literal.text == "true"),
+        Expr::Paren(paren) => eval_const_bool(&paren.inner),
+        Expr::Binary(binary) => match binary.op {
+            BinaryOp::And => Some(eval_const_bool(&binary.lhs)? && eval_const_bool(&binary.rhs)?),
+            BinaryOp::Or => Some(eval_const_bool(&binary.lhs)? || eval_const_bool(&binary.rhs)?),
+            BinaryOp::Eq | BinaryOp::Ne => {
+                let equal = match (eval_const_bool(&binary.lhs), eval_const_bool(&binary.rhs)) {
+                    (Some(lhs), Some(rhs)) => lhs == rhs,
+                    _ => eval_const_i64(&binary.lhs)? == eval_const_i64(&binary.rhs)?,
+                };
+                Some(equal == (binary.op == BinaryOp::Eq))
+            }
+            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge => {
+                let lhs = eval_const_i64(&binary.lhs)?;
+                let rhs = eval_const_i64(&binary.rhs)?;
+                Some(match binary.op {
+                    BinaryOp::Lt => lhs < rhs,
+                    BinaryOp::Gt => lhs > rhs,
+                    BinaryOp::Le => lhs <= rhs,
+                    _ => lhs >= rhs,
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replaces every constant subexpression of `expr` with the literal it
+/// evaluates to, so `x + (1 + 2)` becomes `x + 3`.
+///
+/// Integer arithmetic is folded with [`eval_const_i64`] and boolean logic
+/// with [`eval_const_bool`]. A subexpression that overflows or divides by zero
+/// is not a constant and is kept as written, as is anything involving a
+/// variable or a call. Folded literals take the span of the expression they
+/// replace.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::Expr;
+/// use carbon_parser::consteval::fold_constants;
+/// use carbon_parser::parse_expression_ast;
+///
+/// let folded = fold_constants(parse_expression_ast("2 + 3 * 4").unwrap());
+/// assert!(matches!(folded, Expr::Literal(literal) if literal.text == "14"));
+/// ```
+pub fn fold_constants(mut expr: Expr) -> Expr {
+    fold(&mut expr);
+    expr
+}
+
+fn fold(expr: &mut Expr) {
+    if matches!(expr, Expr::Literal(_)) {
+        return;
+    }
+    let folded = if let Some(value) = eval_const_i64(expr) {
+        (LiteralKind::Integer, value.to_string())
+    } else if let Some(value) = eval_const_bool(expr) {
+        (LiteralKind::Bool, value.to_string())
+    } else {
+        for_each_child_mut(expr, fold);
+        return;
+    };
+    let (kind, text) = folded;
+    *expr = Expr::Literal(Literal {
+        kind,
+        text,
+        span: expr.span(),
+    });
+}
@@ -0,0 +1,226 @@
+//! Plain-English outlines of parsed programs.
+//!
+//! [`describe_program`] summarizes each top-level declaration in one sentence,
+//! which is useful when teaching the language or when a quick overview of an
+//! unfamiliar file is needed. The output is meant for people to read; it is not
+//! stable enough to be parsed back.
+
+use crate::ast::{
+    Block, ClassDecl, ClassMember, ClassModifier, FunctionDecl, Item, Param, Program, Stmt,
+    TypeName, VarDecl,
+};
+use crate::format;
+
+/// Describes every top-level declaration of `program`, one per line.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{describe_program, parse_to_ast};
+///
+/// let program = parse_to_ast(
+///     "fn add(x: i32, y: i32) -> i32 { var sum: i32 = x + y; return sum; }",
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     describe_program(&program),
+///     "Function add takes x: i32, y: i32, returns i32; declares 1 local; returns an expression."
+/// );
+/// ```
+pub fn describe_program(program: &Program) -> String {
+    program
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::Function(function) => describe_function(function),
+            Item::Class(class) => describe_class(class),
+            Item::Choice(choice) => format!(
+                "Choice {} has {}.",
+                choice.name.name,
+                count(choice.variants.len(), "variant", "variants")
+            ),
+            Item::Var(decl) => describe_global(decl),
+            Item::Let(decl) => format!(
+                "Constant {} of type {}.",
+                decl.name.name,
+                decl.ty.canonical()
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_function(function: &FunctionDecl) -> String {
+    let mut header = format!(
+        "{} {}",
+        if function.is_async {
+            "Async function"
+        } else {
+            "Function"
+        },
+        function.name.name
+    );
+
+    if function.params.is_empty() {
+        header.push_str(" takes no parameters");
+    } else {
+        let params: Vec<String> = function.params.iter().map(describe_param).collect();
+        header.push_str(&format!(" takes {}", params.join(", ")));
+    }
+    if let Some(return_type) = &function.return_type {
+        header.push_str(&format!(", returns {}", return_type.canonical()));
+    }
+    if function.throws {
+        header.push_str(", may throw");
+    }
+
+    let mut sentence = vec![header];
+    sentence.extend(describe_block(&function.body));
+    sentence.join("; ") + "."
+}
+
+fn describe_param(param: &Param) -> String {
+    match param {
+        Param::Regular {
+            direction,
+            name,
+            ty,
+            comptime,
+            ..
+        } => {
+            let separator = if *comptime { ":!" } else { ":" };
+            let direction = direction
+                .keyword()
+                .map(|keyword| format!("{keyword} "))
+                .unwrap_or_default();
+            format!("{direction}{}{} {}", name.name, separator, ty.canonical())
+        }
+        Param::Pattern { pattern, ty, .. } => {
+            format!("{}: {}", format::pattern(pattern), ty.canonical())
+        }
+        Param::Variadic { name, ty, .. } => {
+            let name = name.as_ref().map_or("", |name| name.name.as_str());
+            let ty = ty.as_ref().map(TypeName::canonical).unwrap_or_default();
+            match name {
+                "" => format!("...{ty}"),
+                name => format!("{name}: ...{ty}"),
+            }
+        }
+    }
+}
+
+fn describe_class(class: &ClassDecl) -> String {
+    let mut header = match class.modifier {
+        Some(ClassModifier::Final) => format!("Final class {}", class.name.name),
+        Some(ClassModifier::Sealed) => format!("Sealed class {}", class.name.name),
+        None => format!("Class {}", class.name.name),
+    };
+    if let Some(base) = &class.extends {
+        header.push_str(&format!(" extends {},", base.canonical()));
+    }
+    let params: Vec<String> = class
+        .type_params()
+        .map(|param| format!("{}:! {}", param.name.name, param.ty.canonical()))
+        .collect();
+    if !params.is_empty() {
+        header.push_str(&format!(" is generic over {}", params.join(", ")));
+        header.push(',');
+    }
+    if !class.constraints.is_empty() {
+        let constraints: Vec<String> = class
+            .constraints
+            .iter()
+            .map(|constraint| {
+                format!(
+                    "{} impls {}",
+                    constraint.param.name,
+                    constraint.bound.canonical()
+                )
+            })
+            .collect();
+        header.push_str(&format!(" requires {},", constraints.join(", ")));
+    }
+
+    let fields = class
+        .members
+        .iter()
+        .filter(|member| matches!(member, ClassMember::Field(_)))
+        .count();
+    let methods = class.members.len() - fields;
+    format!(
+        "{header} has {}.",
+        [
+            count(fields, "field", "fields"),
+            count(methods, "method", "methods")
+        ]
+        .join(" and ")
+    )
+}
+
+fn count(n: usize, singular: &str, plural: &str) -> String {
+    format!("{n} {}", if n == 1 { singular } else { plural })
+}
+
+fn describe_global(decl: &VarDecl) -> String {
+    let init = if decl.init.is_some() {
+        "initialized"
+    } else {
+        "uninitialized"
+    };
+    format!(
+        "Variable {} of type {}, {}.",
+        decl.name.name,
+        decl.ty.canonical(),
+        init
+    )
+}
+
+/// One phrase per statement, with runs of local declarations merged.
+fn describe_block(block: &Block) -> Vec<String> {
+    if block.stmts.is_empty() {
+        return vec!["has an empty body".to_string()];
+    }
+
+    let mut phrases = Vec::new();
+    let mut locals = 0;
+    for stmt in &block.stmts {
+        if let Stmt::Var(_) | Stmt::Let(_) = stmt {
+            locals += 1;
+            continue;
+        }
+        if locals > 0 {
+            phrases.push(describe_locals(locals));
+            locals = 0;
+        }
+        phrases.push(describe_stmt(stmt).to_string());
+    }
+    if locals > 0 {
+        phrases.push(describe_locals(locals));
+    }
+    phrases
+}
+
+fn describe_locals(n: usize) -> String {
+    format!("declares {}", count(n, "local", "locals"))
+}
+
+fn describe_stmt(stmt: &Stmt) -> &'static str {
+    match stmt {
+        Stmt::Var(_) | Stmt::Let(_) => "declares a local",
+        Stmt::Return(ret) if ret.value.is_some() => "returns an expression",
+        Stmt::Return(_) => "returns",
+        Stmt::Throw(_) => "throws an error",
+        Stmt::Fallthrough(_) => "falls through to the next arm",
+        Stmt::Break(_) => "leaves the loop",
+        Stmt::Continue(_) => "continues with the next iteration",
+        Stmt::If(_) => "branches on a condition",
+        Stmt::While(_) => "loops while a condition holds",
+        Stmt::For(_) => "loops over a collection",
+        Stmt::CFor(_) => "loops with a counter",
+        Stmt::Match(_) => "matches a value against patterns",
+        Stmt::Assign(_) => "assigns a variable",
+        Stmt::MultiAssign(_) => "assigns several variables",
+        Stmt::Expr(stmt) if stmt.has_semicolon => "evaluates an expression",
+        Stmt::Expr(_) => "yields an expression",
+    }
+}
@@ -0,0 +1,65 @@
+//! Graphviz export of parse trees.
+//!
+//! [`to_dot`] draws the parse tree of a program as a [DOT] graph, which makes
+//! the shape of the grammar easy to see when teaching it or debugging a rule.
+//! Render the output with `dot -Tsvg tree.dot -o tree.svg`.
+//!
+//! [DOT]: https://graphviz.org/doc/info/lang.html
+
+use crate::{parse_carbon, ParseResult, Rule};
+use pest::iterators::Pair;
+
+/// Parses `input` as a program and returns its parse tree as a Graphviz
+/// `digraph`.
+///
+/// Every pair becomes a node labeled with its rule, with an edge to each of
+/// its children. Leaves, such as identifiers and literals, also show the text
+/// they matched.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::dot::to_dot;
+///
+/// let dot = to_dot("fn main() {}").unwrap();
+/// assert!(dot.starts_with("digraph parse_tree {"));
+/// assert!(dot.contains("n1 [label=\"function_decl\"];"));
+/// assert!(dot.contains("n0 -> n1;"));
+/// ```
+pub fn to_dot(input: &str) -> ParseResult<String> {
+    let mut out = String::from("digraph parse_tree {\n  node [shape=box];\n");
+    let mut next_id = 0;
+    for pair in parse_carbon(input)? {
+        node(pair, &mut next_id, &mut out);
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Writes `pair` and its subtree, returning the id of its node.
+fn node(pair: Pair<'_, Rule>, next_id: &mut usize, out: &mut String) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+
+    let rule = format!("{:?}", pair.as_rule());
+    let mut children = pair.clone().into_inner().peekable();
+    let label = if children.peek().is_none() && !pair.as_str().is_empty() {
+        format!("{rule}\\n{}", escape(pair.as_str()))
+    } else {
+        rule
+    };
+    out.push_str(&format!("  n{id} [label=\"{label}\"];\n"));
+
+    for child in children.filter(|child| child.as_rule() != Rule::EOI) {
+        let child_id = node(child, next_id, out);
+        out.push_str(&format!("  n{id} -> n{child_id};\n"));
+    }
+    id
+}
+
+/// Escapes `text` for a double-quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
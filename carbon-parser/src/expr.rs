@@ -0,0 +1,311 @@
+//! Building nested expression trees from the flat Pest output.
+//!
+//! The grammar matches a chain of binary operations as one flat sequence,
+//! `operand (operator operand)*`, because a PEG cannot express precedence
+//! without one rule per level. [`build_expr_tree`] restores the nesting with
+//! Pest's [`PrattParser`], using these levels from loosest to tightest, all
+//! left-associative:
+//!
+//! | Level | Operators              |
+//! |-------|------------------------|
+//! | 1     | `\|\|`                 |
+//! | 2     | `&&`                   |
+//! | 3     | `==` `!=`              |
+//! | 4     | `<` `>` `<=` `>=`      |
+//! | 5     | `+` `-`                |
+//! | 6     | `*` `/` `%`            |
+//!
+//! The same levels make up the default [`PrecedenceTable`]. Tools that want a
+//! different binding can pass their own table to
+//! [`parse_expression_with`](crate::parse_expression_with).
+
+use crate::ast::{BinaryExpr, BinaryOp, Expr, Literal, LiteralKind};
+use crate::{Rule, Span};
+use pest::iterators::Pairs;
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Builds a nested [`Expr`] from a flat sequence of operands and operators.
+///
+/// `pairs` are typically the children of an `expression` pair: operands
+/// (`primary` pairs) separated by the per-operator rules such as `add` or
+/// `mul`. A single operand is converted on its own.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::{BinaryOp, Expr};
+/// use carbon_parser::expr::build_expr_tree;
+/// use carbon_parser::{parse_expression, Rule};
+///
+/// let mut pairs = parse_expression("a || b && c").unwrap();
+/// let expression = pairs.next().unwrap();
+/// assert_eq!(expression.as_rule(), Rule::expression);
+///
+/// let Expr::Binary(or) = build_expr_tree(expression.into_inner()) else { unreachable!() };
+/// assert_eq!(or.op, BinaryOp::Or);
+/// assert!(matches!(*or.rhs, Expr::Binary(ref and) if and.op == BinaryOp::And));
+/// ```
+pub fn build_expr_tree(pairs: Pairs<'_, Rule>) -> Expr {
+    pratt_parser()
+        .map_primary(Expr::from)
+        .map_infix(|lhs, op, rhs| {
+            let span = Span::new(lhs.span().start, rhs.span().end);
+            Expr::Binary(BinaryExpr {
+                op: BinaryOp::from_rule(op.as_rule()),
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                span,
+            })
+        })
+        .parse(pairs)
+}
+
+/// Operator precedence from lowest to highest, all left-associative.
+fn pratt_parser() -> &'static PrattParser<Rule> {
+    static PARSER: OnceLock<PrattParser<Rule>> = OnceLock::new();
+    PARSER.get_or_init(|| {
+        PrattParser::new()
+            .op(Op::infix(Rule::or, Assoc::Left))
+            .op(Op::infix(Rule::and, Assoc::Left))
+            .op(Op::infix(Rule::eq, Assoc::Left) | Op::infix(Rule::ne, Assoc::Left))
+            .op(Op::infix(Rule::lt, Assoc::Left)
+                | Op::infix(Rule::gt, Assoc::Left)
+                | Op::infix(Rule::le, Assoc::Left)
+                | Op::infix(Rule::ge, Assoc::Left))
+            .op(Op::infix(Rule::add, Assoc::Left) | Op::infix(Rule::sub, Assoc::Left))
+            .op(Op::infix(Rule::mul, Assoc::Left)
+                | Op::infix(Rule::div, Assoc::Left)
+                | Op::infix(Rule::rem, Assoc::Left))
+    })
+}
+
+/// How tightly each binary operator binds.
+///
+/// Higher levels bind tighter, and operators on the same level associate to
+/// the left. The default table holds the built-in levels listed in the
+/// [module documentation](self).
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::BinaryOp;
+/// use carbon_parser::expr::PrecedenceTable;
+///
+/// let table = PrecedenceTable::default();
+/// assert!(table.level(BinaryOp::Mul) > table.level(BinaryOp::Add));
+///
+/// let flat = table.with_level(BinaryOp::Mul, 5);
+/// assert_eq!(flat.level(BinaryOp::Mul), flat.level(BinaryOp::Add));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecedenceTable {
+    levels: HashMap<BinaryOp, u8>,
+}
+
+impl Default for PrecedenceTable {
+    fn default() -> Self {
+        let levels = [
+            (BinaryOp::Or, 1),
+            (BinaryOp::And, 2),
+            (BinaryOp::Eq, 3),
+            (BinaryOp::Ne, 3),
+            (BinaryOp::Lt, 4),
+            (BinaryOp::Gt, 4),
+            (BinaryOp::Le, 4),
+            (BinaryOp::Ge, 4),
+            (BinaryOp::Add, 5),
+            (BinaryOp::Sub, 5),
+            (BinaryOp::Mul, 6),
+            (BinaryOp::Div, 6),
+            (BinaryOp::Rem, 6),
+        ];
+        PrecedenceTable {
+            levels: levels.into_iter().collect(),
+        }
+    }
+}
+
+impl PrecedenceTable {
+    /// The level of `op`; higher binds tighter.
+    pub fn level(&self, op: BinaryOp) -> u8 {
+        self.levels[&op]
+    }
+
+    /// Returns the table with `op` moved to `level`.
+    pub fn with_level(mut self, op: BinaryOp, level: u8) -> Self {
+        self.levels.insert(op, level);
+        self
+    }
+}
+
+/// Regroups every chain of binary operators in `expr` by `table`.
+///
+/// A chain is a binary expression together with the binary operands directly
+/// beneath it. Parenthesized operands are [`Expr::Paren`] nodes, which end a
+/// chain, so source grouping is kept.
+pub(crate) fn reassociate(expr: &mut Expr, table: &PrecedenceTable) {
+    if !matches!(expr, Expr::Binary(_)) {
+        for_each_child_mut(expr, |child| reassociate(child, table));
+        return;
+    }
+
+    let mut operands = Vec::new();
+    let mut ops = Vec::new();
+    flatten_chain(take(expr), &mut operands, &mut ops);
+    let mut operands = operands.into_iter().map(|mut operand| {
+        reassociate(&mut operand, table);
+        operand
+    });
+
+    // Shunting-yard: an operator waits on the stack until one that binds no
+    // tighter arrives, which makes equal levels associate to the left.
+    let mut output = vec![operands.next().expect("a chain starts with an operand")];
+    let mut pending: Vec<BinaryOp> = Vec::new();
+    for (op, rhs) in ops.into_iter().zip(operands) {
+        while pending
+            .last()
+            .is_some_and(|&top| table.level(top) >= table.level(op))
+        {
+            reduce(&mut output, &mut pending);
+        }
+        pending.push(op);
+        output.push(rhs);
+    }
+    while !pending.is_empty() {
+        reduce(&mut output, &mut pending);
+    }
+    *expr = output.pop().expect("the chain reduces to one expression");
+}
+
+fn flatten_chain(expr: Expr, operands: &mut Vec<Expr>, ops: &mut Vec<BinaryOp>) {
+    match expr {
+        Expr::Binary(binary) => {
+            flatten_chain(*binary.lhs, operands, ops);
+            ops.push(binary.op);
+            flatten_chain(*binary.rhs, operands, ops);
+        }
+        operand => operands.push(operand),
+    }
+}
+
+/// Combines the top two operands with the top operator.
+fn reduce(output: &mut Vec<Expr>, pending: &mut Vec<BinaryOp>) {
+    let op = pending.pop().expect("an operator to apply");
+    let rhs = output.pop().expect("a right operand");
+    let lhs = output.pop().expect("a left operand");
+    let span = Span::new(lhs.span().start, rhs.span().end);
+    output.push(Expr::Binary(BinaryExpr {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+        span,
+    }));
+}
+
+/// Rewrites unparenthesized chains of `<`, `>`, `<=` and `>=` in `expr` into
+/// explicit conjunctions, so `a < b <= c` becomes `a < b && b <= c`.
+///
+/// The shared operand `b` is cloned into both comparisons. Both copies keep
+/// the span of the single `b` in the source, which is how an evaluator can
+/// tell that it stands for one value and must be evaluated only once.
+pub(crate) fn desugar_comparison_chains(expr: &mut Expr) {
+    if !is_comparison_chain(expr) {
+        for_each_child_mut(expr, desugar_comparison_chains);
+        return;
+    }
+
+    let Expr::Binary(binary) = take(expr) else {
+        unreachable!("a comparison chain is a binary expression");
+    };
+    let mut operands = Vec::new();
+    let mut ops = Vec::new();
+    flatten_comparisons(binary, &mut operands, &mut ops);
+    for operand in &mut operands {
+        desugar_comparison_chains(operand);
+    }
+
+    let comparisons = ops.into_iter().zip(operands.windows(2)).map(|(op, pair)| {
+        Expr::Binary(BinaryExpr {
+            op,
+            lhs: Box::new(pair[0].clone()),
+            rhs: Box::new(pair[1].clone()),
+            span: Span::new(pair[0].span().start, pair[1].span().end),
+        })
+    });
+    *expr = comparisons
+        .reduce(|lhs, rhs| {
+            let span = Span::new(lhs.span().start, rhs.span().end);
+            Expr::Binary(BinaryExpr {
+                op: BinaryOp::And,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                span,
+            })
+        })
+        .expect("a chain has at least two comparisons");
+}
+
+fn is_relational(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge
+    )
+}
+
+/// `a < b < c` is parsed as `(a < b) < c` without a [`Expr::Paren`] around
+/// the inner comparison.
+fn is_comparison_chain(expr: &Expr) -> bool {
+    matches!(expr, Expr::Binary(outer) if is_relational(outer.op)
+        && matches!(&*outer.lhs, Expr::Binary(inner) if is_relational(inner.op)))
+}
+
+fn flatten_comparisons(binary: BinaryExpr, operands: &mut Vec<Expr>, ops: &mut Vec<BinaryOp>) {
+    match *binary.lhs {
+        Expr::Binary(lhs) if is_relational(lhs.op) => flatten_comparisons(lhs, operands, ops),
+        lhs => operands.push(lhs),
+    }
+    ops.push(binary.op);
+    operands.push(*binary.rhs);
+}
+
+/// Moves `expr` out, leaving a placeholder that is overwritten afterwards.
+fn take(expr: &mut Expr) -> Expr {
+    let placeholder = Expr::Literal(Literal {
+        kind: LiteralKind::Bool,
+        text: String::new(),
+        span: expr.span(),
+    });
+    std::mem::replace(expr, placeholder)
+}
+
+pub(crate) fn for_each_child_mut(expr: &mut Expr, mut f: impl FnMut(&mut Expr)) {
+    match expr {
+        Expr::Call(call) => call.args.iter_mut().for_each(f),
+        Expr::Binary(binary) => {
+            f(&mut binary.lhs);
+            f(&mut binary.rhs);
+        }
+        Expr::Paren(paren) => f(&mut paren.inner),
+        Expr::Await(await_expr) => f(&mut await_expr.operand),
+        Expr::Try(try_expr) => f(&mut try_expr.operand),
+        Expr::Member(member) => f(&mut member.base),
+        Expr::MethodCall(call) => {
+            f(&mut call.receiver);
+            call.args.iter_mut().for_each(f);
+        }
+        Expr::TupleIndex(tuple_index) => f(&mut tuple_index.base),
+        Expr::Index(index) => {
+            f(&mut index.base);
+            f(&mut index.index);
+        }
+        Expr::Convert(convert) => f(&mut convert.arg),
+        Expr::Conditional(conditional) => {
+            f(&mut conditional.condition);
+            f(&mut conditional.then_branch);
+            f(&mut conditional.else_branch);
+        }
+        Expr::Literal(_) | Expr::Ident(_) | Expr::TypeValue(_) => {}
+    }
+}
@@ -0,0 +1,87 @@
+//! Names of the language features this version of the parser understands.
+//!
+//! Build scripts and tools that support several parser versions can check for
+//! a capability by name instead of comparing version numbers.
+
+/// Every supported feature, sorted and without duplicates.
+///
+/// Names are lowercase and hyphenated. A name is never removed or renamed once
+/// it has been published, so lists from different versions can be compared
+/// directly.
+const FEATURES: &[&str] = &[
+    "arrays",
+    "async",
+    "break-continue",
+    "c-style-for",
+    "choice-types",
+    "class-modifiers",
+    "classes",
+    "comments",
+    "compound-assignment",
+    "comptime-params",
+    "conditional-expressions",
+    "deduced-params",
+    "fallthrough",
+    "for-loops",
+    "generic-types",
+    "if-bindings",
+    "if-else",
+    "index-expressions",
+    "index-operators",
+    "integer-widths",
+    "let-bindings",
+    "match",
+    "match-guards",
+    "member-access",
+    "method-calls",
+    "multi-assignment",
+    "packages",
+    "param-directions",
+    "pattern-params",
+    "pointers",
+    "pragmas",
+    "tail-expressions",
+    "throws",
+    "trailing-commas",
+    "try-operator",
+    "tuple-index",
+    "tuple-types",
+    "type-conversions",
+    "type-qualifiers",
+    "type-values",
+    "variadic-params",
+    "where-clauses",
+    "while-loops",
+];
+
+/// Returns the names of the supported language features.
+///
+/// The slice is sorted and contains no duplicates, so it can be compared or
+/// binary-searched as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::supported_features;
+///
+/// let features = supported_features();
+/// assert!(features.contains(&"classes"));
+/// assert!(features.windows(2).all(|pair| pair[0] < pair[1]));
+/// ```
+pub fn supported_features() -> &'static [&'static str] {
+    FEATURES
+}
+
+/// Returns `true` if `name` is one of [`supported_features`].
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::has_feature;
+///
+/// assert!(has_feature("let-bindings"));
+/// assert!(!has_feature("templates"));
+/// ```
+pub fn has_feature(name: &str) -> bool {
+    FEATURES.binary_search(&name).is_ok()
+}
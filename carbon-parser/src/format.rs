@@ -0,0 +1,814 @@
+//! Canonical formatting of Carbon source.
+//!
+//! [`format_carbon`] parses a program and prints it back in a fixed layout, so
+//! code written in different styles ends up looking the same. The layout is:
+//!
+//! - two spaces of indentation per nested block;
+//! - one declaration or statement per line;
+//! - single spaces around binary and assignment operators and after commas;
+//! - one blank line between top-level declarations, and before each method of
+//!   a class.
+//!
+//! Parentheses are kept exactly where the source has them, so formatting never
+//! changes how an expression groups. The output always parses back to the same
+//! program, and formatting it again returns it unchanged.
+//!
+//! [`format_carbon_to`] writes the same layout to an [`io::Write`] as it goes,
+//! with the indentation width taken from [`FormatOptions`]. [`format_range`]
+//! reformats only the declarations inside a selection and keeps the rest of
+//! the source byte for byte.
+//!
+//! Comments are read from the source and written back before the
+//! declaration, statement or class member that follows them, at its
+//! indentation. A comment that follows code on its line stays at the end of
+//! that line; one inside an expression moves to the end of its statement.
+//! Pragmas are printed with the package and imports, in source order, and
+//! pragmas written between declarations join them at the top.
+
+use crate::ast::{
+    ArrayType, Block, ChoiceDecl, ClassDecl, ClassMember, Condition, ElseBranch, Expr,
+    FunctionDecl, GenericParam, IfStmt, IndexOperatorDecl, Item, LetDecl, MatchStmt, Param,
+    Pattern, Pragma, Program, Stmt, TopItem, TypeName, VarDecl,
+};
+use crate::tokens::comment_spans;
+use crate::{parse_to_ast, ParseResult, Span};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+
+/// Layout settings for [`format_carbon_to`].
+///
+/// `FormatOptions::default()` is the canonical layout that [`format_carbon`]
+/// prints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Spaces of indentation per nested block.
+    pub indent_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions { indent_width: 2 }
+    }
+}
+
+/// Parses `input` and returns it in canonical form.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::format::format_carbon;
+///
+/// let messy = "fn add(x:i32,y:i32)->i32{return x+y;}   var total:i32=add(1,2);";
+/// assert_eq!(
+///     format_carbon(messy).unwrap(),
+///     "fn add(x: i32, y: i32) -> i32 {\n  return x + y;\n}\n\nvar total: i32 = add(1, 2);\n"
+/// );
+/// ```
+pub fn format_carbon(input: &str) -> ParseResult<String> {
+    let program = parse_to_ast(input)?;
+    let comments = comments(input, &program)?;
+    Ok(print_to_string(&program, comments))
+}
+
+/// Prints an already parsed program in canonical form.
+///
+/// The AST does not record comments, so the output has none; use
+/// [`format_carbon`] to keep them. This is also the `Display` output of
+/// [`Program`].
+pub fn format_program(program: &Program) -> String {
+    print_to_string(program, VecDeque::new())
+}
+
+fn print_to_string(program: &Program, comments: VecDeque<Comment>) -> String {
+    let mut out = String::new();
+    print_program(program, &FormatOptions::default(), comments, |chunk| {
+        out.push_str(chunk);
+        Ok(())
+    })
+    .expect("writing to a String cannot fail");
+    out
+}
+
+/// Parses `input` and writes it in the layout set by `options` to `w`.
+///
+/// Output is written one top-level declaration at a time, so only the AST
+/// and a single declaration's text are held in memory, never the whole
+/// formatted file. Errors writing to `w` are returned as [`ParseError::Io`].
+///
+/// [`ParseError::Io`]: crate::ParseError::Io
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::format::{format_carbon_to, FormatOptions};
+///
+/// let mut out = Vec::new();
+/// let options = FormatOptions { indent_width: 4 };
+/// format_carbon_to("fn main(){return;}", &mut out, &options).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "fn main() {\n    return;\n}\n");
+/// ```
+pub fn format_carbon_to<W: io::Write>(
+    input: &str,
+    w: &mut W,
+    options: &FormatOptions,
+) -> ParseResult<()> {
+    let program = parse_to_ast(input)?;
+    let comments = comments(input, &program)?;
+    print_program(&program, options, comments, |chunk| {
+        w.write_all(chunk.as_bytes())
+    })?;
+    Ok(w.flush()?)
+}
+
+/// Prints `program` with `comments`, passing the text to `emit` after the
+/// header and after each top-level declaration.
+fn print_program(
+    program: &Program,
+    options: &FormatOptions,
+    comments: VecDeque<Comment>,
+    mut emit: impl FnMut(&str) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut printer = Printer::new(options, comments);
+    let mut header: Vec<(Span, String)> = program
+        .pragmas
+        .iter()
+        .map(|directive| (directive.span, pragma(directive)))
+        .collect();
+    if let Some(package) = &program.package {
+        header.push((
+            package.span,
+            format!(
+                "package {}{} {};",
+                package.name.name,
+                library(&package.library),
+                package.kind.as_str()
+            ),
+        ));
+    }
+    for import in &program.imports {
+        header.push((
+            import.span,
+            format!("import {}{};", import.name.name, library(&import.library)),
+        ));
+    }
+    header.sort_by_key(|(span, _)| span.start);
+    let first_item = program
+        .top_level
+        .first()
+        .map_or(usize::MAX, |top| top_span(program, top).start);
+    for (span, text) in &header {
+        // A pragma among the declarations moves up without its comments.
+        if span.start < first_item {
+            printer.comments_before(span.start);
+        }
+        printer.line(text);
+    }
+    for (index, top) in program.top_level.iter().enumerate() {
+        let start = top_span(program, top).start;
+        printer.trailing_comments_before(start);
+        emit(&printer.out)?;
+        printer.out.clear();
+        if index > 0 || !header.is_empty() {
+            printer.out.push('\n');
+        }
+        printer.comments_before(start);
+        printer.top_item(program, top);
+    }
+    printer.comments_before(usize::MAX);
+    emit(&printer.out)
+}
+
+/// Source span of a top-level declaration or script statement of `program`.
+fn top_span(program: &Program, top: &TopItem) -> Span {
+    match top {
+        TopItem::Item(index) => program.top_level_item(*index).span(),
+        TopItem::Stmt(stmt) => stmt.span(),
+    }
+}
+
+/// A comment of the source that is still to be printed.
+struct Comment {
+    text: String,
+    start: usize,
+    /// Whether code comes before the comment on its line, so that it ends the
+    /// line written before it rather than taking a line of its own.
+    trailing: bool,
+}
+
+/// The comments of `src` in source order, leaving out the pragmas that
+/// `program` already holds.
+fn comments(src: &str, program: &Program) -> ParseResult<VecDeque<Comment>> {
+    Ok(comment_spans(src)?
+        .into_iter()
+        .filter(|span| {
+            program
+                .pragmas
+                .iter()
+                .all(|directive| directive.span.start != span.start)
+        })
+        .map(|span| {
+            let line_start = src[..span.start].rfind('\n').map_or(0, |at| at + 1);
+            Comment {
+                text: src[span.start..span.end].trim_end().to_string(),
+                start: span.start,
+                trailing: !src[line_start..span.start].trim().is_empty(),
+            }
+        })
+        .collect())
+}
+
+/// Reformats the top-level declarations of `src` that lie entirely inside
+/// `range`, in the layout set by `options`, and leaves every other byte of
+/// `src` as it was.
+///
+/// This is the "format selection" of an editor. A declaration that is only
+/// partly inside `range` is left untouched, so a selection that starts or
+/// ends in the middle of a function does not reformat it; select the whole
+/// function to include it. Only the text of each declaration is replaced:
+/// the whitespace and comments between declarations are kept as they are,
+/// and comments inside a reformatted declaration are placed as
+/// [`format_carbon`] places them.
+///
+/// The whole of `src` must parse; otherwise the error is returned and
+/// nothing is formatted.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::format::{format_range, FormatOptions};
+/// use carbon_parser::Span;
+///
+/// let src = "var  a:i32=1;\nfn f(){return;}\n";
+/// let range = Span::new(14, src.len());
+/// assert_eq!(
+///     format_range(src, range, &FormatOptions::default()).unwrap(),
+///     "var  a:i32=1;\nfn f() {\n  return;\n}\n"
+/// );
+/// ```
+pub fn format_range(src: &str, range: Span, options: &FormatOptions) -> ParseResult<String> {
+    let program = parse_to_ast(src)?;
+    let mut printer = Printer::new(options, comments(src, &program)?);
+    let mut out = String::with_capacity(src.len());
+    let mut copied = 0;
+    for top in &program.top_level {
+        let span = top_span(&program, top);
+        if span.start < range.start || span.end > range.end {
+            continue;
+        }
+        printer.out.clear();
+        // Comments before the declaration are copied with the source.
+        printer
+            .comments
+            .retain(|comment| comment.start >= span.start);
+        printer.top_item(&program, top);
+        printer.comments_before(span.end);
+        out.push_str(&src[copied..span.start]);
+        // The line break after the declaration is still in `src`.
+        out.push_str(printer.out.trim_end_matches('\n'));
+        copied = span.end;
+    }
+    out.push_str(&src[copied..]);
+    Ok(out)
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_program(self))
+    }
+}
+
+struct Printer {
+    out: String,
+    /// One level of indentation.
+    indent: String,
+    depth: usize,
+    /// Comments not printed yet, in source order.
+    comments: VecDeque<Comment>,
+}
+
+impl Printer {
+    fn new(options: &FormatOptions, comments: VecDeque<Comment>) -> Self {
+        Printer {
+            out: String::new(),
+            indent: " ".repeat(options.indent_width),
+            depth: 0,
+            comments,
+        }
+    }
+
+    /// Whether a comment that is still to be printed starts before `pos`.
+    fn has_comments_before(&self, pos: usize) -> bool {
+        self.comments
+            .front()
+            .is_some_and(|comment| comment.start < pos)
+    }
+
+    /// Writes the comments that start before `pos`.
+    fn comments_before(&mut self, pos: usize) {
+        while self.has_comments_before(pos) {
+            let comment = self.comments.pop_front().expect("checked above");
+            self.comment(&comment);
+        }
+    }
+
+    /// Writes the comments before `pos` that end the line of code written
+    /// last, leaving the ones on lines of their own.
+    fn trailing_comments_before(&mut self, pos: usize) {
+        while self.has_comments_before(pos) && self.comments[0].trailing {
+            let comment = self.comments.pop_front().expect("checked above");
+            self.comment(&comment);
+        }
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        if comment.trailing && self.out.ends_with('\n') {
+            self.out.pop();
+            self.out.push(' ');
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+        } else {
+            self.line(&comment.text);
+        }
+    }
+
+    /// Writes one top-level declaration or script statement of `program`.
+    fn top_item(&mut self, program: &Program, top: &TopItem) {
+        let item = match top {
+            TopItem::Item(index) => program.top_level_item(*index),
+            TopItem::Stmt(stmt) => return self.stmt(stmt),
+        };
+        match item {
+            Item::Function(function) => self.function(function),
+            Item::Class(class) => self.class(class),
+            Item::Choice(choice) => self.choice(choice),
+            Item::Var(decl) => self.line(&var_decl(decl)),
+            Item::Let(decl) => self.line(&let_decl(decl)),
+        }
+    }
+
+    /// Writes `text` on a line of its own at the current indentation.
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(&self.indent);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Writes `header {`, the statements of `block` and the closing brace.
+    fn block(&mut self, header: &str, block: &Block) {
+        if block.stmts.is_empty() && !self.has_comments_before(block.span.end) {
+            self.line(&format!("{header} {{}}"));
+            return;
+        }
+        self.open_block(header, block);
+        self.line("}");
+    }
+
+    fn function(&mut self, function: &FunctionDecl) {
+        let mut header = String::new();
+        if function.is_async {
+            header.push_str("async ");
+        }
+        header.push_str(&format!(
+            "fn {}({})",
+            function.name.name,
+            params(&function.params)
+        ));
+        if function.throws {
+            header.push_str(" throws");
+        }
+        if let Some(return_type) = &function.return_type {
+            header.push_str(&format!(" -> {}", type_name(return_type)));
+        }
+        self.block(&header, &function.body);
+    }
+
+    fn index_operator(&mut self, operator: &IndexOperatorDecl) {
+        let mut header = format!(
+            "fn ({}) [{}]",
+            param(&operator.receiver),
+            params(&operator.index_params)
+        );
+        if let Some(return_type) = &operator.return_type {
+            header.push_str(&format!(" -> {}", type_name(return_type)));
+        }
+        self.block(&header, &operator.body);
+    }
+
+    fn class(&mut self, class: &ClassDecl) {
+        let mut header = String::new();
+        if let Some(modifier) = class.modifier {
+            header.push_str(modifier.as_str());
+            header.push(' ');
+        }
+        header.push_str(&format!("class {}", class.name.name));
+        if !class.deduced_params.is_empty() {
+            header.push_str(&format!("[{}]", generic_params(&class.deduced_params)));
+        }
+        if !class.generic_params.is_empty() {
+            header.push_str(&format!("({})", generic_params(&class.generic_params)));
+        }
+        if let Some(base) = &class.extends {
+            header.push_str(&format!(" extends {}", type_name(base)));
+        }
+        if !class.constraints.is_empty() {
+            let constraints: Vec<String> = class
+                .constraints
+                .iter()
+                .map(|constraint| {
+                    format!(
+                        "{} impls {}",
+                        constraint.param.name,
+                        type_name(&constraint.bound)
+                    )
+                })
+                .collect();
+            header.push_str(&format!(" where {}", constraints.join(", ")));
+        }
+        if class.members.is_empty() && !self.has_comments_before(class.span.end) {
+            self.line(&format!("{header} {{}}"));
+            return;
+        }
+
+        self.line(&format!("{header} {{"));
+        self.depth += 1;
+        for (index, member) in class.members.iter().enumerate() {
+            let start = match member {
+                ClassMember::Field(field) => field.span.start,
+                ClassMember::Method(method) => method.span.start,
+                ClassMember::IndexOperator(operator) => operator.span.start,
+            };
+            self.trailing_comments_before(start);
+            if index > 0 && !matches!(member, ClassMember::Field(_)) {
+                self.out.push('\n');
+            }
+            self.comments_before(start);
+            match member {
+                ClassMember::Field(field) => self.line(&var_decl(field)),
+                ClassMember::Method(method) => self.function(method),
+                ClassMember::IndexOperator(operator) => self.index_operator(operator),
+            }
+        }
+        self.comments_before(class.span.end);
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn choice(&mut self, choice: &ChoiceDecl) {
+        let header = format!("choice {}", choice.name.name);
+        if choice.variants.is_empty() && !self.has_comments_before(choice.span.end) {
+            self.line(&format!("{header} {{}}"));
+            return;
+        }
+
+        self.line(&format!("{header} {{"));
+        self.depth += 1;
+        for (index, variant) in choice.variants.iter().enumerate() {
+            self.comments_before(variant.span.start);
+            let mut text = variant.name.name.clone();
+            if let Some(value) = &variant.value {
+                text.push_str(&format!(" = {}", expr(value)));
+            }
+            if index + 1 < choice.variants.len() {
+                text.push(',');
+            }
+            self.line(&text);
+        }
+        self.comments_before(choice.span.end);
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Var(decl) => self.line(&var_decl(decl)),
+            Stmt::Let(decl) => self.line(&let_decl(decl)),
+            Stmt::Return(ret) => match &ret.value {
+                Some(value) => self.line(&format!("return {};", expr(value))),
+                None => self.line("return;"),
+            },
+            Stmt::Throw(throw) => self.line(&format!("throw {};", expr(&throw.value))),
+            Stmt::Fallthrough(_) => self.line("fallthrough;"),
+            Stmt::Break(_) => self.line("break;"),
+            Stmt::Continue(_) => self.line("continue;"),
+            Stmt::If(if_stmt) => self.if_stmt("", if_stmt),
+            Stmt::While(while_stmt) => self.block(
+                &format!("while ({})", expr(&while_stmt.condition)),
+                &while_stmt.body,
+            ),
+            Stmt::For(for_stmt) => self.block(
+                &format!(
+                    "for (var {}: {} in {})",
+                    for_stmt.name.name,
+                    type_name(&for_stmt.ty),
+                    expr(&for_stmt.iterable)
+                ),
+                &for_stmt.body,
+            ),
+            Stmt::CFor(for_stmt) => {
+                let init = for_stmt.init.as_deref().map(for_clause).unwrap_or_default();
+                let condition = match &for_stmt.condition {
+                    Some(condition) => format!(" {}", expr(condition)),
+                    None => String::new(),
+                };
+                let update = match &for_stmt.update {
+                    Some(update) => format!(" {}", for_clause(update)),
+                    None => String::new(),
+                };
+                self.block(
+                    &format!("for ({init};{condition};{update})"),
+                    &for_stmt.body,
+                );
+            }
+            Stmt::Match(match_stmt) => self.match_stmt(match_stmt),
+            Stmt::Assign(assign) => self.line(&format!(
+                "{} {} {};",
+                assign.target.name,
+                assign.op.as_str(),
+                expr(&assign.value)
+            )),
+            Stmt::MultiAssign(assign) => {
+                let targets: Vec<&str> = assign
+                    .targets
+                    .iter()
+                    .map(|target| target.name.as_str())
+                    .collect();
+                self.line(&format!(
+                    "{} = {};",
+                    targets.join(", "),
+                    expr(&assign.value)
+                ));
+            }
+            Stmt::Expr(stmt) => {
+                let semicolon = if stmt.has_semicolon { ";" } else { "" };
+                self.line(&format!("{}{semicolon}", expr(&stmt.expr)));
+            }
+        }
+    }
+
+    /// Writes an `if` chain. `prefix` is `"} else "` for chained links, which
+    /// continue on the line that closes the previous block.
+    fn if_stmt(&mut self, prefix: &str, if_stmt: &IfStmt) {
+        let condition = match &if_stmt.condition {
+            Condition::Expr(condition) => expr(condition),
+            Condition::Binding(binding) => format!(
+                "var {}: {} = {}",
+                binding.name.name,
+                type_name(&binding.ty),
+                expr(&binding.value)
+            ),
+        };
+        let header = format!("{prefix}if ({condition})");
+        let Some(else_branch) = &if_stmt.else_branch else {
+            self.block(&header, &if_stmt.then_block);
+            return;
+        };
+
+        // The closing brace of the then-block is written by the else branch.
+        self.open_block(&header, &if_stmt.then_block);
+        match else_branch {
+            ElseBranch::If(next) => self.if_stmt("} else ", next),
+            ElseBranch::Block(block) => self.block("} else", block),
+        }
+    }
+
+    /// Writes `header {` and the statements of `block`, leaving the closing
+    /// brace to the caller.
+    fn open_block(&mut self, header: &str, block: &Block) {
+        self.line(&format!("{header} {{"));
+        self.depth += 1;
+        for stmt in &block.stmts {
+            self.comments_before(stmt.span().start);
+            self.stmt(stmt);
+        }
+        self.comments_before(block.span.end);
+        self.depth -= 1;
+    }
+
+    fn match_stmt(&mut self, match_stmt: &MatchStmt) {
+        self.line(&format!("match ({}) {{", expr(&match_stmt.scrutinee)));
+        self.depth += 1;
+        for arm in &match_stmt.arms {
+            self.comments_before(arm.span.start);
+            let mut header = format!("case {}", pattern(&arm.pattern));
+            if let Some(guard) = &arm.guard {
+                header.push_str(&format!(" if {}", expr(guard)));
+            }
+            header.push_str(" =>");
+            self.block(&header, &arm.body);
+        }
+        if let Some(default) = &match_stmt.default {
+            self.comments_before(default.span.start);
+            self.block("default =>", default);
+        }
+        self.comments_before(match_stmt.span.end);
+        self.depth -= 1;
+        self.line("}");
+    }
+}
+
+fn pragma(pragma: &Pragma) -> String {
+    if pragma.args.is_empty() {
+        format!("//! pragma {}", pragma.name.name)
+    } else {
+        format!(
+            "//! pragma {}({})",
+            pragma.name.name,
+            pragma.args.join(", ")
+        )
+    }
+}
+
+/// The ` library "Name"` clause of a package or import declaration.
+fn library(library: &Option<String>) -> String {
+    match library {
+        Some(name) => format!(" library \"{name}\""),
+        None => String::new(),
+    }
+}
+
+fn var_decl(decl: &VarDecl) -> String {
+    let mut text = format!("var {}: {}", decl.name.name, type_name(&decl.ty));
+    if let Some(init) = &decl.init {
+        text.push_str(&format!(" = {}", expr(init)));
+    }
+    text.push(';');
+    text
+}
+
+/// A clause of a C-style `for` header, without a semicolon.
+fn for_clause(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::Var(decl) => var_decl(decl).trim_end_matches(';').to_string(),
+        Stmt::Assign(assign) => format!(
+            "{} {} {}",
+            assign.target.name,
+            assign.op.as_str(),
+            expr(&assign.value)
+        ),
+        Stmt::Expr(stmt) => expr(&stmt.expr),
+        other => unreachable!("not a for clause: {other:?}"),
+    }
+}
+
+fn let_decl(decl: &LetDecl) -> String {
+    format!(
+        "let {}: {} = {};",
+        decl.name.name,
+        type_name(&decl.ty),
+        expr(&decl.init)
+    )
+}
+
+fn generic_params(params: &[GenericParam]) -> String {
+    params
+        .iter()
+        .map(|param| format!("{}:! {}", param.name.name, type_name(&param.ty)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn params(params: &[Param]) -> String {
+    params.iter().map(param).collect::<Vec<_>>().join(", ")
+}
+
+fn param(param: &Param) -> String {
+    match param {
+        Param::Regular {
+            direction,
+            name,
+            ty,
+            comptime,
+            default,
+            ..
+        } => {
+            let separator = if *comptime { ":!" } else { ":" };
+            let mut text = direction
+                .keyword()
+                .map(|keyword| format!("{keyword} "))
+                .unwrap_or_default();
+            text.push_str(&format!("{}{separator} {}", name.name, type_name(ty)));
+            if let Some(default) = default {
+                text.push_str(&format!(" = {}", expr(default)));
+            }
+            text
+        }
+        Param::Pattern { pattern, ty, .. } => {
+            format!("{}: {}", self::pattern(pattern), type_name(ty))
+        }
+        Param::Variadic { name, ty, .. } => {
+            let mut text = String::new();
+            if let Some(name) = name {
+                text.push_str(&format!("{}: ", name.name));
+            }
+            text.push_str("...");
+            if let Some(ty) = ty {
+                text.push_str(&type_name(ty));
+            }
+            text
+        }
+    }
+}
+
+/// Like [`TypeName::canonical`], but also formats array size expressions.
+fn type_name(ty: &TypeName) -> String {
+    let mut out = String::new();
+    for qualifier in ty.qualifiers.keywords() {
+        out.push_str(qualifier);
+        out.push(' ');
+    }
+    match &ty.array {
+        Some(array) => out.push_str(&array_type(array)),
+        None => match &ty.tuple {
+            Some(tuple) => {
+                let elements: Vec<String> = tuple.elements.iter().map(type_name).collect();
+                out.push_str(&format!("({})", elements.join(", ")));
+            }
+            None => out.push_str(&ty.name),
+        },
+    }
+    if !ty.args.is_empty() {
+        let args: Vec<String> = ty.args.iter().map(type_name).collect();
+        out.push_str(&format!("({})", args.join(", ")));
+    }
+    for pointer in &ty.pointers {
+        out.push('*');
+        for qualifier in pointer.keywords() {
+            out.push(' ');
+            out.push_str(qualifier);
+        }
+    }
+    out
+}
+
+fn array_type(array: &ArrayType) -> String {
+    match &array.size {
+        Some(size) => format!("[{}; {}]", type_name(&array.element), expr(size)),
+        None => format!("[{}]", type_name(&array.element)),
+    }
+}
+
+pub(crate) fn pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard(_) => "_".to_string(),
+        Pattern::Literal(literal) => literal.text.clone(),
+        Pattern::Binding { name, ty, .. } => match ty {
+            Some(ty) => format!("{}: {}", name.name, type_name(ty)),
+            None => name.name.clone(),
+        },
+        Pattern::Constructor { name, args, .. } => {
+            let args: Vec<String> = args.iter().map(self::pattern).collect();
+            format!("{}({})", name.name, args.join(", "))
+        }
+        Pattern::Tuple { elements, .. } => {
+            let elements: Vec<String> = elements.iter().map(self::pattern).collect();
+            format!("({})", elements.join(", "))
+        }
+    }
+}
+
+fn expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(literal) => literal.text.clone(),
+        Expr::Ident(ident) => ident.name.clone(),
+        Expr::Call(call) => {
+            let args: Vec<String> = call.args.iter().map(self::expr).collect();
+            format!("{}({})", call.callee.name, args.join(", "))
+        }
+        Expr::Binary(binary) => format!(
+            "{} {} {}",
+            self::expr(&binary.lhs),
+            binary.op.as_str(),
+            self::expr(&binary.rhs)
+        ),
+        Expr::Paren(paren) => format!("({})", self::expr(&paren.inner)),
+        Expr::Await(await_expr) => format!("await {}", self::expr(&await_expr.operand)),
+        Expr::Try(try_expr) => format!("{}?", self::expr(&try_expr.operand)),
+        Expr::Member(member) => format!("{}.{}", self::expr(&member.base), member.member.name),
+        Expr::MethodCall(call) => {
+            let args: Vec<String> = call.args.iter().map(self::expr).collect();
+            format!(
+                "{}.{}({})",
+                self::expr(&call.receiver),
+                call.method.name,
+                args.join(", ")
+            )
+        }
+        Expr::TupleIndex(tuple_index) => {
+            format!("{}.{}", self::expr(&tuple_index.base), tuple_index.index)
+        }
+        Expr::Index(index) => format!("{}[{}]", self::expr(&index.base), self::expr(&index.index)),
+        Expr::TypeValue(ty) => type_name(ty),
+        Expr::Convert(convert) => {
+            format!("{}({})", type_name(&convert.ty), self::expr(&convert.arg))
+        }
+        Expr::Conditional(conditional) => format!(
+            "if {} then {} else {}",
+            self::expr(&conditional.condition),
+            self::expr(&conditional.then_branch),
+            self::expr(&conditional.else_branch)
+        ),
+    }
+}
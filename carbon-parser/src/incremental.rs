@@ -0,0 +1,271 @@
+//! Helpers for editors that re-parse source incrementally.
+//!
+//! After a text edit, only the top-level declarations touched by the edit need
+//! to be parsed again. [`damage_range`] maps an [`Edit`] onto the previous parse
+//! and returns that region.
+//!
+//! Syntax highlighting needs tokens rather than declarations, and
+//! [`LineTokenCache`] keeps them line by line so that an edit only
+//! re-tokenizes the lines it touches.
+
+use crate::stream::State;
+use crate::tokens::tokenize;
+use crate::{parse_carbon, ParseResult, Rule, Span, TokenKind};
+use std::ops::Range;
+
+/// A single text replacement: the bytes in `range` are replaced by `new_text`.
+///
+/// An insertion has an empty `range`; a deletion has an empty `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte range in the old source that is being replaced.
+    pub range: Span,
+    /// Text inserted in place of `range`.
+    pub new_text: String,
+}
+
+impl Edit {
+    /// Creates an edit replacing `range` with `new_text`.
+    pub fn new(range: Span, new_text: impl Into<String>) -> Self {
+        Edit {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+}
+
+/// Returns the region of `old_src` that must be re-parsed after `edit`.
+///
+/// The result is the union of every top-level declaration the edit overlaps or
+/// touches, expressed in `old_src` byte offsets. An edit that only changes
+/// whitespace or comments between declarations returns the edit's own range,
+/// which is empty for insertions. If `old_src` itself does not parse, there is
+/// no reliable structure to map onto and the whole source is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{damage_range, Edit, Span};
+///
+/// let src = "fn a() { return 1; }\n\nfn b() { return 2; }";
+///
+/// // Changing `1` to `10` only damages the first function.
+/// let edit = Edit::new(Span::new(16, 17), "10");
+/// assert_eq!(damage_range(src, edit), Span::new(0, 20));
+///
+/// // Inserting a blank line between the functions damages nothing.
+/// let edit = Edit::new(Span::new(21, 21), "\n");
+/// assert!(damage_range(src, edit).is_empty());
+/// ```
+pub fn damage_range(old_src: &str, edit: Edit) -> Span {
+    let Ok(mut pairs) = parse_carbon(old_src) else {
+        return Span::new(0, old_src.len());
+    };
+    let Some(program) = pairs.next() else {
+        return Span::new(0, old_src.len());
+    };
+
+    let range = edit.range;
+    program
+        .into_inner()
+        .filter(|decl| decl.as_rule() != Rule::EOI)
+        .map(|decl| Span::from(decl.as_span()))
+        .filter(|decl| range.start <= decl.end && decl.start <= range.end)
+        .reduce(|a, b| Span::new(a.start.min(b.start), a.end.max(b.end)))
+        .unwrap_or(range)
+}
+
+/// The tokens of a source file, kept per line.
+///
+/// Block comments and string literals may span several lines. Such lines are
+/// tokenized together, and a token belongs to the line it starts on.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{Edit, LineTokenCache, Span};
+///
+/// let old_src = "var a: i32 = 1;\nvar b: i32 = 2;\n";
+/// let mut cache = LineTokenCache::new(old_src).unwrap();
+/// assert_eq!(cache.tokens(1).len(), 7);
+///
+/// // Renaming `b` only re-tokenizes the second line.
+/// let edit = Edit::new(Span::new(20, 21), "total");
+/// let new_src = "var a: i32 = 1;\nvar total: i32 = 2;\n";
+/// assert_eq!(cache.update(old_src, new_src, edit).unwrap(), 1..2);
+/// assert_eq!(cache.tokens(1)[1].span, Span::new(4, 9));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineTokenCache {
+    lines: Vec<CachedLine>,
+}
+
+/// A token in a [`LineTokenCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineToken {
+    pub kind: TokenKind,
+    /// Byte range of the token relative to the start of its line. It ends past
+    /// the line for a token that continues on the next one.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedLine {
+    tokens: Vec<LineToken>,
+    /// Whether the line ends inside a block comment or string literal, which
+    /// ties it to the next line.
+    continued: bool,
+}
+
+impl LineTokenCache {
+    /// Tokenizes every line of `src`.
+    pub fn new(src: &str) -> ParseResult<Self> {
+        let starts = line_starts(src);
+        Ok(LineTokenCache {
+            lines: tokenize_lines(src, &starts, 0, |_| false)?,
+        })
+    }
+
+    /// The number of lines in the cached source.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The tokens starting on `line`, counted from 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is not less than [`line_count`](Self::line_count).
+    pub fn tokens(&self, line: usize) -> &[LineToken] {
+        &self.lines[line].tokens
+    }
+
+    /// Brings the cache from `old_src` to `new_src`, which is `old_src` with
+    /// `edit` applied, and returns the lines of `new_src` that were
+    /// re-tokenized.
+    ///
+    /// These are the lines `edit` touches, widened to whole block comments
+    /// and string literals: re-tokenizing continues until a line ends outside
+    /// of them in both sources. If the new text cannot be tokenized, such as
+    /// while a string literal is still unterminated, the error is returned and
+    /// the cache still describes `old_src`.
+    pub fn update(
+        &mut self,
+        old_src: &str,
+        new_src: &str,
+        edit: Edit,
+    ) -> ParseResult<Range<usize>> {
+        let old_starts = line_starts(old_src);
+        let new_starts = line_starts(new_src);
+        let mut first = line_of(&old_starts, edit.range.start);
+        while first > 0 && self.lines[first - 1].continued {
+            first -= 1;
+        }
+        let old_last = line_of(&old_starts, edit.range.end);
+        let new_last = line_of(&new_starts, edit.range.start + edit.new_text.len());
+
+        // Past the edit, new line `end - 1` is old line `end - 1 - new_last +
+        // old_last`, and the lines after both are the same text.
+        let lines = tokenize_lines(new_src, &new_starts, first, |end| {
+            end > new_last && !self.lines[end - 1 - new_last + old_last].continued
+        })?;
+        let end = first + lines.len();
+        self.lines.splice(first..end - new_last + old_last, lines);
+        Ok(first..end)
+    }
+}
+
+/// Byte offset at which each line of `src` starts.
+fn line_starts(src: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// The line containing byte `offset`.
+fn line_of(starts: &[usize], offset: usize) -> usize {
+    starts.partition_point(|&start| start <= offset) - 1
+}
+
+/// Tokenizes the lines of `src` from `first`, which must not start inside a
+/// block comment or string literal, until `done` accepts the end of a line
+/// that ends outside of them, or to the end of `src`.
+fn tokenize_lines(
+    src: &str,
+    starts: &[usize],
+    first: usize,
+    mut done: impl FnMut(usize) -> bool,
+) -> ParseResult<Vec<CachedLine>> {
+    let line_end = |line: usize| starts.get(line + 1).copied().unwrap_or(src.len());
+    let mut lines = Vec::new();
+    let mut group = first;
+    let mut state = State::Code;
+    for line in first..starts.len() {
+        state = end_state(state, &src[starts[line]..line_end(line)]);
+        let continued = matches!(state, State::String | State::BlockComment(_));
+        lines.push(CachedLine {
+            tokens: Vec::new(),
+            continued,
+        });
+        if continued && line + 1 < starts.len() {
+            continue;
+        }
+
+        let offset = starts[group];
+        for token in tokenize(&src[offset..line_end(line)])? {
+            let start = offset + token.span.start;
+            let owner = line_of(starts, start);
+            lines[owner - first].tokens.push(LineToken {
+                kind: token.kind,
+                span: Span::new(
+                    start - starts[owner],
+                    offset + token.span.end - starts[owner],
+                ),
+            });
+        }
+        group = line + 1;
+        state = State::Code;
+        if done(line + 1) {
+            break;
+        }
+    }
+    Ok(lines)
+}
+
+/// The state of the lexer after `line`, starting in `state`. Line comments and
+/// character literals end with their line.
+fn end_state(mut state: State, line: &str) -> State {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match (state, bytes[i]) {
+            (State::Code, b'"') => state = State::String,
+            (State::Code, b'\'') => state = State::Char,
+            (State::Code, b'/') if next == Some(b'/') => state = State::LineComment,
+            (State::Code, b'/') if next == Some(b'*') => {
+                state = State::BlockComment(1);
+                i += 1;
+            }
+            (State::String | State::Char, b'\\') => i += 1,
+            (State::String, b'"') | (State::Char, b'\'') => state = State::Code,
+            (State::BlockComment(depth), b'/') if next == Some(b'*') => {
+                state = State::BlockComment(depth + 1);
+                i += 1;
+            }
+            (State::BlockComment(depth), b'*') if next == Some(b'/') => {
+                state = match depth {
+                    1 => State::Code,
+                    _ => State::BlockComment(depth - 1),
+                };
+                i += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    match state {
+        State::LineComment | State::Char => State::Code,
+        other => other,
+    }
+}
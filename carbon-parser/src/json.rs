@@ -0,0 +1,42 @@
+//! JSON export of parse trees.
+//!
+//! [`to_json`] turns the parse tree of a program into nested JSON objects,
+//! the format printed by `carbon-parser parse --format json`. Each node has
+//! the form:
+//!
+//! ```json
+//! { "rule": "function_decl", "span": { "start": 0, "end": 12 }, "text": "...", "children": [] }
+//! ```
+
+use crate::{parse_carbon, ParseResult, Rule};
+use pest::iterators::Pair;
+use serde_json::{json, Value};
+
+/// Parses `input` as a program and returns its parse tree as JSON.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::json::to_json;
+///
+/// let tree = to_json("fn main() {}").unwrap();
+/// assert_eq!(tree["rule"], "program");
+/// assert_eq!(tree["children"][0]["rule"], "function_decl");
+/// ```
+pub fn to_json(input: &str) -> ParseResult<Value> {
+    let program = parse_carbon(input)?
+        .next()
+        .expect("the program rule always produces a single pair");
+    Ok(pair_to_json(program))
+}
+
+/// Converts `pair` and its subtree to JSON.
+pub fn pair_to_json(pair: Pair<'_, Rule>) -> Value {
+    let span = pair.as_span();
+    json!({
+        "rule": format!("{:?}", pair.as_rule()),
+        "span": { "start": span.start(), "end": span.end() },
+        "text": pair.as_str(),
+        "children": pair.into_inner().map(pair_to_json).collect::<Vec<_>>(),
+    })
+}
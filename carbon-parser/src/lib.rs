@@ -81,6 +81,10 @@
 //! // Function with different parameter types
 //! let code = "fn process(name: String, age: i32, active: bool) -> bool { return active; }";
 //! assert!(parse_function_decl(code).is_ok());
+//!
+//! // Parameter destructured by a tuple pattern
+//! let code = "fn dist((x, y): (i32, i32)) -> i32 { return x * x + y * y; }";
+//! assert!(parse_function_decl(code).is_ok());
 //! ```
 //!
 //! ### Variables
@@ -109,6 +113,122 @@
 //! // Variable with expression
 //! let code = "var sum: i32 = 10 + 20;";
 //! assert!(parse_var_decl(code).is_ok());
+//!
+//! // Inferred type, written as `auto` or left out
+//! assert!(parse_var_decl("var count: auto = 0;").is_ok());
+//! assert!(parse_var_decl("var count = 0;").is_ok());
+//! ```
+//!
+//! Immutable bindings use `let` and always need an initializer. They are
+//! accepted both at the top level and inside blocks:
+//!
+//! ```rust
+//! use carbon_parser::parse_let_decl;
+//!
+//! assert!(parse_let_decl("let x: i32 = 5;").is_ok());
+//! assert!(parse_let_decl("let x: i32;").is_err());
+//! ```
+//!
+//! ### Assignments
+//!
+//! Declared variables can be reassigned with `=` or a compound operator:
+//!
+//! ```rust
+//! use carbon_parser::parse_function_decl;
+//!
+//! let code = r#"
+//!     fn count(n: i32) -> i32 {
+//!         var total: i32 = 0;
+//!         while (total < n) { total += 1; }
+//!         total = total * 2;
+//!         return total;
+//!     }
+//! "#;
+//! assert!(parse_function_decl(code).is_ok());
+//! ```
+//!
+//! Several variables can be assigned at once from a function that returns a
+//! tuple:
+//!
+//! ```rust
+//! use carbon_parser::parse_function_decl;
+//!
+//! let code = "fn step(a: i32, b: i32) -> (i32, i32) { a, b = swap(a, b); return a; }";
+//! assert!(parse_function_decl(code).is_ok());
+//! ```
+//!
+//! ### Classes
+//!
+//! Classes group fields and methods. Generic classes take compile-time
+//! parameters in parentheses after the name:
+//!
+//! ```rust
+//! use carbon_parser::parse_class_decl;
+//!
+//! let code = r#"
+//!     class Stack(T:! type) {
+//!         var size: i32 = 0;
+//!         fn Push(value: T) { store(value); }
+//!     }
+//! "#;
+//! assert!(parse_class_decl(code).is_ok());
+//! ```
+//!
+//! Parameters in square brackets are deduced rather than passed, and a `where`
+//! clause constrains them. Methods may only use the type parameters their
+//! class declares:
+//!
+//! ```rust
+//! use carbon_parser::check_program;
+//!
+//! let code = r#"
+//!     class Set[T:! type] where T impls Hashable {
+//!         fn Contains(value: T) -> bool { return lookup(value); }
+//!     }
+//! "#;
+//! assert!(check_program(code).unwrap().is_empty());
+//! ```
+//!
+//! A class can extend a base class. Marking a class `final` forbids that, and
+//! [`check_program`] reports any class extending a `final` class; `sealed`
+//! classes may only be extended within the same file:
+//!
+//! ```rust
+//! use carbon_parser::check_program;
+//!
+//! let code = "final class Circle { } class Ring extends Circle { }";
+//! assert_eq!(check_program(code).unwrap().len(), 1);
+//! ```
+//!
+//! Containers overload indexing with an index operator, which takes exactly
+//! one index in square brackets and is used as `container[i]`:
+//!
+//! ```rust
+//! use carbon_parser::{parse_class_decl, parse_expression};
+//!
+//! let code = r#"
+//!     class Buffer {
+//!         fn (self: Self) [index: i32] -> u8 { return load(index); }
+//!     }
+//! "#;
+//! assert!(parse_class_decl(code).is_ok());
+//! assert!(parse_expression("buffer[i + 1]").is_ok());
+//! ```
+//!
+//! ### Choice Types
+//!
+//! A `choice` lists named variants. Variants may carry an explicit integer
+//! discriminant; omitted ones continue from the previous value:
+//!
+//! ```rust
+//! use carbon_parser::check_program;
+//!
+//! let code = "choice Status { Ok = 0, Warning, Error = 10 }";
+//! assert!(check_program(code).unwrap().is_empty());
+//!
+//! // `Warning` is 1, the same as `Retry`.
+//! let code = "choice Status { Ok = 0, Warning, Retry = 1 }";
+//! assert_eq!(check_program(code).unwrap().len(), 1);
 //! ```
 //!
 //! ### Expressions
@@ -119,11 +239,14 @@
 //! ```rust
 //! use carbon_parser::parse_expression;
 //!
-//! // Integer literal
+//! // Integer literals, also in hexadecimal or binary and with digit separators
 //! assert!(parse_expression("42").is_ok());
+//! assert!(parse_expression("0xFF").is_ok());
+//! assert!(parse_expression("1_000_000").is_ok());
 //!
-//! // Float literal
+//! // Float literals, optionally with an exponent
 //! assert!(parse_expression("3.14").is_ok());
+//! assert!(parse_expression("6.022e23").is_ok());
 //!
 //! // Boolean literals
 //! assert!(parse_expression("true").is_ok());
@@ -132,6 +255,10 @@
 //! // String literal
 //! assert!(parse_expression(r#""Hello, World!""#).is_ok());
 //!
+//! // Character literals hold one character or escape sequence
+//! assert!(parse_expression("'a'").is_ok());
+//! assert!(parse_expression(r"'\n'").is_ok());
+//!
 //! // Identifier
 //! assert!(parse_expression("variable_name").is_ok());
 //!
@@ -142,6 +269,17 @@
 //! // Function call
 //! assert!(parse_expression("calculate(x, y)").is_ok());
 //!
+//! // Conversion to a built-in type
+//! assert!(parse_expression("i64(x)").is_ok());
+//!
+//! // Conditional expression
+//! assert!(parse_expression("if x > 0 then 1 else -1").is_ok());
+//!
+//! // Member access, method calls and tuple indexing
+//! assert!(parse_expression("point.x").is_ok());
+//! assert!(parse_expression("shape.area(scale)").is_ok());
+//! assert!(parse_expression("nested.0.1").is_ok());
+//!
 //! // Comparison operators
 //! assert!(parse_expression("x == y").is_ok());
 //! assert!(parse_expression("x != y").is_ok());
@@ -156,9 +294,11 @@
 //! ```rust
 //! use carbon_parser::parse_type_name;
 //!
-//! // Integer types
+//! // Integer types, signed and unsigned, 8 to 64 bits wide
 //! assert!(parse_type_name("i32").is_ok());
 //! assert!(parse_type_name("i64").is_ok());
+//! assert!(parse_type_name("u8").is_ok());
+//! assert!(parse_type_name("i17").is_err());
 //!
 //! // Float types
 //! assert!(parse_type_name("f32").is_ok());
@@ -174,6 +314,140 @@
 //! assert!(parse_type_name("CustomType").is_ok());
 //! ```
 //!
+//! ### Conditionals
+//!
+//! `if` statements take a parenthesized condition and an optional `else` block,
+//! which can be chained as `else if (...) { ... }`. The condition may bind a variable that is only visible in the then-branch:
+//!
+//! ```rust
+//! use carbon_parser::{check_program, parse_function_decl};
+//!
+//! let code = r#"
+//!     fn first(items: List) -> i32 {
+//!         if (var x: i32 = head(items)) { return x; } else { return 0; }
+//!     }
+//! "#;
+//! assert!(parse_function_decl(code).is_ok());
+//! assert!(check_program(code).unwrap().is_empty());
+//! ```
+//!
+//! ### Loops
+//!
+//! `while` loops repeat a block as long as the condition holds, and `for` loops
+//! iterate over a collection. A C-style `for` with init, condition and update
+//! clauses, any of which may be left empty, is also accepted:
+//!
+//! ```rust
+//! use carbon_parser::parse_function_decl;
+//!
+//! let code = r#"
+//!     fn drain(queue: Queue) {
+//!         while (size(queue) > 0) { pop(queue); }
+//!         for (var item: Task in pending(queue)) { run(item); }
+//!         for (var i: i32 = 0; i < 3; i += 1) { retry(queue); }
+//!     }
+//! "#;
+//! assert!(parse_function_decl(code).is_ok());
+//! ```
+//!
+//! ### Async Functions
+//!
+//! A function marked `async` may `await` other calls. The postfix `?`
+//! propagates an error, so `await fetch()?` awaits first and then checks the
+//! result:
+//!
+//! ```rust
+//! use carbon_parser::{check_program, parse_function_decl};
+//!
+//! let code = "async fn load() throws -> i32 { return await fetch()?; }";
+//! assert!(parse_function_decl(code).is_ok());
+//! assert!(check_program(code).unwrap().is_empty());
+//!
+//! // `await` outside an async function is reported.
+//! let code = "fn load() -> i32 { return await fetch(); }";
+//! assert_eq!(check_program(code).unwrap().len(), 1);
+//! ```
+//!
+//! ### Pattern Matching
+//!
+//! A `match` statement compares a value against `case` arms. Each arm has a
+//! pattern (`_`, a literal, a binding such as `x: i32`, or a constructor such as
+//! `Some(x)`) and an optional `if` guard:
+//!
+//! ```rust
+//! use carbon_parser::parse_function_decl;
+//!
+//! let code = r#"
+//!     fn sign(value: Option) -> i32 {
+//!         match (value) {
+//!             case Some(x) if x > 0 => { return 1; }
+//!             case Some(x) => { return 0; }
+//!             default => { return -1; }
+//!         }
+//!     }
+//! "#;
+//! assert!(parse_function_decl(code).is_ok());
+//! ```
+//!
+//! ### Errors
+//!
+//! Functions that can fail are marked with `throws` and raise errors with a
+//! `throw` statement. Using `throw` in a function without the marker parses, but
+//! is reported by [`check_program`]:
+//!
+//! ```rust
+//! use carbon_parser::{check_program, parse_function_decl};
+//!
+//! let code = r#"fn open(path: String) throws -> i32 { throw IoError(path, 2); }"#;
+//! assert!(parse_function_decl(code).is_ok());
+//! assert!(check_program(code).unwrap().is_empty());
+//!
+//! let code = "fn open() -> i32 { throw IoError(2); }";
+//! assert_eq!(check_program(code).unwrap().len(), 1);
+//! ```
+//!
+//! ### Pragmas
+//!
+//! A top-level line starting with `//! pragma` is a directive for tools rather
+//! than a comment. Pragmas are collected on [`ast::Program::pragmas`] with their
+//! arguments, and [`check_program`] warns about names it does not recognize.
+//! Inside a function or class, or when the rest of the line is not a
+//! well-formed directive, such a line stays an ordinary comment:
+//!
+//! ```rust
+//! use carbon_parser::{check_program, parse_to_ast};
+//!
+//! let program = parse_to_ast("//! pragma optimize(2)\nfn main() { }").unwrap();
+//! assert_eq!(program.pragmas[0].name.name, "optimize");
+//! assert_eq!(program.pragmas[0].args, ["2"]);
+//!
+//! let warnings = check_program("//! pragma optimise(2)\nfn main() { }").unwrap();
+//! assert_eq!(warnings.len(), 1);
+//!
+//! let program = parse_to_ast("fn main() {\n//! pragma optimize(2)\n}").unwrap();
+//! assert!(program.pragmas.is_empty());
+//! ```
+//!
+//! ### Packages
+//!
+//! A file can start with a package declaration, followed by imports. Both are
+//! collected on [`ast::Program`] apart from the other declarations:
+//!
+//! ```rust
+//! use carbon_parser::ast::PackageKind;
+//! use carbon_parser::parse_to_ast;
+//!
+//! let code = "package Geometry api;\nimport Math;\nfn area() -> f64 { return 0.0; }";
+//! let program = parse_to_ast(code).unwrap();
+//! let package = program.package.as_ref().unwrap();
+//! assert_eq!(package.name.name, "Geometry");
+//! assert_eq!(package.kind, PackageKind::Api);
+//! assert_eq!(program.imports[0].name.name, "Math");
+//!
+//! // The package declaration must come first.
+//! assert!(parse_to_ast("import Math;\npackage Geometry api;").is_err());
+//! ```
+//!
 //! ## Complete Programs
 //!
 //! The main parsing function handles complete Carbon programs:
@@ -256,6 +530,48 @@
 //! }
 //! ```
 //!
+//! ## Typed AST
+//!
+//! [`parse_to_ast`] converts the parse tree into the owned types of the [`ast`]
+//! module, with binary operators nested according to their precedence and
+//! every node carrying its source span:
+//!
+//! ```rust
+//! use carbon_parser::ast::Item;
+//! use carbon_parser::parse_to_ast;
+//!
+//! let program = parse_to_ast("fn main() -> i32 { return 0; }").unwrap();
+//! for item in &program.items {
+//!     if let Item::Function(function) = item {
+//!         println!("fn {} at {}", function.name.name, function.span);
+//!     }
+//! }
+//! ```
+//!
+//! [`parse_expression_ast`] does the same for a single expression; the
+//! precedence table it follows is documented in the [`expr`] module.
+//!
+//! To analyse a program without matching every node yourself, implement
+//! [`ast::Visitor`], override the nodes of interest and pass it to
+//! [`ast::walk_program`]; the default methods descend into everything else.
+//!
+//! [`describe_program`] turns an AST into a short English outline, one sentence
+//! per declaration.
+//!
+//! When only the size of the input matters, [`tokenize`] and [`count_tokens`]
+//! split it into tokens without parsing it as a program.
+//!
+//! [`format::format_carbon`] prints a program back in a canonical layout, like
+//! `rustfmt` does for Rust, keeping its comments. [`format::format_carbon_to`]
+//! writes it to any `io::Write` instead, one declaration at a time, and
+//! [`format::format_range`] reformats only the declarations in a selection.
+//!
+//! [`supported_features`] lists the language features this version parses, and
+//! [`has_feature`] checks for one by name.
+//!
+//! The most common functions and AST types are also available together through
+//! `use carbon_parser::prelude::*;` (see [`prelude`]).
+//!
 //! ## Error Handling
 //!
 //! The parser provides detailed error messages indicating the exact location and nature of syntax errors:
@@ -286,6 +602,27 @@
 //! }
 //! ```
 //!
+//! Tools that need the details without parsing the message can ask the error
+//! directly with [`ParseError::location`] and [`ParseError::expected`]:
+//!
+//! ```rust
+//! use carbon_parser::parse_carbon;
+//!
+//! let err = parse_carbon("var x: i32 = 42").unwrap_err();
+//! assert_eq!(err.location(), Some((1, 16)));
+//! assert!(err.expected().contains(&";".to_string()));
+//! ```
+//!
+//! When the source came from a file, [`parse_with_filename`] reports errors as
+//! `filename:line:col: message`:
+//!
+//! ```rust
+//! use carbon_parser::parse_with_filename;
+//!
+//! let err = parse_with_filename("var x: i32 = 42", "config.carbon").unwrap_err();
+//! assert!(err.to_string().starts_with("config.carbon:1:16:"));
+//! ```
+//!
 //! ## Command Line Interface
 //!
 //! The parser includes a CLI tool for parsing Carbon files:
@@ -297,6 +634,12 @@
 //! # Parse with verbose output showing the parse tree
 //! cargo run -- parse example.carbon --verbose
 //!
+//! # Print the parse tree as JSON (rule, span, text, children)
+//! cargo run -- parse example.carbon --format json
+//!
+//! # Print the parse tree as a Graphviz graph (see [`dot::to_dot`])
+//! cargo run -- parse example.carbon --format dot
+//!
 //! # Show author information
 //! cargo run -- authors
 //! ```
@@ -350,6 +693,9 @@
 //! }
 //! ```
 //!
+//! [`stats::collect_stats`] gathers such counts, along with the depth and size
+//! of the whole parse tree, in one pass.
+//!
 //! ## Performance Considerations
 //!
 //! This parser is designed for optimal performance:
@@ -360,7 +706,8 @@
 //! - **Efficient grammar**: The PEG grammar is optimized to minimize backtracking.
 //!
 //! For large files (>1MB), consider:
-//! - Using streaming or incremental parsing if available
+//! - Using [`parse_carbon_streaming`], which parses one top-level declaration
+//!   at a time from any [`BufRead`](std::io::BufRead)
 //! - Processing the parse tree in chunks
 //! - Using the `--verbose` flag judiciously in the CLI tool
 //!
@@ -376,14 +723,26 @@
 //!         // Pest error with line/column information
 //!         eprintln!("Syntax error at: {}", e);
 //!     }
-//!     Err(ParseError::SyntaxError(msg)) => {
+//!     Err(ParseError::SyntaxError { message, .. }) => {
 //!         // Custom syntax error
-//!         eprintln!("Error: {}", msg);
+//!         eprintln!("Error: {}", message);
+//!     }
+//!     Err(err @ ParseError::WithSource { .. }) => {
+//!         // Error produced by `ParseError::with_source`, with a caret snippet
+//!         eprintln!("{}", err);
+//!     }
+//!     Err(ParseError::Io(e)) => {
+//!         // Reading the input failed (streaming parser only)
+//!         eprintln!("Read error: {}", e);
 //!     }
 //!     Ok(_) => {}
 //! }
 //! ```
 //!
+//! Parsing stops at the first error. Editors that want every syntax error in a
+//! file can use [`parse_carbon_recover`], which skips each broken statement and
+//! keeps going. Recovery is best-effort; see its documentation for the details.
+//!
 //! ## Testing
 //!
 //! The library includes comprehensive integration tests covering:
@@ -422,10 +781,55 @@
 //!
 //! This project is available under standard open source licenses.
 
+use pest::error::{ErrorVariant, LineColLocation};
+use pest::iterators::Pairs;
 use pest::Parser;
 use pest_derive::Parser;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
 use thiserror::Error;
 
+pub mod ast;
+pub mod consteval;
+pub mod describe;
+pub mod dot;
+pub mod expr;
+pub mod features;
+pub mod format;
+pub mod incremental;
+pub mod json;
+pub mod options;
+pub mod prelude;
+pub mod recover;
+pub mod report;
+pub mod semantics;
+pub mod span;
+pub mod stats;
+pub mod stream;
+pub mod tokens;
+pub mod transcript;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use ast::ast_hash;
+pub use describe::describe_program;
+pub use expr::PrecedenceTable;
+pub use features::{has_feature, supported_features};
+pub use incremental::{damage_range, Edit, LineTokenCache};
+pub use options::ParseOptions;
+pub use recover::parse_carbon_recover;
+pub use report::{render_diagnostic, render_diagnostic_colored};
+pub use semantics::{
+    annotate, build_symbol_table, check_ast, check_program, check_undeclared, infer_literal_type,
+    locals_of, validate_unique_names, LocalVar, SemanticError, Severity, SymbolTable,
+};
+pub use span::{LineColumn, Span, SpanResolver};
+pub use stream::{parse_carbon_streaming, Declaration};
+pub use tokens::{
+    count_tokens, spans_of_kind, tokenize, unescape_string, RuleCategory, Token, TokenKind,
+};
+pub use transcript::parse_transcript;
+
 /// Carbon parser implementation using Pest.
 ///
 /// This struct is generated by the `pest_derive` macro and implements the parsing
@@ -452,9 +856,11 @@ pub enum ParseError {
     /// A syntax error in the Carbon source code.
     ///
     /// This error is used for high-level syntax violations that are caught
-    /// during semantic analysis rather than during initial parsing.
-    #[error("Syntax error: {0}")]
-    SyntaxError(String),
+    /// during semantic analysis rather than during initial parsing. `span` is
+    /// the byte range of the offending text, when known; `Display` appends it
+    /// as `at start..end`.
+    #[error("Syntax error: {message}{}", at_span(.span))]
+    SyntaxError { message: String, span: Option<Span> },
 
     /// An error from the Pest parser.
     ///
@@ -475,6 +881,266 @@ pub enum ParseError {
     /// ```
     #[error("Parser error: {0}")]
     PestError(#[from] pest::error::Error<Rule>),
+
+    /// An error rendered against its source by [`ParseError::with_source`],
+    /// or located in the whole input by [`parse_carbon_streaming`].
+    ///
+    /// `Display` shows the message followed by the offending line, with a `^`
+    /// under the column where parsing failed.
+    #[error("{message}\n{snippet}")]
+    WithSource {
+        message: String,
+        /// 1-based line the snippet points at.
+        line: usize,
+        /// 1-based column the snippet points at, in characters.
+        column: usize,
+        /// Byte offset in the source of the position the snippet points at.
+        offset: usize,
+        snippet: String,
+        source: Box<ParseError>,
+    },
+
+    /// Reading the input or writing the output failed.
+    ///
+    /// Reads fail in [`parse_carbon_streaming`], [`parse_carbon_file`] and the
+    /// `carbon-parser` command-line tool; writes fail in
+    /// [`format::format_carbon_to`].
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The ` at start..end` suffix of a [`ParseError::SyntaxError`] with a span.
+fn at_span(span: &Option<Span>) -> String {
+    span.map(|span| format!(" at {span}")).unwrap_or_default()
+}
+
+impl ParseError {
+    /// A [`ParseError::SyntaxError`] without a location.
+    pub fn syntax(message: impl Into<String>) -> Self {
+        ParseError::SyntaxError {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// A [`ParseError::SyntaxError`] about the text at `span`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::{ParseError, Span};
+    ///
+    /// let error = ParseError::syntax_at("unexpected `else`", Span::new(14, 18));
+    /// assert_eq!(error.to_string(), "Syntax error: unexpected `else` at 14..18");
+    /// ```
+    pub fn syntax_at(message: impl Into<String>, span: Span) -> Self {
+        ParseError::SyntaxError {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    /// The 1-based `(line, column)` where parsing failed.
+    ///
+    /// Returns `None` for a [`ParseError::SyntaxError`] or [`ParseError::Io`],
+    /// which have no line and column; a syntax error with a span gets one
+    /// from [`with_source`](Self::with_source).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_carbon;
+    ///
+    /// let error = parse_carbon("fn main() -> { }").unwrap_err();
+    /// assert_eq!(error.location(), Some((1, 14)));
+    /// ```
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            ParseError::PestError(e) => {
+                let (LineColLocation::Pos(start) | LineColLocation::Span(start, _)) = e.line_col;
+                Some(start)
+            }
+            ParseError::WithSource { line, column, .. } => Some((*line, *column)),
+            ParseError::SyntaxError { .. } | ParseError::Io(_) => None,
+        }
+    }
+
+    /// A stable code for the kind of error, for tools that branch on the
+    /// kind instead of the message text.
+    ///
+    /// | Code   | Meaning                                                        |
+    /// |--------|----------------------------------------------------------------|
+    /// | `E001` | unexpected input                                               |
+    /// | `E002` | missing `;`: a semicolon would have been accepted              |
+    /// | `E003` | unclosed delimiter: a `)`, `]` or `}` would have been accepted |
+    /// | `E004` | a [`ParseError::SyntaxError`]                                  |
+    /// | `E005` | reading or writing failed ([`ParseError::Io`])                 |
+    ///
+    /// Codes are never reused for a different meaning. A
+    /// [`ParseError::WithSource`] has the code of the error it wraps.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_carbon;
+    ///
+    /// assert_eq!(parse_carbon("var x: i32 = 1").unwrap_err().code(), "E002");
+    /// assert_eq!(parse_carbon("fn f() { g(1 }").unwrap_err().code(), "E003");
+    /// assert_eq!(parse_carbon("fn test {").unwrap_err().code(), "E001");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::PestError(_) => {
+                let expected = self.expected();
+                let accepts = |token: &str| expected.iter().any(|e| e == token);
+                if accepts(";") {
+                    "E002"
+                } else if accepts(")") || accepts("]") || accepts("}") {
+                    "E003"
+                } else {
+                    "E001"
+                }
+            }
+            ParseError::WithSource { source, .. } => source.code(),
+            ParseError::SyntaxError { .. } => "E004",
+            ParseError::Io(_) => "E005",
+        }
+    }
+
+    /// What the parser would have accepted at the point where it gave up.
+    ///
+    /// Entries are the literal tokens that were tried at the furthest position
+    /// the parser reached, such as `(` or `;`, with whitespace and comment
+    /// openers left out. Character classes are written as ranges like `a..z`.
+    /// When no token information is available, the names of the grammar rules
+    /// Pest expected are returned instead. A [`ParseError::SyntaxError`] or
+    /// [`ParseError::Io`] returns an empty list.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_carbon;
+    ///
+    /// let error = parse_carbon("fn test {").unwrap_err();
+    /// assert_eq!(error.expected(), ["("]);
+    /// ```
+    pub fn expected(&self) -> Vec<String> {
+        let e = match self {
+            ParseError::PestError(e) => e,
+            ParseError::WithSource { source, .. } => return source.expected(),
+            ParseError::SyntaxError { .. } | ParseError::Io(_) => return Vec::new(),
+        };
+        let tokens: Vec<String> = e
+            .parse_attempts()
+            .map(|attempts| attempts.expected_tokens())
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .filter(|token| {
+                !(token.trim().is_empty() || token.starts_with("//") || token.starts_with("/*"))
+            })
+            .collect();
+        if !tokens.is_empty() {
+            return tokens;
+        }
+        match &e.variant {
+            ErrorVariant::ParsingError { positives, .. } => {
+                positives.iter().map(|rule| format!("{rule:?}")).collect()
+            }
+            ErrorVariant::CustomError { .. } => Vec::new(),
+        }
+    }
+
+    /// Attaches the offending line of `source` to the error.
+    ///
+    /// `source` must be the text that produced the error. The snippet points at
+    /// the furthest position the parser reached, which is often more precise
+    /// than the position Pest reports, or at the start of a syntax error's
+    /// span. Errors without a position are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use carbon_parser::parse_carbon;
+    ///
+    /// let source = "fn main() { }\nfn broken { }";
+    /// let error = parse_carbon(source).unwrap_err().with_source(source);
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "expected `(`\n --> 2:11\n  |\n2 | fn broken { }\n  |           ^"
+    /// );
+    /// assert_eq!(error.location(), Some((2, 11)));
+    /// ```
+    pub fn with_source(self, source: &str) -> ParseError {
+        let offset = match &self {
+            ParseError::PestError(_) => self.offset().unwrap_or(0),
+            ParseError::SyntaxError {
+                span: Some(span), ..
+            } => span.start,
+            ParseError::WithSource { .. } | ParseError::SyntaxError { .. } | ParseError::Io(_) => {
+                return self
+            }
+        };
+        let LineColumn { line, column } = SpanResolver::new(source).line_column(offset);
+        let text = source.lines().nth(line - 1).unwrap_or("");
+        self.pointing_at(offset, line, column, text)
+    }
+
+    /// Wraps the error in a [`ParseError::WithSource`] whose snippet shows
+    /// `text` as line `line`, with a `^` under `column`.
+    pub(crate) fn pointing_at(
+        self,
+        offset: usize,
+        line: usize,
+        column: usize,
+        text: &str,
+    ) -> ParseError {
+        let gutter = " ".repeat(line.to_string().len());
+        let snippet = format!(
+            "{gutter}--> {line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {}^",
+            " ".repeat(column - 1)
+        );
+        ParseError::WithSource {
+            message: self.summary(),
+            line,
+            column,
+            offset,
+            snippet,
+            source: Box::new(self),
+        }
+    }
+
+    /// The message of the error without its position, such as
+    /// ``expected `(` ``.
+    pub(crate) fn summary(&self) -> String {
+        match self {
+            ParseError::PestError(e) => match self.expected().as_slice() {
+                [] => e.variant.message().into_owned(),
+                [token] => format!("expected `{token}`"),
+                tokens => format!("expected one of `{}`", tokens.join("`, `")),
+            },
+            ParseError::WithSource { message, .. } | ParseError::SyntaxError { message, .. } => {
+                message.clone()
+            }
+            ParseError::Io(e) => e.to_string(),
+        }
+    }
+
+    /// Byte offset of the furthest position the parser reached, if known.
+    pub(crate) fn offset(&self) -> Option<usize> {
+        match self {
+            ParseError::PestError(e) => Some(match e.parse_attempts() {
+                Some(attempts) => attempts.max_position,
+                None => match e.location {
+                    pest::error::InputLocation::Pos(offset)
+                    | pest::error::InputLocation::Span((offset, _)) => offset,
+                },
+            }),
+            ParseError::WithSource { offset, .. } => Some(*offset),
+            ParseError::SyntaxError { span, .. } => span.map(|span| span.start),
+            ParseError::Io(_) => None,
+        }
+    }
 }
 
 /// Result type for parsing operations.
@@ -483,6 +1149,48 @@ pub enum ParseError {
 /// throughout the library. All parsing functions return this type.
 pub type ParseResult<T> = Result<T, ParseError>;
 
+/// An error attributed to a named source file.
+///
+/// The `Display` output starts with `filename:line:col:`, the location format
+/// used by compilers and understood by most editors and terminals.
+#[derive(Error, Debug)]
+pub enum CarbonError {
+    /// The file's contents are not valid Carbon.
+    ///
+    /// Errors without a position of their own are reported at `1:1`.
+    #[error("{filename}:{line}:{column}: {message}")]
+    Parse {
+        filename: String,
+        /// 1-based line of the error.
+        line: usize,
+        /// 1-based column of the error.
+        column: usize,
+        message: String,
+        #[source]
+        source: Box<ParseError>,
+    },
+}
+
+impl CarbonError {
+    fn parse(filename: &str, error: ParseError) -> Self {
+        let (line, column) = error.location().unwrap_or((1, 1));
+        let message = match &error {
+            ParseError::PestError(e) => e.variant.message().into_owned(),
+            ParseError::WithSource { message, .. } | ParseError::SyntaxError { message, .. } => {
+                message.clone()
+            }
+            ParseError::Io(e) => e.to_string(),
+        };
+        CarbonError::Parse {
+            filename: filename.to_string(),
+            line,
+            column,
+            message,
+            source: Box::new(error),
+        }
+    }
+}
+
 /// Parses a complete Carbon program.
 ///
 /// This is the main entry point for parsing Carbon source code. It expects
@@ -566,49 +1274,303 @@ pub type ParseResult<T> = Result<T, ParseError>;
 ///     Err(e) => println!("Expected error: {}", e),
 /// }
 /// ```
-pub fn parse_carbon(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
-    CarbonParser::parse(Rule::program, input).map_err(ParseError::from)
+pub fn parse_carbon(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_rule(Rule::program, input)
 }
 
-/// Parses a single function declaration.
+/// Reads the file at `path` into `source` and parses it as a program.
 ///
-/// Use this function when you need to parse an individual function definition
-/// in isolation, without requiring a complete program context. This is useful
-/// for testing, code generation, incremental parsing, or working with code fragments.
+/// The returned pairs borrow the text of the file, so `source` has to outlive
+/// them; any previous contents of `source` are replaced. A file that cannot
+/// be read is reported as [`ParseError::Io`].
 ///
-/// # Carbon Function Syntax
+/// # Examples
 ///
-/// A function declaration in Carbon has the following structure:
+/// ```rust
+/// use carbon_parser::{parse_carbon_file, ParseError};
 ///
-/// ```text
-/// fn function_name(param1: Type1, param2: Type2) -> ReturnType {
-///     // function body statements
-///     return expression;
-/// }
+/// let mut source = String::new();
+/// let error = parse_carbon_file("missing.carbon", &mut source).unwrap_err();
+/// assert!(matches!(error, ParseError::Io(_)));
 /// ```
+pub fn parse_carbon_file(
+    path: impl AsRef<Path>,
+    source: &mut String,
+) -> ParseResult<Pairs<'_, Rule>> {
+    *source = std::fs::read_to_string(path)?;
+    parse_carbon(source)
+}
+
+/// Parses a complete Carbon program into a typed AST.
 ///
-/// Components:
-/// - `fn` keyword to start the declaration
-/// - Function name (identifier)
-/// - Parameter list in parentheses (may be empty)
-/// - Optional return type preceded by `->`
-/// - Function body in braces
+/// This runs [`parse_carbon`] and converts the resulting parse tree into an
+/// [`ast::Program`], whose nodes are owned Rust types carrying their source
+/// spans. Prefer this over the raw Pest pairs when you want to inspect the
+/// structure of a program rather than its grammar rules.
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice containing exactly one function declaration
+/// * `input` - The complete Carbon source code as a string slice
 ///
 /// # Returns
 ///
-/// Returns a `ParseResult` containing the parsed function declaration tree.
-///
-/// # Grammar Rule
-///
-/// This function uses the `function_decl` grammar rule from `carbon.pest`.
+/// Returns the program's AST on success, or a `ParseError` if the input is
+/// not syntactically valid.
 ///
 /// # Examples
 ///
-/// ## Function Without Parameters
+/// ```rust
+/// use carbon_parser::ast::{Expr, Item};
+/// use carbon_parser::parse_to_ast;
+///
+/// let program = parse_to_ast("var x: i32 = 1 + 2 * 3;").unwrap();
+/// let Item::Var(decl) = &program.items[0] else {
+///     panic!("expected a variable declaration");
+/// };
+/// assert_eq!(decl.name.name, "x");
+/// assert_eq!(decl.ty.name, "i32");
+/// assert!(matches!(decl.init, Some(Expr::Binary(_))));
+/// ```
+pub fn parse_to_ast(input: &str) -> ParseResult<ast::Program> {
+    let program = parse_carbon(input)?
+        .next()
+        .expect("the program rule always produces a single pair");
+    Ok(ast::Program::from(program))
+}
+
+/// Parses a complete program into an AST, enforcing `options`.
+///
+/// The limits are checked on the parse tree before any AST strings are
+/// allocated, so an oversized identifier or literal is rejected cheaply. A
+/// violated limit is reported as [`ParseError::SyntaxError`] naming the
+/// offending line and column, with the span of the identifier or literal.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{parse_to_ast_with_options, ParseError, ParseOptions};
+///
+/// let options = ParseOptions {
+///     max_identifier_len: Some(8),
+///     ..ParseOptions::default()
+/// };
+/// assert!(parse_to_ast_with_options("var short: i32;", &options).is_ok());
+///
+/// let err = parse_to_ast_with_options("var much_too_long: i32;", &options).unwrap_err();
+/// assert!(matches!(err, ParseError::SyntaxError { span: Some(_), .. }));
+/// ```
+pub fn parse_to_ast_with_options(input: &str, options: &ParseOptions) -> ParseResult<ast::Program> {
+    let rule = if options.script_mode {
+        Rule::script
+    } else {
+        Rule::program
+    };
+    let program = parse_rule(rule, input)?
+        .next()
+        .expect("the program rule always produces a single pair");
+
+    for pair in program.clone().into_inner().flatten() {
+        let (kind, limit) = match pair.as_rule() {
+            Rule::identifier => ("identifier", options.max_identifier_len),
+            Rule::literal => ("literal", options.max_literal_len),
+            _ => continue,
+        };
+        let len = pair.as_str().len();
+        if let Some(limit) = limit.filter(|&limit| len > limit) {
+            return Err(ParseError::syntax_at(
+                format!("{kind} is {len} bytes long, the limit is {limit}"),
+                pair.as_span().into(),
+            ));
+        }
+    }
+
+    let mut program = ast::Program::from(program);
+    if options.desugar_comparison_chains {
+        ast::for_each_root_expr_mut(&mut program, &mut expr::desugar_comparison_chains);
+    }
+    Ok(program)
+}
+
+/// Parses a single expression into an [`ast::Expr`].
+///
+/// Binary operators are nested by precedence and associate to the left, as
+/// described in the [`expr`] module, so the tree can be evaluated or printed
+/// without consulting the grammar.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::{BinaryOp, Expr};
+/// use carbon_parser::parse_expression_ast;
+///
+/// let Expr::Binary(add) = parse_expression_ast("1 + 2 * 3").unwrap() else {
+///     unreachable!()
+/// };
+/// assert_eq!(add.op, BinaryOp::Add);
+/// assert!(matches!(*add.rhs, Expr::Binary(ref mul) if mul.op == BinaryOp::Mul));
+/// ```
+pub fn parse_expression_ast(input: &str) -> ParseResult<ast::Expr> {
+    let expression = parse_expression(input)?
+        .next()
+        .expect("the expression rule always produces a single pair");
+    Ok(ast::Expr::from(expression))
+}
+
+/// Parses a single expression like [`parse_expression_ast`], nesting binary
+/// operators by `precedence` instead of the built-in levels.
+///
+/// Parenthesized subexpressions keep their grouping whatever the table says.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::{BinaryOp, Expr};
+/// use carbon_parser::expr::PrecedenceTable;
+/// use carbon_parser::parse_expression_with;
+///
+/// // Make `+` bind tighter than `*`.
+/// let table = PrecedenceTable::default()
+///     .with_level(BinaryOp::Add, 7)
+///     .with_level(BinaryOp::Sub, 7);
+/// let Expr::Binary(mul) = parse_expression_with("1 + 2 * 3", &table).unwrap() else {
+///     unreachable!()
+/// };
+/// assert_eq!(mul.op, BinaryOp::Mul);
+/// assert!(matches!(*mul.lhs, Expr::Binary(ref add) if add.op == BinaryOp::Add));
+/// ```
+pub fn parse_expression_with(
+    input: &str,
+    precedence: &expr::PrecedenceTable,
+) -> ParseResult<ast::Expr> {
+    let mut expr = parse_expression_ast(input)?;
+    expr::reassociate(&mut expr, precedence);
+    Ok(expr)
+}
+
+/// Parses a complete program read from `filename`.
+///
+/// This behaves like [`parse_to_ast`], but errors name the file they came
+/// from. Use it when the source text was loaded from disk or another named
+/// location, so messages can point users at the right file.
+///
+/// # Arguments
+///
+/// * `input` - The complete Carbon source code as a string slice
+/// * `filename` - The name to report in errors; it is not opened or read
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_with_filename;
+///
+/// let err = parse_with_filename("fn main( { }", "main.carbon").unwrap_err();
+/// assert!(err.to_string().starts_with("main.carbon:1:10:"));
+/// ```
+pub fn parse_with_filename(input: &str, filename: &str) -> Result<ast::Program, CarbonError> {
+    parse_to_ast(input).map_err(|error| CarbonError::parse(filename, error))
+}
+
+/// Parses `input` with one of the anchored `*_entry` rules.
+///
+/// Entry rules wrap a single construct in `SOI ~ ... ~ EOI`, so surrounding
+/// whitespace and comments are accepted while trailing garbage is rejected.
+/// The returned pairs are the construct itself followed by `EOI`.
+fn parse_entry(rule: Rule, input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    let entry = parse_rule(rule, input)?
+        .next()
+        .expect("entry rules always produce a single pair");
+    Ok(entry.into_inner())
+}
+
+/// Parses `input` with `rule`.
+///
+/// [`ParseError::expected`] reports tokens only if Pest recorded every token
+/// it tried, which it does while its error detail switch is on. Recording
+/// slows parsing down, so the switch stays off and only a failed parse is
+/// repeated with it on, through [`ErrorDetail`].
+fn parse_rule(rule: Rule, input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    CarbonParser::parse(rule, input)
+        .or_else(|_| {
+            let _detail = ErrorDetail::on();
+            CarbonParser::parse(rule, input)
+        })
+        .map_err(ParseError::PestError)
+}
+
+/// Keeps Pest's error detail switch on while it is alive.
+///
+/// The switch is shared by every Pest grammar in the process, so it is on
+/// only while a failed parse is being repeated. Guards are counted, so a
+/// thread that finishes does not turn the switch off under another one that
+/// is still repeating its parse.
+struct ErrorDetail;
+
+static ERROR_DETAIL_GUARDS: Mutex<usize> = Mutex::new(0);
+
+impl ErrorDetail {
+    fn on() -> Self {
+        let mut guards = ERROR_DETAIL_GUARDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if *guards == 0 {
+            pest::set_error_detail(true);
+        }
+        *guards += 1;
+        ErrorDetail
+    }
+}
+
+impl Drop for ErrorDetail {
+    fn drop(&mut self) {
+        let mut guards = ERROR_DETAIL_GUARDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *guards -= 1;
+        if *guards == 0 {
+            pest::set_error_detail(false);
+        }
+    }
+}
+
+/// Parses a single function declaration.
+///
+/// Use this function when you need to parse an individual function definition
+/// in isolation, without requiring a complete program context. This is useful
+/// for testing, code generation, incremental parsing, or working with code fragments.
+///
+/// # Carbon Function Syntax
+///
+/// A function declaration in Carbon has the following structure:
+///
+/// ```text
+/// fn function_name(param1: Type1, param2: Type2) -> ReturnType {
+///     // function body statements
+///     return expression;
+/// }
+/// ```
+///
+/// Components:
+/// - `fn` keyword to start the declaration
+/// - Function name (identifier)
+/// - Parameter list in parentheses (may be empty)
+/// - Optional return type preceded by `->`
+/// - Function body in braces
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one function declaration
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed function declaration tree.
+///
+/// # Grammar Rule
+///
+/// This function uses the `function_decl` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ## Function Without Parameters
 ///
 /// ```rust
 /// use carbon_parser::parse_function_decl;
@@ -657,8 +1619,107 @@ pub fn parse_carbon(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
 /// let result = parse_function_decl(code);
 /// assert!(result.is_ok());
 /// ```
-pub fn parse_function_decl(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
-    CarbonParser::parse(Rule::function_decl, input).map_err(ParseError::from)
+pub fn parse_function_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::function_decl_entry, input)
+}
+
+/// Parses a class declaration.
+///
+/// A class has a name, optional generic parameters written as compile-time
+/// parameters (`T:! type`), and a body of field declarations and methods.
+/// Fields use the same syntax as variable declarations and methods the same
+/// syntax as function declarations.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one class declaration
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed class.
+///
+/// # Grammar Rule
+///
+/// This function uses the `class_decl` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ## Fields Only
+///
+/// ```rust
+/// use carbon_parser::parse_class_decl;
+///
+/// assert!(parse_class_decl("class Point { var x: i32; var y: i32; }").is_ok());
+/// ```
+///
+/// ## Generic Class with a Method
+///
+/// ```rust
+/// use carbon_parser::parse_class_decl;
+///
+/// let code = r#"
+///     class Box(T:! type) {
+///         var value: T;
+///         fn Get() -> T { return value; }
+///     }
+/// "#;
+/// assert!(parse_class_decl(code).is_ok());
+/// ```
+pub fn parse_class_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::class_decl_entry, input)
+}
+
+/// Parses a single class member into an [`ast::ClassMember`].
+///
+/// A member is a field written as a variable declaration, a method written as
+/// a function declaration (including `async` and `throws`), or an index
+/// operator. This lets tools that edit one member at a time re-parse just that
+/// member instead of the whole class.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::ClassMember;
+/// use carbon_parser::parse_class_member;
+///
+/// let member = parse_class_member("var size: i32;").unwrap();
+/// assert!(matches!(member, ClassMember::Field(ref field) if field.name.name == "size"));
+///
+/// let member = parse_class_member("async fn Load(self: Self) -> i32 { return 0; }").unwrap();
+/// assert!(matches!(member, ClassMember::Method(ref method) if method.is_async));
+///
+/// assert!(parse_class_member("size + 1").is_err());
+/// ```
+pub fn parse_class_member(input: &str) -> ParseResult<ast::ClassMember> {
+    let member = parse_entry(Rule::class_member_entry, input)?
+        .next()
+        .expect("the class member rule always produces a single pair");
+    Ok(ast::ClassMember::from(member))
+}
+
+/// Parses a bracketed list of type parameters, such as `[T:! Comparable, U]`,
+/// on its own.
+///
+/// Each parameter is `Name:! Constraint`, or a bare `Name`, which is short for
+/// `Name:! type`. The list must have at least one parameter.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_type_params;
+///
+/// let params = parse_type_params("[T:! Comparable, U]").unwrap();
+/// let names: Vec<_> = params.iter().map(|param| param.name.name.as_str()).collect();
+/// assert_eq!(names, ["T", "U"]);
+/// assert_eq!(params[1].ty.name, "type");
+///
+/// assert!(parse_type_params("[]").is_err());
+/// ```
+pub fn parse_type_params(input: &str) -> ParseResult<Vec<ast::GenericParam>> {
+    let list = parse_entry(Rule::type_params_entry, input)?
+        .next()
+        .expect("the type parameter list rule always produces a single pair");
+    Ok(list.into_inner().map(ast::GenericParam::from).collect())
 }
 
 /// Parses a variable declaration statement.
@@ -745,8 +1806,79 @@ pub fn parse_function_decl(input: &str) -> ParseResult<pest::iterators::Pairs<Ru
 /// let result = parse_var_decl(code);
 /// assert!(result.is_ok());
 /// ```
-pub fn parse_var_decl(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
-    CarbonParser::parse(Rule::var_decl, input).map_err(ParseError::from)
+pub fn parse_var_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::var_decl_entry, input)
+}
+
+/// Parses an immutable `let` binding.
+///
+/// A `let` binding looks like a variable declaration, but its value cannot be
+/// changed later, so the initializer is required.
+///
+/// # Grammar Rule
+///
+/// This function uses the `let_decl` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_let_decl;
+///
+/// assert!(parse_let_decl("let x: i32 = 5;").is_ok());
+/// assert!(parse_let_decl("let limit: i64 = 10 * 1024;").is_ok());
+///
+/// // Unlike `var`, a `let` binding must be initialized.
+/// assert!(parse_let_decl("let x: i32;").is_err());
+/// ```
+pub fn parse_let_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::let_decl_entry, input)
+}
+
+/// Parses a package declaration.
+///
+/// A file may start with one, naming its package, optionally a library in
+/// it, and whether the file is the package's `api` or an `impl` file. In a
+/// complete program it must come before every other declaration.
+///
+/// # Grammar Rule
+///
+/// This function uses the `package_decl` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_package_decl;
+///
+/// assert!(parse_package_decl("package Geometry api;").is_ok());
+/// assert!(parse_package_decl(r#"package Geometry library "Shapes" impl;"#).is_ok());
+///
+/// // The `api` or `impl` keyword is required.
+/// assert!(parse_package_decl("package Geometry;").is_err());
+/// ```
+pub fn parse_package_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::package_decl_entry, input)
+}
+
+/// Parses an import declaration.
+///
+/// Imports follow the package declaration, if there is one, and come before
+/// the other declarations of a program.
+///
+/// # Grammar Rule
+///
+/// This function uses the `import_decl` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_import_decl;
+///
+/// assert!(parse_import_decl("import Math;").is_ok());
+/// assert!(parse_import_decl(r#"import Geometry library "Shapes";"#).is_ok());
+/// assert!(parse_import_decl("import Math").is_err());
+/// ```
+pub fn parse_import_decl(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::import_decl_entry, input)
 }
 
 /// Parses an expression.
@@ -757,7 +1889,7 @@ pub fn parse_var_decl(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>>
 ///
 /// # Supported Expression Types
 ///
-/// - **Literals**: Integer, float, boolean, and string constants
+/// - **Literals**: Integer, float, boolean, string, and character constants
 /// - **Identifiers**: Variable and function names
 /// - **Binary Operations**: Arithmetic, comparison, and logical operators
 /// - **Function Calls**: Invocations with argument lists
@@ -851,8 +1983,8 @@ pub fn parse_var_decl(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>>
 /// assert!(parse_expression("x < y").is_ok());
 /// assert!(parse_expression("x > y").is_ok());
 /// ```
-pub fn parse_expression(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
-    CarbonParser::parse(Rule::expression, input).map_err(ParseError::from)
+pub fn parse_expression(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::expression_entry, input)
 }
 
 /// Parses a type name.
@@ -868,6 +2000,7 @@ pub fn parse_expression(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>
 /// - **Floating-Point**: `f32`, `f64`
 /// - **Boolean**: `bool`
 /// - **String**: `String`
+/// - **Types**: `Type`, the type of types themselves
 ///
 /// # Custom Types
 ///
@@ -890,11 +2023,16 @@ pub fn parse_expression(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>
 ///
 /// ## Integer Types
 ///
+/// Signed `i8`, `i16`, `i32` and `i64`, and unsigned `u8` through `u64`.
+/// Other widths are rejected rather than read as custom type names.
+///
 /// ```rust
 /// use carbon_parser::parse_type_name;
 ///
 /// assert!(parse_type_name("i32").is_ok());
 /// assert!(parse_type_name("i64").is_ok());
+/// assert!(parse_type_name("u16").is_ok());
+/// assert!(parse_type_name("i17").is_err());
 /// ```
 ///
 /// ## Float Types
@@ -932,8 +2070,254 @@ pub fn parse_expression(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>
 /// let result = parse_type_name("CustomType");
 /// assert!(result.is_ok());
 /// ```
-pub fn parse_type_name(input: &str) -> ParseResult<pest::iterators::Pairs<Rule>> {
-    CarbonParser::parse(Rule::type_name, input).map_err(ParseError::from)
+///
+/// ## Qualified Types
+///
+/// ```rust
+/// use carbon_parser::parse_type_name;
+///
+/// assert!(parse_type_name("const i32").is_ok());
+/// assert!(parse_type_name("mut String").is_ok());
+///
+/// // A pointer to a pointer to `CustomType`.
+/// assert!(parse_type_name("CustomType**").is_ok());
+///
+/// // A `const` pointer to `i32`.
+/// assert!(parse_type_name("i32*const").is_ok());
+/// ```
+///
+/// ## Generic Types
+///
+/// Carbon writes generic arguments in parentheses:
+///
+/// ```rust
+/// use carbon_parser::parse_type_name;
+///
+/// assert!(parse_type_name("Vector(i32)").is_ok());
+/// assert!(parse_type_name("HashMap(String, Vector(i32))").is_ok());
+/// assert!(parse_type_name("Vector()").is_err());
+/// ```
+///
+/// ## Array and Slice Types
+///
+/// ```rust
+/// use carbon_parser::parse_type_name;
+///
+/// assert!(parse_type_name("[i32; 4]").is_ok());
+/// assert!(parse_type_name("[[i32; 2]; 3]").is_ok());
+///
+/// // An array of pointers and a pointer to an array.
+/// assert!(parse_type_name("[i32*; 4]").is_ok());
+/// assert!(parse_type_name("[i32; 4]*").is_ok());
+///
+/// // A slice has no size.
+/// assert!(parse_type_name("[i32]").is_ok());
+/// assert!(parse_type_name("[i32;]").is_err());
+/// ```
+///
+/// Any expression is accepted as a size by the grammar. [`check_program`]
+/// reports a size that is not a compile-time constant as an error and an empty
+/// array such as `[u8; 0]` as a warning.
+///
+/// ## Types as Values
+///
+/// `Type` is the type of types, so built-in types can also be used as values:
+///
+/// ```rust
+/// use carbon_parser::parse_var_decl;
+///
+/// assert!(parse_var_decl("var t: Type = i32;").is_ok());
+/// ```
+pub fn parse_type_name(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::type_name_entry, input)
+}
+
+/// Parses a single `if` statement.
+///
+/// The condition is either a boolean expression or a `var` binding. The
+/// `else` branch is optional and may itself be another `if`, which gives
+/// `else if` chains. Because every branch is a braced block, an `else` always
+/// belongs to the `if` whose block it directly follows.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one `if` statement
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed statement.
+///
+/// # Grammar Rule
+///
+/// This function uses the `if_statement` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ## Without `else`
+///
+/// ```rust
+/// use carbon_parser::parse_if_statement;
+///
+/// assert!(parse_if_statement("if (x > 0) { return x; }").is_ok());
+/// ```
+///
+/// ## `else if` Chain
+///
+/// ```rust
+/// use carbon_parser::parse_if_statement;
+///
+/// let code = r#"
+///     if (x < 0) {
+///         return -1;
+///     } else if (x == 0) {
+///         return 0;
+///     } else {
+///         return 1;
+///     }
+/// "#;
+/// assert!(parse_if_statement(code).is_ok());
+/// ```
+pub fn parse_if_statement(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::if_statement_entry, input)
+}
+
+/// Parses a single `while` loop.
+///
+/// The loop condition is a parenthesized expression and the body is a block,
+/// which may contain any statements, including nested loops and variable
+/// declarations.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one `while` loop
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed loop.
+///
+/// # Grammar Rule
+///
+/// This function uses the `while_statement` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_while_statement;
+///
+/// let code = r#"
+///     while (i < n) {
+///         var next: i32 = step(i);
+///         consume(next);
+///     }
+/// "#;
+/// assert!(parse_while_statement(code).is_ok());
+/// ```
+pub fn parse_while_statement(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::while_statement_entry, input)
+}
+
+/// Parses a single `for` loop over a collection.
+///
+/// The loop header declares the loop variable with its type, followed by `in`
+/// and the expression being iterated: `for (var x: i32 in items) { ... }`.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one `for` loop
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed loop.
+///
+/// # Grammar Rule
+///
+/// This function uses the `for_statement` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_for_statement;
+///
+/// assert!(parse_for_statement("for (var x: i32 in items) { print(x); }").is_ok());
+/// assert!(parse_for_statement("for (var x: i32 in range(0, 10)) { }").is_ok());
+///
+/// // The `in` clause is required.
+/// assert!(parse_for_statement("for (var x: i32) { }").is_err());
+/// ```
+pub fn parse_for_statement(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::for_statement_entry, input)
+}
+
+/// Parses a single assignment statement.
+///
+/// The target is a variable name, followed by `=` or one of the compound
+/// operators `+=`, `-=`, `*=` and `/=`, the new value, and a semicolon.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one assignment
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed assignment.
+///
+/// # Grammar Rule
+///
+/// This function uses the `assignment` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_assignment;
+///
+/// assert!(parse_assignment("x = x + 1;").is_ok());
+/// assert!(parse_assignment("total += price(item);").is_ok());
+///
+/// // The semicolon is required.
+/// assert!(parse_assignment("x = 1").is_err());
+/// ```
+pub fn parse_assignment(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::assignment_entry, input)
+}
+
+/// Parses a single statement of any kind.
+///
+/// Accepts everything a block may contain: `var` and `let` declarations,
+/// assignments, `return`, `throw`, `if`, `while`, `for` and `match`
+/// statements, and expression statements. As on a REPL line, an expression
+/// may also stand on its own without the closing semicolon.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing exactly one statement
+///
+/// # Returns
+///
+/// Returns a `ParseResult` containing the parsed statement. Its single pair is
+/// a `statement`, wrapping the rule of the specific kind, or a `tail_expr` for
+/// an expression without a semicolon.
+///
+/// # Grammar Rule
+///
+/// This function uses the `statement` grammar rule from `carbon.pest`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{parse_statement, Rule};
+///
+/// let statement = parse_statement("while (i < n) { i += 1; }").unwrap().next().unwrap();
+/// let kind = statement.into_inner().next().unwrap();
+/// assert_eq!(kind.as_rule(), Rule::while_statement);
+///
+/// assert!(parse_statement("print(x);").is_ok());
+/// assert!(parse_statement("x + 1").is_ok());
+///
+/// // Only one statement is allowed.
+/// assert!(parse_statement("x = 1; y = 2;").is_err());
+/// ```
+pub fn parse_statement(input: &str) -> ParseResult<Pairs<'_, Rule>> {
+    parse_entry(Rule::statement_entry, input)
 }
 
 #[cfg(test)]
@@ -1,6 +1,12 @@
-use carbon_parser::{parse_carbon, ParseError};
-use clap::{Parser, Subcommand};
+use carbon_parser::dot::to_dot;
+use carbon_parser::json::to_json;
+use carbon_parser::{
+    parse_carbon, parse_expression, parse_statement, render_diagnostic, render_diagnostic_colored,
+    ParseError, Rule,
+};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -11,6 +17,10 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print parse errors without colors
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -21,17 +31,51 @@ enum Commands {
 
         #[arg(short, long)]
         verbose: bool,
+
+        /// Output format: `text` prints a summary (and the tree with
+        /// `--verbose`), `json` prints only the parse tree as JSON, `dot`
+        /// prints it as a Graphviz graph
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
+    /// Read lines from standard input and print the parse tree of each one,
+    /// parsed as a statement if it ends in `;` or `}` and as an expression
+    /// otherwise, until end of input
+    Repl,
+
     Authors,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+    Dot,
+}
+
 fn main() {
     let cli = Cli::parse();
+    let color = !cli.no_color && io::stderr().is_terminal();
 
     match cli.command {
-        Commands::Parse { file, verbose } => {
-            if let Err(e) = parse_file(&file, verbose) {
+        Commands::Parse {
+            file,
+            verbose,
+            format,
+        } => {
+            if let Err(e) = parse_file(&file, verbose, format, color) {
+                match e.downcast_ref::<ParseError>() {
+                    Some(ParseError::Io(io)) => {
+                        eprintln!("Error: failed to read file '{}': {}", file.display(), io)
+                    }
+                    _ => eprintln!("Error: {}", e),
+                }
+                std::process::exit(1);
+            }
+        }
+        Commands::Repl => {
+            if let Err(e) = repl(color) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
@@ -45,14 +89,22 @@ fn main() {
     }
 }
 
-fn parse_file(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path).map_err(|e| {
-        format!(
-            "Failed to read file '{}': {}",
-            path.display(),
-            e
-        )
-    })?;
+fn parse_file(
+    path: &PathBuf,
+    verbose: bool,
+    format: OutputFormat,
+    color: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path).map_err(ParseError::Io)?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&to_json(&content)?)?);
+        return Ok(());
+    }
+    if format == OutputFormat::Dot {
+        print!("{}", to_dot(&content)?);
+        return Ok(());
+    }
 
     println!("Parsing file: {}", path.display());
     println!("Size: {} bytes", content.len());
@@ -72,9 +124,9 @@ fn parse_file(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::E
 
             Ok(())
         }
-        Err(ParseError::PestError(e)) => {
+        Err(e @ ParseError::PestError(_)) => {
             println!("Parse error:\n");
-            eprintln!("{}", e);
+            eprint!("{}", render(&content, &e, color));
             Err(Box::new(e))
         }
         Err(e) => {
@@ -83,6 +135,49 @@ fn parse_file(path: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::E
         }
     }
 }
+
+fn render(source: &str, err: &ParseError, color: bool) -> String {
+    if color {
+        render_diagnostic_colored(source, err)
+    } else {
+        render_diagnostic(source, err)
+    }
+}
+
+fn repl(color: bool) -> io::Result<()> {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let line = line?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        let parsed = if input.ends_with(';') || input.ends_with('}') {
+            parse_statement(input)
+        } else {
+            parse_expression(input)
+        };
+        match parsed {
+            Ok(pairs) => {
+                for pair in pairs.filter(|pair| pair.as_rule() != Rule::EOI) {
+                    print_pair(pair, 0);
+                }
+            }
+            Err(e) => print!("{}", render(input, &e, color && io::stdout().is_terminal())),
+        }
+    }
+}
+
 fn print_pair(pair: pest::iterators::Pair<carbon_parser::Rule>, indent: usize) {
     let indent_str = "  ".repeat(indent);
     println!(
@@ -95,4 +190,4 @@ fn print_pair(pair: pest::iterators::Pair<carbon_parser::Rule>, indent: usize) {
     for inner_pair in pair.into_inner() {
         print_pair(inner_pair, indent + 1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,43 @@
+//! Settings that control how input is parsed.
+
+/// Options for [`parse_to_ast_with_options`](crate::parse_to_ast_with_options).
+///
+/// Every limit defaults to `None`, meaning unlimited, and every rewrite is off,
+/// so `ParseOptions::default()` accepts and returns exactly what
+/// [`parse_to_ast`](crate::parse_to_ast) does. Set the fields you need and
+/// leave the rest at their defaults:
+///
+/// ```rust
+/// use carbon_parser::ParseOptions;
+///
+/// let options = ParseOptions {
+///     max_identifier_len: Some(64),
+///     ..ParseOptions::default()
+/// };
+/// assert_eq!(options.max_literal_len, None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// Longest identifier accepted, in bytes.
+    pub max_identifier_len: Option<usize>,
+    /// Longest literal accepted, in bytes of source text. String literals
+    /// count their quotes.
+    pub max_literal_len: Option<usize>,
+    /// Rewrite chained comparisons such as `a < b < c` into `a < b && b < c`.
+    ///
+    /// Without this option the chain is parsed like any other left-associative
+    /// operators, as `(a < b) < c`, which compares a `bool` with `c`. Only
+    /// unparenthesized chains of `<`, `>`, `<=` and `>=` are rewritten.
+    ///
+    /// In the rewritten tree the middle operand appears twice. Both copies
+    /// have the same span, and evaluators should treat them as one value and
+    /// evaluate it only once, so that `a < next() < c` calls `next` once.
+    pub desugar_comparison_chains: bool,
+    /// Accept statements such as assignments, loops and expression statements
+    /// at the top level, between the declarations.
+    ///
+    /// They are collected in [`Program::top_level`](crate::ast::Program::top_level)
+    /// together with the declarations, in source order. Without this option a
+    /// top-level statement is a syntax error.
+    pub script_mode: bool,
+}
@@ -0,0 +1,16 @@
+//! The commonly used parts of the public API in one place.
+//!
+//! ```rust
+//! use carbon_parser::prelude::*;
+//!
+//! assert!(parse_carbon("fn main() { }").is_ok());
+//!
+//! let program: Program = parse_to_ast("var x: i32 = 1;").unwrap();
+//! assert!(matches!(&program.items[0], Item::Var(decl) if decl.ty.name == "i32"));
+//!
+//! let expr: ParseResult<Expr> = parse_expression_ast("1 + 2");
+//! assert!(matches!(expr, Ok(Expr::Binary(_))));
+//! ```
+
+pub use crate::ast::{walk_program, Expr, Item, Program, Stmt, TypeName, Visitor};
+pub use crate::{parse_carbon, parse_expression_ast, parse_to_ast, ParseError, ParseResult};
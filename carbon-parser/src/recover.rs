@@ -0,0 +1,102 @@
+//! Best-effort error recovery for tools that want every syntax error at once.
+//!
+//! Pest stops at the first error. [`parse_carbon_recover`] works around this
+//! by blanking out the statement that failed and parsing again, so the errors
+//! after it are reported too.
+
+use std::ops::Range;
+
+use crate::ast::Program;
+use crate::{parse_to_ast, ParseError};
+
+/// Parses `input`, collecting one error per broken statement instead of
+/// stopping at the first.
+///
+/// When parsing fails, the text from the start of the failing statement up to
+/// the next `;` (inclusive) or the `}` closing the enclosing block (exclusive)
+/// is replaced by spaces and the input is parsed again. A brace opened after the
+/// error ends the statement where it closes, so a broken header drops its whole
+/// body. Line breaks are kept, so the errors and the returned [`Program`] use
+/// the line, column and byte positions of the original `input`.
+///
+/// Recovery is best-effort. Statement boundaries are found by looking at the
+/// raw characters, so a `;` or brace inside a string literal or comment can
+/// end a skipped region early, and one mistake may be reported more than once.
+/// The program is `None` when the remaining input could not be repaired, for
+/// example when a closing `}` is missing at the end of the file; otherwise it
+/// holds every declaration and statement that survived recovery.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_carbon_recover;
+///
+/// let src = "fn main() {\n  var x: i32 = ;\n  var y: i32 = 2;\n  y = * 3;\n}";
+/// let (program, errors) = parse_carbon_recover(src);
+///
+/// let lines: Vec<_> = errors.iter().filter_map(|e| e.location()).map(|(line, _)| line).collect();
+/// assert_eq!(lines, [2, 4]);
+/// assert!(program.is_some());
+/// ```
+pub fn parse_carbon_recover(input: &str) -> (Option<Program>, Vec<ParseError>) {
+    let mut source = input.as_bytes().to_vec();
+    let mut errors = Vec::new();
+    loop {
+        let text =
+            std::str::from_utf8(&source).expect("blanking whole characters keeps UTF-8 valid");
+        let err = match parse_to_ast(text) {
+            Ok(program) => return (Some(program), errors),
+            Err(err) => err,
+        };
+        let skip = err.offset().and_then(|offset| skip_range(&source, offset));
+        errors.push(err);
+        let Some(range) = skip else {
+            return (None, errors);
+        };
+        for byte in &mut source[range] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+}
+
+/// The region to drop for an error at `offset`, or `None` if there is nothing
+/// left to drop.
+fn skip_range(source: &[u8], offset: usize) -> Option<Range<usize>> {
+    let start = source[..offset]
+        .iter()
+        .rposition(|b| matches!(b, b';' | b'{' | b'}'))
+        .map_or(0, |i| i + 1);
+
+    let mut end = source.len();
+    let mut depth = 0usize;
+    for (i, byte) in source.iter().enumerate().skip(offset) {
+        match (byte, depth) {
+            (b'{', _) => depth += 1,
+            (b'}', 0) => {
+                end = i;
+                break;
+            }
+            (b'}', 1) => {
+                end = i + 1;
+                break;
+            }
+            (b'}', _) => depth -= 1,
+            (b';', 0) => {
+                end = i + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    if source[start..end].iter().any(|b| !b.is_ascii_whitespace()) {
+        Some(start..end)
+    } else if source.get(end) == Some(&b'}') {
+        // Nothing precedes the brace, so the brace itself is the stray token.
+        Some(start..end + 1)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,58 @@
+//! Rendering parse errors the way compilers show them.
+//!
+//! [`render_diagnostic`] prints an error in the style of `rustc`: a headline,
+//! the file position, and the offending source line with the failing column
+//! underlined. [`ParseError::with_source`] gives a shorter form of the same
+//! information that is built into the error value itself.
+
+use crate::ParseError;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::SimpleFile;
+use codespan_reporting::term::termcolor::Buffer;
+use codespan_reporting::term::{self, Config};
+
+/// Name shown for the source in rendered diagnostics.
+const SOURCE_NAME: &str = "<input>";
+
+/// Renders `err` as a labeled snippet of `source`, without colors.
+///
+/// `source` must be the text that produced the error. The label points at the
+/// furthest position the parser reached, like [`ParseError::with_source`].
+/// Errors without a position are rendered as the headline alone.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{parse_carbon, render_diagnostic};
+///
+/// let source = "fn main() { }\nfn broken { }";
+/// let error = parse_carbon(source).unwrap_err();
+/// let rendered = render_diagnostic(source, &error);
+/// assert!(rendered.starts_with("error: expected `(`"));
+/// assert!(rendered.contains("<input>:2:11"));
+/// assert!(rendered.contains("2 │ fn broken { }"));
+/// ```
+pub fn render_diagnostic(source: &str, err: &ParseError) -> String {
+    render(source, err, Buffer::no_color())
+}
+
+/// Like [`render_diagnostic`], but colors the output with ANSI escape codes
+/// for display in a terminal.
+pub fn render_diagnostic_colored(source: &str, err: &ParseError) -> String {
+    render(source, err, Buffer::ansi())
+}
+
+fn render(source: &str, err: &ParseError, mut buffer: Buffer) -> String {
+    let file = SimpleFile::new(SOURCE_NAME, source);
+    let mut diagnostic = Diagnostic::error().with_message(err.summary());
+    if let Some(offset) = err.offset() {
+        let offset = offset.min(source.len());
+        // Underline the character the parser stopped at, or nothing at the end
+        // of the input.
+        let width = source[offset..].chars().next().map_or(0, char::len_utf8);
+        diagnostic = diagnostic.with_labels(vec![Label::primary((), offset..offset + width)]);
+    }
+    term::emit(&mut buffer, &Config::default(), &file, &diagnostic)
+        .expect("the label lies inside the source and a buffer accepts any output");
+    String::from_utf8_lossy(buffer.as_slice()).into_owned()
+}
@@ -0,0 +1,2156 @@
+//! Semantic checks that run on top of a successful parse.
+//!
+//! The grammar accepts some programs that are still ill-formed, for example a
+//! `throw` inside a function that is not declared `throws`, or a `fallthrough`
+//! that is not the last statement of a `match` arm. These rules are
+//! easier to express as a pass over the AST than as grammar rules, and
+//! reporting them separately lets tools show every problem at once instead of
+//! stopping at the first one.
+//!
+//! [`build_symbol_table`] records which names are declared in which scope, so
+//! analyses can resolve an identifier at any position in the source.
+
+use crate::ast::{
+    walk_block, walk_class, walk_expr, walk_function, walk_index_operator, walk_program, walk_stmt,
+    walk_type_name, AssignOp, Block, ChoiceDecl, ClassDecl, ClassMember, ClassModifier, Condition,
+    ElseBranch, Expr, FunctionDecl, Ident, IfStmt, IndexOperatorDecl, Item, Literal, LiteralKind,
+    MatchStmt, Param, ParamDirection, Pattern, Program, Stmt, TopItem, TypeName, VarDecl, Visitor,
+};
+use crate::consteval::{eval_const_bool, eval_const_i64};
+use crate::tokens::is_builtin;
+use crate::{parse_to_ast, ParseResult, Span};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How serious a [`SemanticError`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The program is ill-formed.
+    Error,
+    /// The program is valid but probably not what was intended.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A rule violation found after parsing succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SemanticError {
+    /// Human-readable description of the problem.
+    pub message: String,
+    /// Location of the offending construct.
+    pub span: Span,
+    pub severity: Severity,
+}
+
+impl SemanticError {
+    /// Creates a new semantic error.
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        SemanticError {
+            message: message.into(),
+            span,
+            severity: Severity::Error,
+        }
+    }
+
+    /// Creates a diagnostic with [`Severity::Warning`].
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        SemanticError {
+            severity: Severity::Warning,
+            ..SemanticError::new(message, span)
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} (at {})", self.severity, self.message, self.span)
+    }
+}
+
+/// Parses a program and runs every semantic check on it.
+///
+/// Returns the parse error if the input is not syntactically valid, otherwise
+/// the (possibly empty) list of semantic errors and warnings in source order.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::check_program;
+///
+/// let code = "fn fail() -> i32 { throw Error(1); }";
+/// let errors = check_program(code).unwrap();
+/// assert_eq!(errors.len(), 1);
+///
+/// let code = "fn fail() throws -> i32 { throw Error(1); }";
+/// assert!(check_program(code).unwrap().is_empty());
+/// ```
+pub fn check_program(input: &str) -> ParseResult<Vec<SemanticError>> {
+    Ok(check_ast(&parse_to_ast(input)?))
+}
+
+/// Runs every semantic check on an already parsed program.
+pub fn check_ast(program: &Program) -> Vec<SemanticError> {
+    let mut errors = Vec::new();
+    check_final_bases(program, &mut errors);
+    check_type_params(program, &mut errors);
+    check_array_sizes(program, &mut errors);
+    check_pragmas(program, &mut errors);
+    check_multi_assignments(program, &mut errors);
+    check_fallthrough(program, &mut errors);
+    check_builtin_names(program, &mut errors);
+    check_param_patterns(program, &mut errors);
+    check_out_params(program, &mut errors);
+
+    for item in &program.items {
+        match item {
+            Item::Function(function) => check_function(function, &mut errors),
+            Item::Class(class) => {
+                for member in &class.members {
+                    match member {
+                        ClassMember::Field(field) => check_var_init(field, &mut errors),
+                        ClassMember::Method(method) => check_function(method, &mut errors),
+                        ClassMember::IndexOperator(operator) => {
+                            check_index_operator(operator, &mut errors)
+                        }
+                    }
+                }
+            }
+            Item::Choice(choice) => check_discriminants(choice, &mut errors),
+            Item::Var(decl) => check_var_init(decl, &mut errors),
+            Item::Let(decl) => check_type_arithmetic(&decl.init, &mut errors),
+        }
+    }
+
+    // In script mode, statements outside functions get the checks of a body.
+    let script: Vec<Stmt> = program
+        .top_level
+        .iter()
+        .filter_map(|top| match top {
+            TopItem::Stmt(stmt) => Some(Stmt::clone(stmt)),
+            TopItem::Item(_) => None,
+        })
+        .collect();
+    if !script.is_empty() {
+        let span = program.span;
+        check_body(
+            &Block {
+                stmts: script,
+                span,
+            },
+            &mut errors,
+        );
+    }
+
+    errors.sort_by_key(|error| error.span.start);
+    errors
+}
+
+/// Runs the checks that apply to a single function or method.
+fn check_function(function: &FunctionDecl, errors: &mut Vec<SemanticError>) {
+    check_params(function, errors);
+    check_throws(function, errors);
+    check_await(function, errors);
+    check_if_bindings(&function.params, &function.body, errors);
+    check_body(&function.body, errors);
+}
+
+/// An index operator takes exactly one index, as in `container[i]`.
+fn check_index_operator(operator: &IndexOperatorDecl, errors: &mut Vec<SemanticError>) {
+    if operator.index_params.len() != 1 {
+        errors.push(SemanticError::new(
+            format!(
+                "an index operator takes exactly one index parameter, found {}",
+                operator.index_params.len()
+            ),
+            operator.span,
+        ));
+    }
+    let params = std::iter::once(&operator.receiver).chain(&operator.index_params);
+    check_if_bindings(params, &operator.body, errors);
+    check_body(&operator.body, errors);
+}
+
+/// Checks that only look at the statements of a body.
+fn check_body(body: &Block, errors: &mut Vec<SemanticError>) {
+    check_constant_conditions(body, errors);
+    for_each_stmt(body, &mut |stmt| {
+        for expr in stmt_exprs(stmt) {
+            check_type_arithmetic(expr, errors);
+        }
+    });
+}
+
+fn check_var_init(decl: &VarDecl, errors: &mut Vec<SemanticError>) {
+    if let Some(init) = &decl.init {
+        check_type_arithmetic(init, errors);
+    }
+}
+
+/// Calls `f` on every statement in `block`, including nested blocks.
+fn for_each_stmt<'a>(block: &'a Block, f: &mut impl FnMut(&'a Stmt)) {
+    for stmt in &block.stmts {
+        f(stmt);
+        match stmt {
+            Stmt::If(if_stmt) => {
+                for link in if_stmt.chain() {
+                    for_each_stmt(&link.then_block, f);
+                }
+                if let Some(else_block) = if_stmt.final_else() {
+                    for_each_stmt(else_block, f);
+                }
+            }
+            Stmt::While(while_stmt) => for_each_stmt(&while_stmt.body, f),
+            Stmt::For(for_stmt) => for_each_stmt(&for_stmt.body, f),
+            Stmt::CFor(for_stmt) => {
+                let clauses = for_stmt.init.iter().chain(&for_stmt.update);
+                for clause in clauses {
+                    f(clause);
+                }
+                for_each_stmt(&for_stmt.body, f);
+            }
+            Stmt::Match(match_stmt) => {
+                for arm in &match_stmt.arms {
+                    for_each_stmt(&arm.body, f);
+                }
+                if let Some(default) = &match_stmt.default {
+                    for_each_stmt(default, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Calls `f` on `expr` and every expression nested inside it.
+fn for_each_subexpr<'a>(expr: &'a Expr, f: &mut impl FnMut(&'a Expr)) {
+    f(expr);
+    match expr {
+        Expr::Call(call) => {
+            for arg in &call.args {
+                for_each_subexpr(arg, f);
+            }
+        }
+        Expr::Binary(binary) => {
+            for_each_subexpr(&binary.lhs, f);
+            for_each_subexpr(&binary.rhs, f);
+        }
+        Expr::Paren(paren) => for_each_subexpr(&paren.inner, f),
+        Expr::Await(await_expr) => for_each_subexpr(&await_expr.operand, f),
+        Expr::Try(try_expr) => for_each_subexpr(&try_expr.operand, f),
+        Expr::Member(member) => for_each_subexpr(&member.base, f),
+        Expr::MethodCall(call) => {
+            for_each_subexpr(&call.receiver, f);
+            for arg in &call.args {
+                for_each_subexpr(arg, f);
+            }
+        }
+        Expr::TupleIndex(tuple_index) => for_each_subexpr(&tuple_index.base, f),
+        Expr::Index(index) => {
+            for_each_subexpr(&index.base, f);
+            for_each_subexpr(&index.index, f);
+        }
+        Expr::Convert(convert) => for_each_subexpr(&convert.arg, f),
+        Expr::Conditional(conditional) => {
+            for_each_subexpr(&conditional.condition, f);
+            for_each_subexpr(&conditional.then_branch, f);
+            for_each_subexpr(&conditional.else_branch, f);
+        }
+        Expr::Literal(_) | Expr::Ident(_) | Expr::TypeValue(_) => {}
+    }
+}
+
+/// Expressions that belong to `stmt` itself, not to its nested blocks.
+fn stmt_exprs(stmt: &Stmt) -> Vec<&Expr> {
+    match stmt {
+        Stmt::Var(decl) => decl.init.iter().collect(),
+        Stmt::Let(decl) => vec![&decl.init],
+        Stmt::Return(ret) => ret.value.iter().collect(),
+        Stmt::Throw(throw) => vec![&throw.value],
+        Stmt::Fallthrough(_) | Stmt::Break(_) | Stmt::Continue(_) => Vec::new(),
+        Stmt::Expr(stmt) => vec![&stmt.expr],
+        Stmt::If(if_stmt) => if_stmt
+            .chain()
+            .map(|link| match &link.condition {
+                Condition::Expr(condition) => condition,
+                Condition::Binding(binding) => &binding.value,
+            })
+            .collect(),
+        Stmt::While(while_stmt) => vec![&while_stmt.condition],
+        Stmt::For(for_stmt) => vec![&for_stmt.iterable],
+        Stmt::CFor(for_stmt) => for_stmt.condition.iter().collect(),
+        Stmt::Assign(assign) => vec![&assign.value],
+        Stmt::MultiAssign(assign) => vec![&assign.value],
+        Stmt::Match(match_stmt) => std::iter::once(&match_stmt.scrutinee)
+            .chain(match_stmt.arms.iter().filter_map(|arm| arm.guard.as_ref()))
+            .collect(),
+    }
+}
+
+/// Returns `true` if `expr` mentions a variable or calls a function.
+fn references_runtime_values(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::TypeValue(_) => false,
+        Expr::Ident(_)
+        | Expr::Call(_)
+        | Expr::Await(_)
+        | Expr::Try(_)
+        | Expr::Member(_)
+        | Expr::MethodCall(_)
+        | Expr::TupleIndex(_)
+        | Expr::Index(_) => true,
+        Expr::Binary(binary) => {
+            references_runtime_values(&binary.lhs) || references_runtime_values(&binary.rhs)
+        }
+        Expr::Paren(paren) => references_runtime_values(&paren.inner),
+        Expr::Convert(convert) => references_runtime_values(&convert.arg),
+        Expr::Conditional(conditional) => {
+            references_runtime_values(&conditional.condition)
+                || references_runtime_values(&conditional.then_branch)
+                || references_runtime_values(&conditional.else_branch)
+        }
+    }
+}
+
+/// Checks parameter lists: a variadic parameter must be last, and the default
+/// of a `:!` parameter must be computable at compile time.
+///
+/// Only literals combined with operators count as compile-time defaults; any
+/// variable reference or function call is treated as runtime-only.
+fn check_params(function: &FunctionDecl, errors: &mut Vec<SemanticError>) {
+    let last = function.params.len().saturating_sub(1);
+    for (index, param) in function.params.iter().enumerate() {
+        match param {
+            Param::Variadic { span, .. } if index != last => {
+                errors.push(SemanticError::new(
+                    "a variadic parameter must be the last parameter",
+                    *span,
+                ));
+            }
+            Param::Regular {
+                name,
+                comptime: true,
+                default: Some(default),
+                ..
+            } if references_runtime_values(default) => {
+                errors.push(SemanticError::new(
+                    format!(
+                        "compile-time parameter `{}` has a runtime-only default",
+                        name.name
+                    ),
+                    default.span(),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Every discriminant of a choice must be a compile-time constant, and no
+/// two variants may share a value.
+fn check_discriminants(choice: &ChoiceDecl, errors: &mut Vec<SemanticError>) {
+    let mut seen: HashMap<i64, &str> = HashMap::new();
+    for (variant, value) in choice.variants.iter().zip(choice.discriminants()) {
+        match (value, &variant.value) {
+            (Some(value), _) => {
+                if let Some(previous) = seen.insert(value, &variant.name.name) {
+                    errors.push(SemanticError::new(
+                        format!(
+                            "discriminant {value} of `{}` is already used by `{previous}`",
+                            variant.name.name
+                        ),
+                        variant.span,
+                    ));
+                }
+            }
+            (None, Some(expr)) => errors.push(SemanticError::new(
+                format!(
+                    "discriminant of `{}` is not a compile-time constant",
+                    variant.name.name
+                ),
+                expr.span(),
+            )),
+            // Follows a variant that was already reported, or overflowed.
+            (None, None) => {}
+        }
+    }
+}
+
+/// Types are values of type `Type`, which supports comparison but not
+/// arithmetic, so `i32 + 1` is rejected.
+fn check_type_arithmetic(expr: &Expr, errors: &mut Vec<SemanticError>) {
+    for_each_subexpr(expr, &mut |expr| {
+        let Expr::Binary(binary) = expr else {
+            return;
+        };
+        if !binary.op.is_arithmetic() {
+            return;
+        }
+        for operand in [&binary.lhs, &binary.rhs] {
+            if let Some(ty) = type_value(operand) {
+                errors.push(SemanticError::new(
+                    format!(
+                        "type `{}` cannot be used with `{}`",
+                        ty.name,
+                        binary.op.as_str()
+                    ),
+                    operand.span(),
+                ));
+            }
+        }
+    });
+}
+
+/// The type a (possibly parenthesized) type-value expression refers to.
+fn type_value(expr: &Expr) -> Option<&TypeName> {
+    match expr {
+        Expr::TypeValue(ty) => Some(ty),
+        Expr::Paren(paren) => type_value(&paren.inner),
+        _ => None,
+    }
+}
+
+/// Type parameters used by a class must be declared by it.
+///
+/// Every `where` constraint has to name one of the class's parameters. In
+/// method signatures and bodies, a type written like a parameter (a single
+/// capital letter, optionally followed by digits, such as `T` or `U2`) that the
+/// class does not declare is also reported, unless a top-level declaration
+/// has that name.
+fn check_type_params(program: &Program, errors: &mut Vec<SemanticError>) {
+    let top_level: HashSet<&str> = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Class(class) => Some(class.name.name.as_str()),
+            Item::Choice(choice) => Some(choice.name.name.as_str()),
+            Item::Function(_) | Item::Var(_) | Item::Let(_) => None,
+        })
+        .collect();
+
+    for item in &program.items {
+        let Item::Class(class) = item else {
+            continue;
+        };
+        let declared: HashSet<&str> = class
+            .type_params()
+            .map(|param| param.name.name.as_str())
+            .collect();
+
+        for constraint in &class.constraints {
+            if !declared.contains(constraint.param.name.as_str()) {
+                errors.push(SemanticError::new(
+                    format!(
+                        "`where` constrains `{}`, which is not a parameter of class `{}`",
+                        constraint.param.name, class.name.name
+                    ),
+                    constraint.param.span,
+                ));
+            }
+        }
+
+        for member in &class.members {
+            let (method, params, return_type, body) = match member {
+                ClassMember::Method(method) => (
+                    format!("method `{}`", method.name.name),
+                    method.params.iter().collect::<Vec<_>>(),
+                    &method.return_type,
+                    &method.body,
+                ),
+                ClassMember::IndexOperator(operator) => (
+                    "the index operator".to_string(),
+                    std::iter::once(&operator.receiver)
+                        .chain(&operator.index_params)
+                        .collect(),
+                    &operator.return_type,
+                    &operator.body,
+                ),
+                ClassMember::Field(_) => continue,
+            };
+            for_each_type(params, return_type.as_ref(), body, &mut |ty| {
+                let name = ty.name.as_str();
+                if looks_like_type_param(name)
+                    && !declared.contains(name)
+                    && !top_level.contains(name)
+                {
+                    errors.push(SemanticError::new(
+                        format!(
+                            "{method} of class `{}` uses undeclared type parameter `{name}`",
+                            class.name.name
+                        ),
+                        ty.span,
+                    ));
+                }
+            });
+        }
+    }
+}
+
+fn looks_like_type_param(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_digit())
+}
+
+/// Calls `f` on every type written in a function's signature or body,
+/// including generic arguments and array and tuple elements.
+fn for_each_type<'a>(
+    params: Vec<&'a Param>,
+    return_type: Option<&'a TypeName>,
+    body: &'a Block,
+    f: &mut impl FnMut(&'a TypeName),
+) {
+    fn visit<'a>(ty: &'a TypeName, f: &mut impl FnMut(&'a TypeName)) {
+        f(ty);
+        for arg in &ty.args {
+            visit(arg, f);
+        }
+        if let Some(array) = &ty.array {
+            visit(&array.element, f);
+        }
+        if let Some(tuple) = &ty.tuple {
+            for element in &tuple.elements {
+                visit(element, f);
+            }
+        }
+    }
+
+    fn visit_pattern<'a>(pattern: &'a Pattern, f: &mut impl FnMut(&'a TypeName)) {
+        match pattern {
+            Pattern::Binding { ty: Some(ty), .. } => visit(ty, f),
+            Pattern::Constructor { args: parts, .. }
+            | Pattern::Tuple {
+                elements: parts, ..
+            } => {
+                for part in parts {
+                    visit_pattern(part, f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for param in params {
+        match param {
+            Param::Regular { ty, .. } | Param::Variadic { ty: Some(ty), .. } => visit(ty, f),
+            Param::Variadic { ty: None, .. } => {}
+            Param::Pattern { pattern, ty, .. } => {
+                visit_pattern(pattern, f);
+                visit(ty, f);
+            }
+        }
+    }
+    if let Some(ty) = return_type {
+        visit(ty, f);
+    }
+    for_each_stmt(body, &mut |stmt| match stmt {
+        Stmt::Var(decl) => visit(&decl.ty, f),
+        Stmt::Let(decl) => visit(&decl.ty, f),
+        Stmt::For(for_stmt) => visit(&for_stmt.ty, f),
+        Stmt::If(if_stmt) => {
+            for link in if_stmt.chain() {
+                if let Condition::Binding(binding) = &link.condition {
+                    visit(&binding.ty, f);
+                }
+            }
+        }
+        Stmt::Match(match_stmt) => {
+            for arm in &match_stmt.arms {
+                visit_pattern(&arm.pattern, f);
+            }
+        }
+        _ => {}
+    });
+}
+
+/// `a, b = value;` needs a tuple `value` with one element per target.
+///
+/// The number of elements is only known without type inference for literals,
+/// which are never tuples, and for calls to functions declared at the top
+/// level of the program, whose return type says how many values they return.
+fn check_multi_assignments(program: &Program, errors: &mut Vec<SemanticError>) {
+    struct MultiAssignments<'a, 'e> {
+        returns: HashMap<&'a str, Option<&'a TypeName>>,
+        errors: &'e mut Vec<SemanticError>,
+    }
+
+    impl<'a> Visitor<'a> for MultiAssignments<'a, '_> {
+        fn visit_stmt(&mut self, stmt: &'a Stmt) {
+            if let Stmt::MultiAssign(assign) = stmt {
+                let targets = assign.targets.len();
+                let problem = match &assign.value {
+                    Expr::Literal(_) => Some("the value is not a tuple".to_string()),
+                    Expr::Call(call) => match self.returns.get(call.callee.name.as_str()) {
+                        Some(None) => Some(format!("`{}` returns nothing", call.callee.name)),
+                        Some(Some(ty)) => match &ty.tuple {
+                            Some(tuple) if ty.pointers.is_empty() => {
+                                let count = tuple.elements.len();
+                                (count != targets).then(|| {
+                                    format!("`{}` returns {count} values", call.callee.name)
+                                })
+                            }
+                            _ => Some(format!("`{}` returns 1 value", call.callee.name)),
+                        },
+                        None => None,
+                    },
+                    _ => None,
+                };
+                if let Some(problem) = problem {
+                    self.errors.push(SemanticError::new(
+                        format!("{targets} assignment targets, but {problem}"),
+                        assign.span,
+                    ));
+                }
+            }
+            walk_stmt(self, stmt);
+        }
+    }
+
+    let returns = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Function(function) => {
+                Some((function.name.name.as_str(), function.return_type.as_ref()))
+            }
+            _ => None,
+        })
+        .collect();
+    walk_program(&mut MultiAssignments { returns, errors }, program);
+}
+
+/// `fallthrough;` continues with the next arm of a `match`, so it is only
+/// allowed as the last statement of a `case` arm.
+fn check_fallthrough(program: &Program, errors: &mut Vec<SemanticError>) {
+    struct Fallthroughs<'e> {
+        /// Start offsets of the `fallthrough` statements that end an arm.
+        allowed: HashSet<usize>,
+        /// Number of `match` statements around the current statement.
+        matches: usize,
+        errors: &'e mut Vec<SemanticError>,
+    }
+
+    impl<'a> Visitor<'a> for Fallthroughs<'_> {
+        fn visit_stmt(&mut self, stmt: &'a Stmt) {
+            match stmt {
+                Stmt::Match(match_stmt) => {
+                    for arm in &match_stmt.arms {
+                        if let Some(Stmt::Fallthrough(last)) = arm.body.stmts.last() {
+                            self.allowed.insert(last.span.start);
+                        }
+                    }
+                    self.matches += 1;
+                    walk_stmt(self, stmt);
+                    self.matches -= 1;
+                }
+                Stmt::Fallthrough(fallthrough)
+                    if !self.allowed.contains(&fallthrough.span.start) =>
+                {
+                    let message = if self.matches == 0 {
+                        "`fallthrough` outside of a `match`"
+                    } else {
+                        "`fallthrough` must be the last statement of a `case` arm"
+                    };
+                    self.errors
+                        .push(SemanticError::new(message, fallthrough.span));
+                }
+                _ => walk_stmt(self, stmt),
+            }
+        }
+    }
+
+    let mut fallthroughs = Fallthroughs {
+        allowed: HashSet::new(),
+        matches: 0,
+        errors,
+    };
+    walk_program(&mut fallthroughs, program);
+}
+
+/// Array sizes must be non-negative integers known at compile time, and an
+/// array with no elements is almost certainly a mistake.
+///
+/// A size may combine literals with the `:!` parameters of the enclosing
+/// function and the parameters of the enclosing class.
+fn check_array_sizes(program: &Program, errors: &mut Vec<SemanticError>) {
+    struct ArraySizes<'a, 'e> {
+        comptime: Vec<&'a str>,
+        errors: &'e mut Vec<SemanticError>,
+    }
+
+    impl<'a> ArraySizes<'a, '_> {
+        fn is_constant(&self, size: &Expr) -> bool {
+            match size {
+                Expr::Literal(_) => true,
+                Expr::Ident(ident) => self.comptime.contains(&ident.name.as_str()),
+                Expr::Paren(paren) => self.is_constant(&paren.inner),
+                Expr::Convert(convert) => self.is_constant(&convert.arg),
+                Expr::Binary(binary) => {
+                    self.is_constant(&binary.lhs) && self.is_constant(&binary.rhs)
+                }
+                _ => false,
+            }
+        }
+    }
+
+    /// A literal in `size` that is not an integer, such as the `1.5` in
+    /// `[i32; 1.5]`.
+    fn non_integer_literal(size: &Expr) -> Option<&Literal> {
+        match size {
+            Expr::Literal(literal) if literal.kind != LiteralKind::Integer => Some(literal),
+            Expr::Paren(paren) => non_integer_literal(&paren.inner),
+            Expr::Binary(binary) => {
+                non_integer_literal(&binary.lhs).or_else(|| non_integer_literal(&binary.rhs))
+            }
+            _ => None,
+        }
+    }
+
+    impl<'a> Visitor<'a> for ArraySizes<'a, '_> {
+        fn visit_class(&mut self, class: &'a ClassDecl) {
+            let scope = self.comptime.len();
+            self.comptime
+                .extend(class.type_params().map(|param| param.name.name.as_str()));
+            walk_class(self, class);
+            self.comptime.truncate(scope);
+        }
+
+        fn visit_function(&mut self, function: &'a FunctionDecl) {
+            let scope = self.comptime.len();
+            self.comptime
+                .extend(function.params.iter().filter_map(|param| match param {
+                    Param::Regular {
+                        name,
+                        comptime: true,
+                        ..
+                    } => Some(name.name.as_str()),
+                    _ => None,
+                }));
+            walk_function(self, function);
+            self.comptime.truncate(scope);
+        }
+
+        fn visit_type_name(&mut self, ty: &'a TypeName) {
+            if let Some(size) = ty.array.as_ref().and_then(|array| array.size.as_ref()) {
+                if !self.is_constant(size) {
+                    self.errors.push(SemanticError::new(
+                        "an array size must be a compile-time constant",
+                        size.span(),
+                    ));
+                } else if let Some(literal) = non_integer_literal(size) {
+                    self.errors.push(SemanticError::new(
+                        format!("an array size must be an integer, found `{}`", literal.text),
+                        size.span(),
+                    ));
+                } else if let Some(length) = eval_const_i64(size).filter(|&n| n < 0) {
+                    self.errors.push(SemanticError::new(
+                        format!("an array size must not be negative, found `{length}`"),
+                        size.span(),
+                    ));
+                } else if eval_const_i64(size) == Some(0) {
+                    self.errors.push(SemanticError::warning(
+                        format!("array type `{}` has no elements", ty.canonical()),
+                        ty.span,
+                    ));
+                }
+            }
+            walk_type_name(self, ty);
+        }
+    }
+
+    walk_program(
+        &mut ArraySizes {
+            comptime: Vec::new(),
+            errors,
+        },
+        program,
+    );
+}
+
+/// Pragmas that tooling built on this crate understands.
+const KNOWN_PRAGMAS: &[&str] = &["optimize"];
+
+/// Unknown pragmas are accepted by the parser but are probably misspelled.
+fn check_pragmas(program: &Program, errors: &mut Vec<SemanticError>) {
+    for pragma in &program.pragmas {
+        if !KNOWN_PRAGMAS.contains(&pragma.name.name.as_str()) {
+            errors.push(SemanticError::warning(
+                format!("unknown pragma `{}`", pragma.name.name),
+                pragma.name.span,
+            ));
+        }
+    }
+}
+
+/// A parameter pattern must have as many parts as its type: a tuple pattern
+/// one per tuple element, and a constructor pattern one per field of a class
+/// declared in the same program.
+fn check_param_patterns(program: &Program, errors: &mut Vec<SemanticError>) {
+    struct ParamPatterns<'a, 'e> {
+        /// Number of fields of each class in the program.
+        fields: HashMap<&'a str, usize>,
+        errors: &'e mut Vec<SemanticError>,
+    }
+
+    impl ParamPatterns<'_, '_> {
+        fn check(&mut self, pattern: &Pattern, ty: &TypeName) {
+            match pattern {
+                Pattern::Tuple { elements, span } => match &ty.tuple {
+                    Some(tuple) if tuple.elements.len() == elements.len() => {
+                        for (element, ty) in elements.iter().zip(&tuple.elements) {
+                            self.check(element, ty);
+                        }
+                    }
+                    _ => self.errors.push(SemanticError::new(
+                        format!(
+                            "tuple pattern with {} elements does not match type `{}`",
+                            elements.len(),
+                            ty.canonical()
+                        ),
+                        *span,
+                    )),
+                },
+                Pattern::Constructor { name, args, span } => {
+                    let Some(&fields) = self.fields.get(ty.name.as_str()) else {
+                        return;
+                    };
+                    if args.len() != fields {
+                        self.errors.push(SemanticError::new(
+                            format!(
+                                "pattern `{}` does not match the fields of class `{}`: \
+                                 expected {fields}, found {}",
+                                name.name,
+                                ty.name,
+                                args.len()
+                            ),
+                            *span,
+                        ));
+                    }
+                }
+                Pattern::Wildcard(_) | Pattern::Literal(_) | Pattern::Binding { .. } => {}
+            }
+        }
+    }
+
+    impl<'a> Visitor<'a> for ParamPatterns<'a, '_> {
+        fn visit_param(&mut self, param: &'a Param) {
+            if let Param::Pattern { pattern, ty, .. } = param {
+                self.check(pattern, ty);
+            }
+        }
+    }
+
+    let fields = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Class(class) => {
+                let fields = class
+                    .members
+                    .iter()
+                    .filter(|member| matches!(member, ClassMember::Field(_)))
+                    .count();
+                Some((class.name.name.as_str(), fields))
+            }
+            _ => None,
+        })
+        .collect();
+    walk_program(&mut ParamPatterns { fields, errors }, program);
+}
+
+/// An `out` parameter holds no value until the function assigns it.
+///
+/// This is best-effort: statements are checked in source order, and an
+/// assignment in a branch that may not run still counts as assigning the
+/// parameter for the rest of the function.
+fn check_out_params(program: &Program, errors: &mut Vec<SemanticError>) {
+    struct OutParams<'a, 'e> {
+        /// `out` parameters of the current function not assigned yet.
+        unassigned: Vec<&'a str>,
+        errors: &'e mut Vec<SemanticError>,
+    }
+
+    impl OutParams<'_, '_> {
+        fn read(&mut self, ident: &Ident) {
+            if self.unassigned.contains(&ident.name.as_str()) {
+                self.errors.push(SemanticError::new(
+                    format!(
+                        "`out` parameter `{}` is read before it is assigned",
+                        ident.name
+                    ),
+                    ident.span,
+                ));
+                // Report each parameter once.
+                self.assign(&ident.name);
+            }
+        }
+
+        fn assign(&mut self, name: &str) {
+            self.unassigned.retain(|&unassigned| unassigned != name);
+        }
+    }
+
+    impl<'a> Visitor<'a> for OutParams<'a, '_> {
+        fn visit_function(&mut self, function: &'a FunctionDecl) {
+            let params = function
+                .params
+                .iter()
+                .filter_map(|param| match param {
+                    Param::Regular {
+                        direction: ParamDirection::Out,
+                        name,
+                        ..
+                    } => Some(name.name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let outer = std::mem::replace(&mut self.unassigned, params);
+            walk_function(self, function);
+            self.unassigned = outer;
+        }
+
+        fn visit_stmt(&mut self, stmt: &'a Stmt) {
+            if let Stmt::Assign(assign) = stmt {
+                if assign.op != AssignOp::Assign {
+                    self.read(&assign.target);
+                }
+            }
+            walk_stmt(self, stmt);
+            // A local variable with the same name hides the parameter.
+            match stmt {
+                Stmt::Assign(assign) => self.assign(&assign.target.name),
+                Stmt::MultiAssign(assign) => {
+                    for target in &assign.targets {
+                        self.assign(&target.name);
+                    }
+                }
+                Stmt::Var(decl) => self.assign(&decl.name.name),
+                Stmt::Let(decl) => self.assign(&decl.name.name),
+                _ => {}
+            }
+        }
+
+        fn visit_expr(&mut self, expr: &'a Expr) {
+            if let Expr::Ident(ident) = expr {
+                self.read(ident);
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    walk_program(
+        &mut OutParams {
+            unassigned: Vec::new(),
+            errors,
+        },
+        program,
+    );
+}
+
+/// Names starting with `__` belong to compiler intrinsics, so user code
+/// should not declare them.
+fn check_builtin_names(program: &Program, errors: &mut Vec<SemanticError>) {
+    let table = build_symbol_table(program);
+    for symbol in table.scopes().iter().flat_map(|scope| &scope.symbols) {
+        if is_builtin(&symbol.name) {
+            errors.push(SemanticError::warning(
+                format!("`{}` is reserved for compiler builtins", symbol.name),
+                symbol.span,
+            ));
+        }
+    }
+}
+
+/// A class may not extend a class declared `final` in the same program.
+fn check_final_bases(program: &Program, errors: &mut Vec<SemanticError>) {
+    let classes = || {
+        program.items.iter().filter_map(|item| match item {
+            Item::Class(class) => Some(class),
+            _ => None,
+        })
+    };
+    let finals: HashSet<&str> = classes()
+        .filter(|class| class.modifier == Some(ClassModifier::Final))
+        .map(|class| class.name.name.as_str())
+        .collect();
+
+    for class in classes() {
+        if let Some(base) = &class.extends {
+            if finals.contains(base.name.as_str()) {
+                errors.push(SemanticError::new(
+                    format!(
+                        "class `{}` extends `{}`, which is declared `final`",
+                        class.name.name, base.name
+                    ),
+                    base.span,
+                ));
+            }
+        }
+    }
+}
+
+/// A `throw` may only appear in a function declared with `throws`.
+fn check_throws(function: &FunctionDecl, errors: &mut Vec<SemanticError>) {
+    if function.throws {
+        return;
+    }
+
+    for_each_stmt(&function.body, &mut |stmt| {
+        if let Stmt::Throw(throw) = stmt {
+            errors.push(SemanticError::new(
+                format!(
+                    "`throw` in function `{}`, which is not declared `throws`",
+                    function.name.name
+                ),
+                throw.span,
+            ));
+        }
+    });
+}
+
+/// `await` may only appear in a function declared `async`.
+fn check_await(function: &FunctionDecl, errors: &mut Vec<SemanticError>) {
+    if function.is_async {
+        return;
+    }
+
+    for_each_stmt(&function.body, &mut |stmt| {
+        for expr in stmt_exprs(stmt) {
+            for_each_subexpr(expr, &mut |expr| {
+                if let Expr::Await(await_expr) = expr {
+                    errors.push(SemanticError::new(
+                        format!(
+                            "`await` in function `{}`, which is not declared `async`",
+                            function.name.name
+                        ),
+                        await_expr.span,
+                    ));
+                }
+            });
+        }
+    });
+}
+
+/// Constant `if` and `while` conditions are almost always leftovers from
+/// debugging, so they are reported as warnings. The one exception is the
+/// literal `while (true)`, the usual way to write an infinite loop; other
+/// always-true loop conditions such as `while (1 < 2)` are still reported.
+fn check_constant_conditions(body: &Block, errors: &mut Vec<SemanticError>) {
+    let mut warn = |condition: &Expr, value: bool| {
+        errors.push(SemanticError::warning(
+            format!("condition is always {value}"),
+            condition.span(),
+        ));
+    };
+    for_each_stmt(body, &mut |stmt| match stmt {
+        Stmt::If(if_stmt) => {
+            for link in if_stmt.chain() {
+                if let Condition::Expr(condition) = &link.condition {
+                    if let Some(value) = eval_const_bool(condition) {
+                        warn(condition, value);
+                    }
+                }
+            }
+        }
+        Stmt::While(while_stmt) => {
+            let condition = &while_stmt.condition;
+            if matches!(condition, Expr::Literal(literal) if literal.text == "true") {
+                return;
+            }
+            if let Some(value) = eval_const_bool(condition) {
+                warn(condition, value);
+            }
+        }
+        _ => {}
+    });
+}
+
+/// Names bound by `if (var x: T = ...)` are only visible in the then-branch.
+///
+/// Uses of such a name in the else-branch are flagged unless an enclosing or
+/// local declaration with the same name is in scope at that point.
+fn check_if_bindings<'a>(
+    params: impl IntoIterator<Item = &'a Param>,
+    body: &'a Block,
+    errors: &mut Vec<SemanticError>,
+) {
+    let mut scopes = ScopeWalker::default();
+    scopes.frames.push(Vec::new());
+    for param in params {
+        match param {
+            Param::Regular { name, .. }
+            | Param::Variadic {
+                name: Some(name), ..
+            } => scopes.declare(&name.name),
+            Param::Variadic { name: None, .. } => {}
+            Param::Pattern { pattern, .. } => scopes.declare_pattern(pattern),
+        }
+    }
+    scopes.walk_block(body, errors);
+}
+
+/// Tracks declared names per block while walking a function body.
+#[derive(Default)]
+struct ScopeWalker<'a> {
+    frames: Vec<Vec<&'a str>>,
+    /// Condition bindings whose else-branch is currently being walked.
+    hidden: Vec<&'a str>,
+}
+
+impl<'a> ScopeWalker<'a> {
+    fn declare(&mut self, name: &'a str) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push(name);
+        }
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.frames.iter().any(|frame| frame.contains(&name))
+    }
+
+    fn walk_block(&mut self, block: &'a Block, errors: &mut Vec<SemanticError>) {
+        self.frames.push(Vec::new());
+        for stmt in &block.stmts {
+            self.walk_stmt(stmt, errors);
+        }
+        self.frames.pop();
+    }
+
+    fn walk_stmt(&mut self, stmt: &'a Stmt, errors: &mut Vec<SemanticError>) {
+        match stmt {
+            Stmt::Var(decl) => {
+                if let Some(init) = &decl.init {
+                    self.check_expr(init, errors);
+                }
+                self.declare(&decl.name.name);
+            }
+            Stmt::Let(decl) => {
+                self.check_expr(&decl.init, errors);
+                self.declare(&decl.name.name);
+            }
+            Stmt::Return(ret) => {
+                if let Some(value) = &ret.value {
+                    self.check_expr(value, errors);
+                }
+            }
+            Stmt::Throw(throw) => self.check_expr(&throw.value, errors),
+            Stmt::Fallthrough(_) | Stmt::Break(_) | Stmt::Continue(_) => {}
+            Stmt::Expr(stmt) => self.check_expr(&stmt.expr, errors),
+            Stmt::Assign(assign) => {
+                self.check_ident(&assign.target, errors);
+                self.check_expr(&assign.value, errors);
+            }
+            Stmt::MultiAssign(assign) => {
+                for target in &assign.targets {
+                    self.check_ident(target, errors);
+                }
+                self.check_expr(&assign.value, errors);
+            }
+            Stmt::If(if_stmt) => self.walk_if(if_stmt, errors),
+            Stmt::While(while_stmt) => {
+                self.check_expr(&while_stmt.condition, errors);
+                self.walk_block(&while_stmt.body, errors);
+            }
+            Stmt::For(for_stmt) => {
+                self.check_expr(&for_stmt.iterable, errors);
+                self.frames.push(vec![for_stmt.name.name.as_str()]);
+                self.walk_block(&for_stmt.body, errors);
+                self.frames.pop();
+            }
+            Stmt::CFor(for_stmt) => {
+                self.frames.push(Vec::new());
+                if let Some(init) = &for_stmt.init {
+                    self.walk_stmt(init, errors);
+                }
+                if let Some(condition) = &for_stmt.condition {
+                    self.check_expr(condition, errors);
+                }
+                if let Some(update) = &for_stmt.update {
+                    self.walk_stmt(update, errors);
+                }
+                self.walk_block(&for_stmt.body, errors);
+                self.frames.pop();
+            }
+            Stmt::Match(match_stmt) => self.walk_match(match_stmt, errors),
+        }
+    }
+
+    fn walk_if(&mut self, if_stmt: &'a IfStmt, errors: &mut Vec<SemanticError>) {
+        let binding = match &if_stmt.condition {
+            Condition::Expr(condition) => {
+                self.check_expr(condition, errors);
+                None
+            }
+            Condition::Binding(binding) => {
+                self.check_expr(&binding.value, errors);
+                Some(binding.name.name.as_str())
+            }
+        };
+
+        self.frames.push(binding.into_iter().collect());
+        self.walk_block(&if_stmt.then_block, errors);
+        self.frames.pop();
+
+        if let Some(else_branch) = &if_stmt.else_branch {
+            self.hidden.extend(binding);
+            match else_branch {
+                ElseBranch::Block(block) => self.walk_block(block, errors),
+                ElseBranch::If(next) => self.walk_if(next, errors),
+            }
+            if binding.is_some() {
+                self.hidden.pop();
+            }
+        }
+    }
+
+    fn walk_match(&mut self, match_stmt: &'a MatchStmt, errors: &mut Vec<SemanticError>) {
+        self.check_expr(&match_stmt.scrutinee, errors);
+        for arm in &match_stmt.arms {
+            self.frames.push(Vec::new());
+            self.declare_pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                self.check_expr(guard, errors);
+            }
+            self.walk_block(&arm.body, errors);
+            self.frames.pop();
+        }
+        if let Some(default) = &match_stmt.default {
+            self.walk_block(default, errors);
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &'a Pattern) {
+        match pattern {
+            Pattern::Binding { name, .. } => self.declare(&name.name),
+            Pattern::Constructor { args: parts, .. }
+            | Pattern::Tuple {
+                elements: parts, ..
+            } => {
+                for part in parts {
+                    self.declare_pattern(part);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    fn check_ident(&self, ident: &Ident, errors: &mut Vec<SemanticError>) {
+        let name = ident.name.as_str();
+        if self.hidden.contains(&name) && !self.is_declared(name) {
+            errors.push(SemanticError::new(
+                format!("`{name}` is bound by the if condition and not visible here"),
+                ident.span,
+            ));
+        }
+    }
+
+    fn check_expr(&self, expr: &'a Expr, errors: &mut Vec<SemanticError>) {
+        match expr {
+            Expr::Ident(ident) => self.check_ident(ident, errors),
+            Expr::Call(call) => {
+                for arg in &call.args {
+                    self.check_expr(arg, errors);
+                }
+            }
+            Expr::Binary(binary) => {
+                self.check_expr(&binary.lhs, errors);
+                self.check_expr(&binary.rhs, errors);
+            }
+            Expr::Paren(paren) => self.check_expr(&paren.inner, errors),
+            Expr::Await(await_expr) => self.check_expr(&await_expr.operand, errors),
+            Expr::Try(try_expr) => self.check_expr(&try_expr.operand, errors),
+            Expr::Member(member) => self.check_expr(&member.base, errors),
+            Expr::MethodCall(call) => {
+                self.check_expr(&call.receiver, errors);
+                for arg in &call.args {
+                    self.check_expr(arg, errors);
+                }
+            }
+            Expr::TupleIndex(tuple_index) => self.check_expr(&tuple_index.base, errors),
+            Expr::Index(index) => {
+                self.check_expr(&index.base, errors);
+                self.check_expr(&index.index, errors);
+            }
+            Expr::Convert(convert) => self.check_expr(&convert.arg, errors),
+            Expr::Conditional(conditional) => {
+                self.check_expr(&conditional.condition, errors);
+                self.check_expr(&conditional.then_branch, errors);
+                self.check_expr(&conditional.else_branch, errors);
+            }
+            Expr::Literal(_) | Expr::TypeValue(_) => {}
+        }
+    }
+}
+
+/// What kind of declaration introduced a [`Symbol`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Choice,
+    /// A `var` declaration, a `for` loop variable, an `if (var ...)` binding
+    /// or a name bound by a `match` pattern.
+    Variable,
+    /// A `let` binding.
+    Constant,
+    /// A field declared in a class body.
+    Field,
+    Parameter,
+}
+
+/// A declared name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The declared type. For functions this is the return type; it is `None`
+    /// for classes, choices, functions without a return type and untyped
+    /// variadic parameters or pattern bindings.
+    ///
+    /// A variable declared as `auto`, or without a type, gets the type of its
+    /// initializer if [`infer_literal_type`] can tell it, and `None` otherwise.
+    pub ty: Option<TypeName>,
+    /// Span of the declaring construct.
+    pub span: Span,
+    /// First byte offset at which the name can be referred to.
+    visible_from: usize,
+}
+
+/// A region of the program in which declarations are visible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    /// Index of the enclosing scope in [`SymbolTable::scopes`]; `None` only
+    /// for the program scope.
+    pub parent: Option<usize>,
+    pub span: Span,
+    /// Declarations made directly in this scope, in source order.
+    pub symbols: Vec<Symbol>,
+}
+
+/// Every declaration in a program, grouped by scope.
+///
+/// Built by [`build_symbol_table`]. Scope `0` is the whole program; every
+/// class, function, block and `match` arm opens a nested scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolTable {
+    scopes: Vec<Scope>,
+}
+
+impl SymbolTable {
+    /// All scopes, each listed before the scopes nested in it.
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// Index of the innermost scope containing byte `offset`.
+    pub fn scope_at(&self, offset: usize) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.span.contains(offset))
+            .unwrap_or(0)
+    }
+
+    /// Resolves `name` as it would be seen at byte `offset`.
+    ///
+    /// Scopes are searched from the innermost outwards, so an inner
+    /// declaration shadows an outer one, and a later declaration in the same
+    /// scope shadows an earlier one. Variables become visible after their
+    /// declaration ends, while top-level declarations, class members and
+    /// parameters are visible throughout their scope.
+    pub fn lookup(&self, name: &str, offset: usize) -> Option<&Symbol> {
+        let mut scope = Some(self.scope_at(offset));
+        while let Some(index) = scope {
+            let found = self.scopes[index]
+                .symbols
+                .iter()
+                .rev()
+                .find(|symbol| symbol.name == name && symbol.visible_from <= offset);
+            if found.is_some() {
+                return found;
+            }
+            scope = self.scopes[index].parent;
+        }
+        None
+    }
+}
+
+/// The type of a literal initializer: `i32` for an integer, `f64` for a
+/// float, `String` and `bool` for strings and booleans. Parentheses around the
+/// literal are looked through. Any other expression, a char literal included,
+/// returns `None`; there is no inference from calls or operators.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::semantics::infer_literal_type;
+/// use carbon_parser::parse_expression_ast;
+///
+/// let ty = infer_literal_type(&parse_expression_ast("(42)").unwrap());
+/// assert_eq!(ty.map(|ty| ty.name), Some("i32".to_string()));
+/// assert!(infer_literal_type(&parse_expression_ast("f()").unwrap()).is_none());
+/// ```
+pub fn infer_literal_type(expr: &Expr) -> Option<TypeName> {
+    let name = match expr {
+        Expr::Literal(literal) => match literal.kind {
+            LiteralKind::Integer => "i32",
+            LiteralKind::Float => "f64",
+            LiteralKind::String => "String",
+            LiteralKind::Bool => "bool",
+            LiteralKind::Char => return None,
+        },
+        Expr::Paren(paren) => return infer_literal_type(&paren.inner),
+        _ => return None,
+    };
+    Some(TypeName::bare(name, expr.span()))
+}
+
+/// The type recorded for a `var` or `let`: the declared one, or the inferred
+/// one for `auto`.
+fn binding_type(ty: &TypeName, init: Option<&Expr>) -> Option<TypeName> {
+    if ty.is_auto() {
+        init.and_then(infer_literal_type)
+    } else {
+        Some(ty.clone())
+    }
+}
+
+/// Collects every declaration in `program` into a [`SymbolTable`].
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::semantics::SymbolKind;
+/// use carbon_parser::{build_symbol_table, parse_to_ast};
+///
+/// let code = "fn main(n: i32) { var x: i32 = n; while (x > 0) { var y: i32 = x; } }";
+/// let table = build_symbol_table(&parse_to_ast(code).unwrap());
+///
+/// let inner = code.find("var y").unwrap();
+/// assert_eq!(table.lookup("x", inner).map(|s| s.kind), Some(SymbolKind::Variable));
+/// assert_eq!(table.lookup("n", inner).map(|s| s.kind), Some(SymbolKind::Parameter));
+/// assert!(table.lookup("y", code.len() - 1).is_none());
+/// ```
+pub fn build_symbol_table(program: &Program) -> SymbolTable {
+    let mut builder = SymbolTableBuilder {
+        scopes: vec![Scope {
+            parent: None,
+            span: program.span,
+            symbols: Vec::new(),
+        }],
+        current: 0,
+    };
+    for item in &program.items {
+        let (name, kind, ty, span) = match item {
+            Item::Function(function) => (
+                &function.name,
+                SymbolKind::Function,
+                function.return_type.clone(),
+                function.span,
+            ),
+            Item::Class(class) => (&class.name, SymbolKind::Class, None, class.span),
+            Item::Choice(choice) => (&choice.name, SymbolKind::Choice, None, choice.span),
+            Item::Var(decl) => (
+                &decl.name,
+                SymbolKind::Variable,
+                binding_type(&decl.ty, decl.init.as_ref()),
+                decl.span,
+            ),
+            Item::Let(decl) => (
+                &decl.name,
+                SymbolKind::Constant,
+                binding_type(&decl.ty, Some(&decl.init)),
+                decl.span,
+            ),
+        };
+        builder.declare(name, kind, ty.as_ref(), span, 0);
+    }
+    walk_program(&mut builder, program);
+    SymbolTable {
+        scopes: builder.scopes,
+    }
+}
+
+struct SymbolTableBuilder {
+    scopes: Vec<Scope>,
+    current: usize,
+}
+
+impl SymbolTableBuilder {
+    /// Opens a scope nested in the current one and returns the scope to
+    /// restore with [`SymbolTableBuilder::exit`].
+    fn enter(&mut self, span: Span) -> usize {
+        self.scopes.push(Scope {
+            parent: Some(self.current),
+            span,
+            symbols: Vec::new(),
+        });
+        std::mem::replace(&mut self.current, self.scopes.len() - 1)
+    }
+
+    fn exit(&mut self, previous: usize) {
+        self.current = previous;
+    }
+
+    fn declare(
+        &mut self,
+        name: &Ident,
+        kind: SymbolKind,
+        ty: Option<&TypeName>,
+        span: Span,
+        visible_from: usize,
+    ) {
+        self.scopes[self.current].symbols.push(Symbol {
+            name: name.name.clone(),
+            kind,
+            ty: ty.cloned(),
+            span,
+            visible_from,
+        });
+    }
+
+    fn declare_params<'a>(&mut self, params: impl IntoIterator<Item = &'a Param>, from: usize) {
+        for param in params {
+            match param {
+                Param::Regular { name, ty, span, .. } => {
+                    self.declare(name, SymbolKind::Parameter, Some(ty), *span, from)
+                }
+                Param::Variadic {
+                    name: Some(name),
+                    ty,
+                    span,
+                } => self.declare(name, SymbolKind::Parameter, ty.as_ref(), *span, from),
+                Param::Variadic { name: None, .. } => {}
+                Param::Pattern { pattern, .. } => {
+                    self.declare_pattern(pattern, SymbolKind::Parameter, from)
+                }
+            }
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: SymbolKind, from: usize) {
+        match pattern {
+            Pattern::Binding { name, ty, span } => {
+                self.declare(name, kind, ty.as_ref(), *span, from)
+            }
+            Pattern::Constructor { args: parts, .. }
+            | Pattern::Tuple {
+                elements: parts, ..
+            } => {
+                for part in parts {
+                    self.declare_pattern(part, kind, from);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    /// Visits `block` in a new scope that starts out with `declare`'s symbols.
+    fn block_with(&mut self, block: &Block, declare: impl FnOnce(&mut Self)) {
+        let previous = self.enter(block.span);
+        declare(self);
+        walk_block(self, block);
+        self.exit(previous);
+    }
+}
+
+impl<'a> Visitor<'a> for SymbolTableBuilder {
+    fn visit_class(&mut self, class: &'a ClassDecl) {
+        let previous = self.enter(class.span);
+        for member in &class.members {
+            match member {
+                ClassMember::Field(field) => self.declare(
+                    &field.name,
+                    SymbolKind::Field,
+                    Some(&field.ty),
+                    field.span,
+                    class.span.start,
+                ),
+                ClassMember::Method(method) => self.declare(
+                    &method.name,
+                    SymbolKind::Function,
+                    method.return_type.as_ref(),
+                    method.span,
+                    class.span.start,
+                ),
+                ClassMember::IndexOperator(_) => {}
+            }
+        }
+        walk_class(self, class);
+        self.exit(previous);
+    }
+
+    fn visit_function(&mut self, function: &'a FunctionDecl) {
+        let previous = self.enter(function.span);
+        self.declare_params(&function.params, function.span.start);
+        walk_function(self, function);
+        self.exit(previous);
+    }
+
+    fn visit_index_operator(&mut self, operator: &'a IndexOperatorDecl) {
+        let previous = self.enter(operator.span);
+        let params = std::iter::once(&operator.receiver).chain(&operator.index_params);
+        self.declare_params(params, operator.span.start);
+        walk_index_operator(self, operator);
+        self.exit(previous);
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        self.block_with(block, |_| {});
+    }
+
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Var(decl) => {
+                self.visit_var_decl(decl);
+                let ty = binding_type(&decl.ty, decl.init.as_ref());
+                self.declare(
+                    &decl.name,
+                    SymbolKind::Variable,
+                    ty.as_ref(),
+                    decl.span,
+                    decl.span.end,
+                );
+            }
+            Stmt::Let(decl) => {
+                self.visit_let_decl(decl);
+                let ty = binding_type(&decl.ty, Some(&decl.init));
+                self.declare(
+                    &decl.name,
+                    SymbolKind::Constant,
+                    ty.as_ref(),
+                    decl.span,
+                    decl.span.end,
+                );
+            }
+            Stmt::For(for_stmt) => {
+                self.visit_type_name(&for_stmt.ty);
+                self.visit_expr(&for_stmt.iterable);
+                self.block_with(&for_stmt.body, |this| {
+                    let (name, ty) = (&for_stmt.name, Some(&for_stmt.ty));
+                    this.declare(
+                        name,
+                        SymbolKind::Variable,
+                        ty,
+                        name.span,
+                        for_stmt.body.span.start,
+                    );
+                });
+            }
+            Stmt::CFor(for_stmt) => {
+                // The loop is a scope of its own, so the variable declared by
+                // the init clause is visible in the condition and update.
+                let previous = self.enter(for_stmt.span);
+                walk_stmt(self, stmt);
+                self.exit(previous);
+            }
+            Stmt::If(if_stmt) => {
+                for link in if_stmt.chain() {
+                    match &link.condition {
+                        Condition::Expr(condition) => {
+                            self.visit_expr(condition);
+                            self.visit_block(&link.then_block);
+                        }
+                        Condition::Binding(binding) => {
+                            self.visit_type_name(&binding.ty);
+                            self.visit_expr(&binding.value);
+                            self.block_with(&link.then_block, |this| {
+                                let (name, ty) = (&binding.name, Some(&binding.ty));
+                                let from = link.then_block.span.start;
+                                this.declare(name, SymbolKind::Variable, ty, binding.span, from);
+                            });
+                        }
+                    }
+                }
+                if let Some(block) = if_stmt.final_else() {
+                    self.visit_block(block);
+                }
+            }
+            Stmt::Match(match_stmt) => {
+                self.visit_expr(&match_stmt.scrutinee);
+                for arm in &match_stmt.arms {
+                    let previous = self.enter(arm.span);
+                    self.declare_pattern(&arm.pattern, SymbolKind::Variable, arm.span.start);
+                    self.visit_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_block(&arm.body);
+                    self.exit(previous);
+                }
+                if let Some(default) = &match_stmt.default {
+                    self.visit_block(default);
+                }
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+}
+
+/// A variable declared inside a function, as listed by [`locals_of`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalVar {
+    pub name: String,
+    /// `None` for an untyped variadic parameter, and for an `auto` variable
+    /// whose type [`infer_literal_type`] cannot tell.
+    pub ty: Option<TypeName>,
+    /// Span of the declaring construct.
+    pub span: Span,
+    /// Number of blocks around the declaration: parameters are at depth 0,
+    /// declarations directly in the body at depth 1. A variable declared in
+    /// the header of a loop or an `if` belongs to the block that follows.
+    pub depth: usize,
+}
+
+/// Lists the `var` and `let` declarations of `function` in source order,
+/// including those in nested blocks, loop variables and `if (var ...)`
+/// bindings. With `include_params`, the parameters come first.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::Item;
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::semantics::locals_of;
+///
+/// let code = "fn f(n: i32) { var a: i32 = n; while (a > 0) { let b: i32 = a; } }";
+/// let Item::Function(function) = &parse_to_ast(code).unwrap().items[0] else { unreachable!() };
+/// let locals: Vec<_> = locals_of(function, true)
+///     .into_iter()
+///     .map(|local| (local.name, local.depth))
+///     .collect();
+/// assert_eq!(locals, [("n".to_string(), 0), ("a".to_string(), 1), ("b".to_string(), 2)]);
+/// ```
+pub fn locals_of(function: &FunctionDecl, include_params: bool) -> Vec<LocalVar> {
+    fn local(name: &Ident, ty: Option<&TypeName>, span: Span, depth: usize) -> LocalVar {
+        LocalVar {
+            name: name.name.clone(),
+            ty: ty.cloned(),
+            span,
+            depth,
+        }
+    }
+
+    fn collect(block: &Block, depth: usize, locals: &mut Vec<LocalVar>) {
+        let inner = depth + 1;
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Var(decl) => {
+                    let ty = binding_type(&decl.ty, decl.init.as_ref());
+                    locals.push(local(&decl.name, ty.as_ref(), decl.span, depth));
+                }
+                Stmt::Let(decl) => {
+                    let ty = binding_type(&decl.ty, Some(&decl.init));
+                    locals.push(local(&decl.name, ty.as_ref(), decl.span, depth));
+                }
+                Stmt::If(if_stmt) => {
+                    for link in if_stmt.chain() {
+                        if let Condition::Binding(binding) = &link.condition {
+                            let ty = Some(&binding.ty);
+                            locals.push(local(&binding.name, ty, binding.span, inner));
+                        }
+                        collect(&link.then_block, inner, locals);
+                    }
+                    if let Some(block) = if_stmt.final_else() {
+                        collect(block, inner, locals);
+                    }
+                }
+                Stmt::While(while_stmt) => collect(&while_stmt.body, inner, locals),
+                Stmt::For(for_stmt) => {
+                    let name = &for_stmt.name;
+                    locals.push(local(name, Some(&for_stmt.ty), name.span, inner));
+                    collect(&for_stmt.body, inner, locals);
+                }
+                Stmt::CFor(for_stmt) => {
+                    if let Some(Stmt::Var(decl)) = for_stmt.init.as_deref() {
+                        let ty = binding_type(&decl.ty, decl.init.as_ref());
+                        locals.push(local(&decl.name, ty.as_ref(), decl.span, inner));
+                    }
+                    collect(&for_stmt.body, inner, locals);
+                }
+                Stmt::Match(match_stmt) => {
+                    let arms = match_stmt.arms.iter().map(|arm| &arm.body);
+                    for body in arms.chain(&match_stmt.default) {
+                        collect(body, inner, locals);
+                    }
+                }
+                Stmt::Return(_)
+                | Stmt::Throw(_)
+                | Stmt::Fallthrough(_)
+                | Stmt::Break(_)
+                | Stmt::Continue(_)
+                | Stmt::Assign(_)
+                | Stmt::MultiAssign(_)
+                | Stmt::Expr(_) => {}
+            }
+        }
+    }
+
+    fn pattern_locals(pattern: &Pattern, locals: &mut Vec<LocalVar>) {
+        match pattern {
+            Pattern::Binding { name, ty, span } => locals.push(local(name, ty.as_ref(), *span, 0)),
+            Pattern::Constructor { args: parts, .. }
+            | Pattern::Tuple {
+                elements: parts, ..
+            } => {
+                for part in parts {
+                    pattern_locals(part, locals);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+        }
+    }
+
+    let mut locals = Vec::new();
+    if include_params {
+        for param in &function.params {
+            match param {
+                Param::Regular { name, ty, span, .. } => {
+                    locals.push(local(name, Some(ty), *span, 0))
+                }
+                Param::Variadic {
+                    name: Some(name),
+                    ty,
+                    span,
+                } => locals.push(local(name, ty.as_ref(), *span, 0)),
+                Param::Variadic { name: None, .. } => {}
+                Param::Pattern { pattern, .. } => pattern_locals(pattern, &mut locals),
+            }
+        }
+    }
+    collect(&function.body, 1, &mut locals);
+    locals
+}
+
+/// A message attached to a span of the source.
+///
+/// Checks built on the [`SymbolTable`] report the same kind of problem as the
+/// rest of this module, so they share its error type.
+pub type Diagnostic = SemanticError;
+
+/// Reports every identifier used in an expression or assigned to without a
+/// declaration in scope at that point.
+///
+/// Parameters, `var` declarations, loop variables, `if (var ...)` and pattern
+/// bindings, class members and top-level declarations all count as
+/// declarations; see [`SymbolTable::lookup`] for the visibility rules. Callees
+/// of function calls and member names after `.` are not identifiers in this
+/// sense and are never reported.
+///
+/// This check is not part of [`check_ast`], because programs often call into
+/// code that is declared in other files.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::semantics::check_undeclared;
+///
+/// let program = parse_to_ast("fn f(x: i32) -> i32 { return print(x + y); }").unwrap();
+/// let diagnostics = check_undeclared(&program);
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].message, "`y` is not declared");
+/// ```
+pub fn check_undeclared(program: &Program) -> Vec<Diagnostic> {
+    struct Undeclared {
+        table: SymbolTable,
+        diagnostics: Vec<Diagnostic>,
+    }
+
+    impl Undeclared {
+        fn check(&mut self, ident: &Ident) {
+            if self.table.lookup(&ident.name, ident.span.start).is_none() {
+                self.diagnostics.push(Diagnostic::new(
+                    format!("`{}` is not declared", ident.name),
+                    ident.span,
+                ));
+            }
+        }
+    }
+
+    impl<'a> Visitor<'a> for Undeclared {
+        fn visit_stmt(&mut self, stmt: &'a Stmt) {
+            match stmt {
+                Stmt::Assign(assign) => self.check(&assign.target),
+                Stmt::MultiAssign(assign) => {
+                    for target in &assign.targets {
+                        self.check(target);
+                    }
+                }
+                _ => {}
+            }
+            walk_stmt(self, stmt);
+        }
+
+        fn visit_expr(&mut self, expr: &'a Expr) {
+            if let Expr::Ident(ident) = expr {
+                self.check(ident);
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    let mut undeclared = Undeclared {
+        table: build_symbol_table(program),
+        diagnostics: Vec::new(),
+    };
+    walk_program(&mut undeclared, program);
+    undeclared.diagnostics
+}
+
+/// Reports declarations whose name is already declared in the same scope: the
+/// top level of the program, the members of a class or the variants of a
+/// choice.
+///
+/// Each repeated declaration is reported at its name, and the first one is
+/// left alone. With `allow_overloads`, functions and methods may share a name
+/// as long as their parameter types differ.
+///
+/// Like [`check_undeclared`], this check is not part of [`check_ast`].
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::semantics::validate_unique_names;
+///
+/// let program = parse_to_ast("fn f(x: i32) {}\nfn f(x: f64) {}").unwrap();
+/// let errors = validate_unique_names(&program, false);
+/// assert_eq!(errors[0].message, "`f` is already declared at the top level");
+/// assert!(validate_unique_names(&program, true).is_empty());
+/// ```
+pub fn validate_unique_names(program: &Program, allow_overloads: bool) -> Vec<SemanticError> {
+    /// A declared name and, for a function, its parameter types.
+    type Declaration<'a> = (&'a Ident, Option<Vec<String>>);
+
+    fn signature(function: &FunctionDecl) -> Vec<String> {
+        let params = function.params.iter().map(|param| match param {
+            Param::Regular { ty, .. } | Param::Pattern { ty, .. } => ty.canonical(),
+            Param::Variadic { ty, .. } => format!(
+                "...{}",
+                ty.as_ref().map(TypeName::canonical).unwrap_or_default()
+            ),
+        });
+        params.collect()
+    }
+
+    let check = |declarations: Vec<Declaration<'_>>, scope: &str, errors: &mut Vec<_>| {
+        let mut seen: Vec<Declaration<'_>> = Vec::new();
+        for (name, params) in declarations {
+            let clash = seen.iter().any(|(other, other_params)| {
+                let overload = allow_overloads
+                    && params.is_some()
+                    && other_params.is_some()
+                    && params != *other_params;
+                other.name == name.name && !overload
+            });
+            if clash {
+                errors.push(SemanticError::new(
+                    format!("`{}` is already declared {scope}", name.name),
+                    name.span,
+                ));
+            } else {
+                seen.push((name, params));
+            }
+        }
+    };
+
+    let mut errors = Vec::new();
+    let top_level = program
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::Function(function) => (&function.name, Some(signature(function))),
+            Item::Class(class) => (&class.name, None),
+            Item::Choice(choice) => (&choice.name, None),
+            Item::Var(decl) => (&decl.name, None),
+            Item::Let(decl) => (&decl.name, None),
+        })
+        .collect();
+    check(top_level, "at the top level", &mut errors);
+
+    for item in &program.items {
+        match item {
+            Item::Class(class) => {
+                let members = class
+                    .members
+                    .iter()
+                    .filter_map(|member| match member {
+                        ClassMember::Field(field) => Some((&field.name, None)),
+                        ClassMember::Method(method) => {
+                            Some((&method.name, Some(signature(method))))
+                        }
+                        ClassMember::IndexOperator(_) => None,
+                    })
+                    .collect();
+                check(
+                    members,
+                    &format!("in class `{}`", class.name.name),
+                    &mut errors,
+                );
+            }
+            Item::Choice(choice) => {
+                let variants = choice
+                    .variants
+                    .iter()
+                    .map(|variant| (&variant.name, None))
+                    .collect();
+                check(
+                    variants,
+                    &format!("in choice `{}`", choice.name.name),
+                    &mut errors,
+                );
+            }
+            _ => {}
+        }
+    }
+    errors.sort_by_key(|error| error.span.start);
+    errors
+}
+
+/// Attaches each of `diagnostics` to the smallest declaration or statement in
+/// `program` whose span covers it, so an editor can show the diagnostic when
+/// hovering that node.
+///
+/// Diagnostics on expressions, types and parameters go to the declaration or
+/// statement that contains them. A diagnostic outside every declaration, such
+/// as one on a pragma, is not attached anywhere.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::{Item, Stmt};
+/// use carbon_parser::parse_to_ast;
+/// use carbon_parser::semantics::{annotate, check_ast};
+///
+/// let mut program = parse_to_ast("fn main() { throw 1; }").unwrap();
+/// let diagnostics = check_ast(&program);
+/// annotate(&mut program, &diagnostics);
+///
+/// let Item::Function(main) = &program.items[0] else { unreachable!() };
+/// let Stmt::Throw(throw) = &main.body.stmts[0] else { unreachable!() };
+/// assert_eq!(throw.diagnostics, diagnostics);
+/// assert!(main.diagnostics.is_empty());
+/// ```
+pub fn annotate(program: &mut Program, diagnostics: &[SemanticError]) {
+    for diagnostic in diagnostics {
+        let span = diagnostic.span;
+        if let Some(item) = program
+            .items
+            .iter_mut()
+            .find(|item| covers(item.span(), span))
+        {
+            item_diagnostics(item, span).push(diagnostic.clone());
+        }
+    }
+}
+
+fn covers(outer: Span, inner: Span) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+fn item_diagnostics(item: &mut Item, span: Span) -> &mut Vec<SemanticError> {
+    match item {
+        Item::Function(function) => function_diagnostics(function, span),
+        Item::Class(class) => {
+            let member = class.members.iter_mut().find(|member| {
+                let member_span = match member {
+                    ClassMember::Field(field) => field.span,
+                    ClassMember::Method(method) => method.span,
+                    ClassMember::IndexOperator(operator) => operator.span,
+                };
+                covers(member_span, span)
+            });
+            match member {
+                Some(ClassMember::Field(field)) => &mut field.diagnostics,
+                Some(ClassMember::Method(method)) => function_diagnostics(method, span),
+                Some(ClassMember::IndexOperator(operator)) => {
+                    block_diagnostics(&mut operator.body, span, &mut operator.diagnostics)
+                }
+                None => &mut class.diagnostics,
+            }
+        }
+        Item::Choice(choice) => &mut choice.diagnostics,
+        Item::Var(decl) => &mut decl.diagnostics,
+        Item::Let(decl) => &mut decl.diagnostics,
+    }
+}
+
+fn function_diagnostics(function: &mut FunctionDecl, span: Span) -> &mut Vec<SemanticError> {
+    block_diagnostics(&mut function.body, span, &mut function.diagnostics)
+}
+
+/// The diagnostics of the statement in `block` that covers `span`, or
+/// `owner` when no statement does.
+fn block_diagnostics<'a>(
+    block: &'a mut Block,
+    span: Span,
+    owner: &'a mut Vec<SemanticError>,
+) -> &'a mut Vec<SemanticError> {
+    match block
+        .stmts
+        .iter_mut()
+        .find(|stmt| covers(stmt.span(), span))
+    {
+        Some(stmt) => stmt_diagnostics(stmt, span),
+        None => owner,
+    }
+}
+
+fn stmt_diagnostics(stmt: &mut Stmt, span: Span) -> &mut Vec<SemanticError> {
+    match stmt {
+        Stmt::Var(decl) => &mut decl.diagnostics,
+        Stmt::Let(decl) => &mut decl.diagnostics,
+        Stmt::Return(ret) => &mut ret.diagnostics,
+        Stmt::Throw(throw) => &mut throw.diagnostics,
+        Stmt::Fallthrough(fallthrough) => &mut fallthrough.diagnostics,
+        Stmt::Break(stmt) => &mut stmt.diagnostics,
+        Stmt::Continue(stmt) => &mut stmt.diagnostics,
+        Stmt::Assign(assign) => &mut assign.diagnostics,
+        Stmt::MultiAssign(assign) => &mut assign.diagnostics,
+        Stmt::Expr(stmt) => &mut stmt.diagnostics,
+        Stmt::If(if_stmt) => if_diagnostics(if_stmt, span),
+        Stmt::While(while_stmt) => {
+            block_diagnostics(&mut while_stmt.body, span, &mut while_stmt.diagnostics)
+        }
+        Stmt::For(for_stmt) => {
+            block_diagnostics(&mut for_stmt.body, span, &mut for_stmt.diagnostics)
+        }
+        Stmt::CFor(for_stmt) => {
+            block_diagnostics(&mut for_stmt.body, span, &mut for_stmt.diagnostics)
+        }
+        Stmt::Match(match_stmt) => {
+            let arm = match_stmt
+                .arms
+                .iter_mut()
+                .map(|arm| &mut arm.body)
+                .chain(&mut match_stmt.default)
+                .find(|body| covers(body.span, span));
+            match arm {
+                Some(body) => block_diagnostics(body, span, &mut match_stmt.diagnostics),
+                None => &mut match_stmt.diagnostics,
+            }
+        }
+    }
+}
+
+fn if_diagnostics(if_stmt: &mut IfStmt, span: Span) -> &mut Vec<SemanticError> {
+    if covers(if_stmt.then_block.span, span) {
+        return block_diagnostics(&mut if_stmt.then_block, span, &mut if_stmt.diagnostics);
+    }
+    match &mut if_stmt.else_branch {
+        Some(ElseBranch::If(next)) if covers(next.span, span) => if_diagnostics(next, span),
+        Some(ElseBranch::Block(block)) if covers(block.span, span) => {
+            block_diagnostics(block, span, &mut if_stmt.diagnostics)
+        }
+        _ => &mut if_stmt.diagnostics,
+    }
+}
@@ -0,0 +1,147 @@
+//! Source locations.
+//!
+//! A [`Span`] is a pair of byte offsets into the text that was parsed. Spans are
+//! cheap to copy and are attached to diagnostics so tools can map a problem back
+//! to the exact piece of source it refers to.
+//!
+//! Editors usually think in lines and columns instead; [`SpanResolver`]
+//! converts between the two.
+
+use std::fmt;
+
+/// A half-open byte range `start..end` into the parsed source.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::Span;
+///
+/// let span = Span::new(3, 7);
+/// assert_eq!(span.len(), 4);
+/// assert!(span.contains(3));
+/// assert!(!span.contains(7));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// Byte offset of the first character covered by the span.
+    pub start: usize,
+    /// Byte offset one past the last character covered by the span.
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a span covering `start..end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Number of bytes covered by the span.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the span covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns `true` if `offset` falls inside the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+}
+
+impl From<pest::Span<'_>> for Span {
+    fn from(span: pest::Span<'_>) -> Self {
+        Span::new(span.start(), span.end())
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A 1-based line and column in the source.
+///
+/// The column counts characters (Unicode scalar values), not bytes, so a
+/// multibyte character advances it by one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for LineColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Converts byte offsets in one source text into lines and columns.
+///
+/// Line starts are computed once in [`SpanResolver::new`], after which each
+/// lookup is a binary search. Lines end at `\n`; the `\r` of a `\r\n` ending
+/// is treated as the last character of its line.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{LineColumn, Span, SpanResolver};
+///
+/// let source = "var a: i32;\r\nvar é: i32;";
+/// let resolver = SpanResolver::new(source);
+///
+/// let start = source.find("é").unwrap();
+/// let (from, to) = resolver.resolve(Span::new(start, start + "é: i32".len()));
+/// assert_eq!(from, LineColumn { line: 2, column: 5 });
+/// assert_eq!(to, LineColumn { line: 2, column: 11 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpanResolver<'a> {
+    source: &'a str,
+    /// Byte offset at which each line starts; the first entry is always 0.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SpanResolver<'a> {
+    /// Indexes the line starts of `source`.
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        SpanResolver {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The line and column of the character at byte `offset`.
+    ///
+    /// Offsets past the end of the source resolve to the end of the source;
+    /// an offset inside a multibyte character resolves to that character.
+    pub fn line_column(&self, offset: usize) -> LineColumn {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(index, c)| line_start + index + c.len_utf8() <= offset)
+            .count();
+        LineColumn {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// The start and end positions of `span`.
+    ///
+    /// The end is exclusive, like the span itself: it is the position just
+    /// after the last character covered.
+    pub fn resolve(&self, span: Span) -> (LineColumn, LineColumn) {
+        (self.line_column(span.start), self.line_column(span.end))
+    }
+}
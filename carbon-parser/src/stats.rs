@@ -0,0 +1,61 @@
+//! Size and shape statistics of a parse tree.
+//!
+//! [`collect_stats`] counts the nodes of a program's parse tree, which gives
+//! complexity metrics a cheap starting point without building the AST.
+
+use crate::{parse_carbon, ParseResult, Rule};
+use pest::iterators::Pair;
+
+/// Counts gathered by [`collect_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    /// Function declarations, including methods.
+    pub functions: usize,
+    /// `var` declarations at any level, including class fields.
+    pub vars: usize,
+    /// Complete expressions, such as an initializer, a condition or a call
+    /// argument. The operands of a binary operator are not counted apart from
+    /// the expression they belong to.
+    pub expressions: usize,
+    /// Number of nodes on the longest path from the root of the parse tree
+    /// down to a leaf; the root alone has depth 1.
+    pub max_depth: usize,
+    /// Every node of the parse tree, the root included.
+    pub total_nodes: usize,
+}
+
+/// Parses `input` as a program and gathers statistics on its parse tree.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::stats::collect_stats;
+///
+/// let stats = collect_stats("fn main() { var x: i32 = 1 + 2; }").unwrap();
+/// assert_eq!(stats.functions, 1);
+/// assert_eq!(stats.vars, 1);
+/// assert_eq!(stats.expressions, 1);
+/// assert!(stats.max_depth > 3);
+/// ```
+pub fn collect_stats(input: &str) -> ParseResult<TreeStats> {
+    let mut stats = TreeStats::default();
+    for pair in parse_carbon(input)? {
+        visit(pair, 1, &mut stats);
+    }
+    Ok(stats)
+}
+
+fn visit(pair: Pair<'_, Rule>, depth: usize, stats: &mut TreeStats) {
+    match pair.as_rule() {
+        Rule::EOI => return,
+        Rule::function_decl => stats.functions += 1,
+        Rule::var_decl => stats.vars += 1,
+        Rule::expression => stats.expressions += 1,
+        _ => {}
+    }
+    stats.total_nodes += 1;
+    stats.max_depth = stats.max_depth.max(depth);
+    for child in pair.into_inner() {
+        visit(child, depth + 1, stats);
+    }
+}
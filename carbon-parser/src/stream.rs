@@ -0,0 +1,248 @@
+//! Declaration-at-a-time parsing for inputs too large to parse in one go.
+//!
+//! [`parse_carbon_streaming`] reads its input line by line and hands each
+//! top-level declaration to the parser as soon as its text is complete, so
+//! only one declaration's text and tree are held at a time.
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::ast::Item;
+use crate::{parse_to_ast, ParseError, ParseResult};
+
+/// A top-level declaration read by [`parse_carbon_streaming`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// The parsed declaration.
+    ///
+    /// Its spans are relative to [`offset`](Self::offset); add the offset to
+    /// get byte positions in the whole input.
+    pub item: Item,
+    /// Byte offset in the whole input that the spans of `item` are relative to.
+    pub offset: usize,
+    /// 1-based line of the input on which the declaration starts.
+    pub line: usize,
+}
+
+/// Parses the top-level declarations read from `reader` one at a time.
+///
+/// The input is split after every `;` or closing `}` that ends a top-level
+/// declaration, skipping those inside brackets, string literals and comments,
+/// and each piece is parsed on its own. Memory use is bounded by the largest
+/// declaration rather than by the size of the input.
+///
+/// A declaration that fails to parse yields an error located by line and
+/// column in the whole input, and parsing continues with the next one. A read
+/// error yields [`ParseError::Io`] and ends the iteration. `//! pragma`
+/// directives are skipped; use [`parse_to_ast`] to collect them.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::ast::Item;
+/// use carbon_parser::parse_carbon_streaming;
+///
+/// let source = "fn a() {}\nvar limit: i32 = 10;\nfn b() {}\n";
+/// let names: Vec<_> = parse_carbon_streaming(source.as_bytes())
+///     .map(|declaration| match declaration.unwrap().item {
+///         Item::Function(function) => function.name.name,
+///         Item::Var(var) => var.name.name,
+///         other => panic!("unexpected {other:?}"),
+///     })
+///     .collect();
+/// assert_eq!(names, ["a", "limit", "b"]);
+/// ```
+pub fn parse_carbon_streaming<R: BufRead>(reader: R) -> Declarations<R> {
+    Declarations {
+        reader,
+        pending: String::new(),
+        scanner: Scanner::default(),
+        offset: 0,
+        line: 1,
+        column: 1,
+        ready: VecDeque::new(),
+        done: false,
+    }
+}
+
+/// Iterator returned by [`parse_carbon_streaming`].
+#[derive(Debug)]
+pub struct Declarations<R> {
+    reader: R,
+    /// Text read after the end of the last declaration.
+    pending: String,
+    scanner: Scanner,
+    /// Byte offset, line and column of the start of `pending` in the input.
+    offset: usize,
+    line: usize,
+    /// Counted in characters, like [`ParseError::location`].
+    column: usize,
+    ready: VecDeque<ParseResult<Declaration>>,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for Declarations<R> {
+    type Item = ParseResult<Declaration>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.ready.pop_front() {
+                return Some(next);
+            }
+            if self.done {
+                return None;
+            }
+            if let Some(end) = self.scanner.scan(&self.pending) {
+                let rest = self.pending.split_off(end);
+                let text = std::mem::replace(&mut self.pending, rest);
+                self.parse(&text);
+                continue;
+            }
+            match self.reader.read_line(&mut self.pending) {
+                Ok(0) => {
+                    let text = std::mem::take(&mut self.pending);
+                    self.parse(&text);
+                    self.done = true;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.ready.push_back(Err(ParseError::Io(err)));
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+impl<R> Declarations<R> {
+    /// Parses the text of one declaration, along with any comments and
+    /// whitespace before it, and moves past it.
+    fn parse(&mut self, text: &str) {
+        match parse_to_ast(text) {
+            Ok(program) => {
+                for item in program.items {
+                    let before = &text[..item.span().start];
+                    let line = self.line + before.matches('\n').count();
+                    self.ready.push_back(Ok(Declaration {
+                        item,
+                        offset: self.offset,
+                        line,
+                    }));
+                }
+            }
+            Err(err) => self.ready.push_back(Err(self.relocate(err, text))),
+        }
+
+        self.offset += text.len();
+        match text.rfind('\n') {
+            Some(newline) => {
+                self.line += text.matches('\n').count();
+                self.column = text[newline + 1..].chars().count() + 1;
+            }
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    /// Reports `err`, found in `text` alone, at its position in the whole
+    /// input.
+    ///
+    /// The position within `text` is shifted by the line, column and byte
+    /// offset `text` starts at; only the first line of `text` starts partway
+    /// through a line of the input. The error's snippet shows earlier text on
+    /// that line as spaces.
+    fn relocate(&self, err: ParseError, text: &str) -> ParseError {
+        let (Some((line, column)), Some(at)) = (err.location(), err.offset()) else {
+            return err;
+        };
+        let shown = text.lines().nth(line - 1).unwrap_or("");
+        if line == 1 {
+            let shown = format!("{}{shown}", " ".repeat(self.column - 1));
+            err.pointing_at(
+                self.offset + at,
+                self.line,
+                self.column + column - 1,
+                &shown,
+            )
+        } else {
+            err.pointing_at(self.offset + at, self.line + line - 1, column, shown)
+        }
+    }
+}
+
+/// Finds where top-level declarations end, remembering its place between
+/// calls so that text is only looked at once.
+#[derive(Debug, Default)]
+struct Scanner {
+    /// Bytes of the pending text already scanned.
+    position: usize,
+    /// Open `{` not yet closed.
+    braces: usize,
+    /// Open `(` and `[` not yet closed. Counted apart from braces so that an
+    /// unclosed `(` cannot hide the `}` that ends a declaration.
+    brackets: usize,
+    state: State,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum State {
+    #[default]
+    Code,
+    String,
+    Char,
+    LineComment,
+    /// Inside this many nested block comments.
+    BlockComment(usize),
+}
+
+impl Scanner {
+    /// Returns the end of the first complete declaration in `text`, resetting
+    /// the scanner for the text after it.
+    fn scan(&mut self, text: &str) -> Option<usize> {
+        let bytes = text.as_bytes();
+        while self.position < bytes.len() {
+            let i = self.position;
+            let next = bytes.get(i + 1).copied();
+            self.position += 1;
+            match (self.state, bytes[i]) {
+                (State::Code, b'"') => self.state = State::String,
+                (State::Code, b'\'') => self.state = State::Char,
+                (State::Code, b'/') if next == Some(b'/') => self.state = State::LineComment,
+                (State::Code, b'/') if next == Some(b'*') => {
+                    self.state = State::BlockComment(1);
+                    self.position += 1;
+                }
+                (State::Code, b'{') => self.braces += 1,
+                (State::Code, b'(' | b'[') => self.brackets += 1,
+                (State::Code, b')' | b']') => self.brackets = self.brackets.saturating_sub(1),
+                (State::Code, b'}') if self.braces <= 1 => return Some(self.finish()),
+                (State::Code, b'}') => self.braces -= 1,
+                (State::Code, b';') if self.braces == 0 && self.brackets == 0 => {
+                    return Some(self.finish())
+                }
+                (State::String | State::Char, b'\\') => self.position += 1,
+                (State::String, b'"') | (State::Char, b'\'') | (State::LineComment, b'\n') => {
+                    self.state = State::Code
+                }
+                (State::BlockComment(depth), b'/') if next == Some(b'*') => {
+                    self.state = State::BlockComment(depth + 1);
+                    self.position += 1;
+                }
+                (State::BlockComment(depth), b'*') if next == Some(b'/') => {
+                    self.state = match depth {
+                        1 => State::Code,
+                        _ => State::BlockComment(depth - 1),
+                    };
+                    self.position += 1;
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn finish(&mut self) -> usize {
+        self.braces = 0;
+        self.brackets = 0;
+        std::mem::take(&mut self.position)
+    }
+}
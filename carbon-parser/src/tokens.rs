@@ -0,0 +1,256 @@
+//! Lexical analysis without building a syntax tree.
+//!
+//! [`tokenize`] splits source text into tokens, skipping whitespace and
+//! comments. It succeeds on any input made of valid tokens, even if they do not
+//! form a valid program, which makes it suitable for quick size estimates and
+//! for highlighting code that is still being written.
+
+use crate::{parse_entry, ParseResult, Rule, Span};
+use pest::iterators::Pair;
+
+/// What kind of token a [`Token`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// An identifier or a keyword; keywords are not reserved words.
+    Identifier,
+    /// An identifier starting with `__`, such as `__builtin_add`, which
+    /// compiler-internal code uses to name intrinsics.
+    Builtin,
+    Integer,
+    Float,
+    String,
+    Char,
+    /// An operator or a delimiter, such as `+=`, `->` or `{`.
+    Punctuation,
+}
+
+/// A single token and its location in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Words the grammar uses as keywords, sorted. They are not reserved, so the
+/// tokenizer reports them as identifiers.
+const KEYWORDS: &[&str] = &[
+    "api",
+    "async",
+    "await",
+    "break",
+    "case",
+    "choice",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "else",
+    "extends",
+    "fallthrough",
+    "false",
+    "final",
+    "fn",
+    "for",
+    "if",
+    "impl",
+    "impls",
+    "import",
+    "in",
+    "inout",
+    "let",
+    "library",
+    "match",
+    "mut",
+    "out",
+    "package",
+    "return",
+    "sealed",
+    "then",
+    "throw",
+    "throws",
+    "true",
+    "var",
+    "where",
+    "while",
+];
+
+/// How a syntax highlighter would color a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleCategory {
+    /// An identifier spelled like a keyword, such as `fn`, `while` or `true`.
+    Keyword,
+    /// Any other identifier, builtins included.
+    Identifier,
+    /// An integer or float literal.
+    Number,
+    /// A string or character literal.
+    String,
+    Punctuation,
+}
+
+impl Token<'_> {
+    /// The highlighting category of the token.
+    pub fn category(&self) -> RuleCategory {
+        match self.kind {
+            TokenKind::Identifier if KEYWORDS.binary_search(&self.text).is_ok() => {
+                RuleCategory::Keyword
+            }
+            TokenKind::Identifier | TokenKind::Builtin => RuleCategory::Identifier,
+            TokenKind::Integer | TokenKind::Float => RuleCategory::Number,
+            TokenKind::String | TokenKind::Char => RuleCategory::String,
+            TokenKind::Punctuation => RuleCategory::Punctuation,
+        }
+    }
+}
+
+/// Splits `input` into tokens.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{tokenize, TokenKind};
+///
+/// let tokens = tokenize("var x: i32 = 42; // answer").unwrap();
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text).collect();
+/// assert_eq!(texts, ["var", "x", ":", "i32", "=", "42", ";"]);
+/// assert_eq!(tokens[5].kind, TokenKind::Integer);
+/// ```
+pub fn tokenize(input: &str) -> ParseResult<Vec<Token<'_>>> {
+    Ok(token_pairs(input)?
+        .map(|pair| Token {
+            kind: match pair.as_rule() {
+                Rule::identifier if is_builtin(pair.as_str()) => TokenKind::Builtin,
+                Rule::identifier => TokenKind::Identifier,
+                Rule::integer_literal => TokenKind::Integer,
+                Rule::float_literal => TokenKind::Float,
+                Rule::string_literal => TokenKind::String,
+                Rule::char_literal => TokenKind::Char,
+                Rule::punctuation => TokenKind::Punctuation,
+                other => unreachable!("not a token: {other:?}"),
+            },
+            text: pair.as_str(),
+            span: pair.as_span().into(),
+        })
+        .collect())
+}
+
+/// Returns the number of tokens in `input`, the same as
+/// `tokenize(input)?.len()` without allocating the tokens.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::count_tokens;
+///
+/// assert_eq!(count_tokens("fn main() { }").unwrap(), 6);
+/// assert!(count_tokens("var s: String = \"unterminated;").is_err());
+/// ```
+pub fn count_tokens(input: &str) -> ParseResult<usize> {
+    Ok(token_pairs(input)?.count())
+}
+
+/// Returns the spans of all tokens of `input` in `category`, in source order.
+///
+/// `input` is tokenized once, like [`tokenize`], so it does not need to be a
+/// valid program.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::{spans_of_kind, RuleCategory};
+///
+/// let code = r#"fn main() { print("hi"); }"#;
+/// let strings = spans_of_kind(code, RuleCategory::String).unwrap();
+/// assert_eq!(strings.len(), 1);
+/// assert_eq!(&code[strings[0].start..strings[0].end], "\"hi\"");
+/// ```
+pub fn spans_of_kind(input: &str, category: RuleCategory) -> ParseResult<Vec<Span>> {
+    Ok(tokenize(input)?
+        .into_iter()
+        .filter(|token| token.category() == category)
+        .map(|token| token.span)
+        .collect())
+}
+
+/// Returns the spans of the comments in `input`, in source order.
+///
+/// Like [`tokenize`], this reads string and character literals as tokens, so
+/// a `//` inside a string does not start a comment.
+pub(crate) fn comment_spans(input: &str) -> ParseResult<Vec<Span>> {
+    Ok(parse_entry(Rule::comment_stream, input)?
+        .filter(|pair| pair.as_rule() == Rule::comment)
+        .map(|pair| pair.as_span().into())
+        .collect())
+}
+
+/// Whether `name` is reserved for compiler intrinsics, which start with `__`.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::tokens::is_builtin;
+///
+/// assert!(is_builtin("__builtin_add"));
+/// assert!(!is_builtin("_private"));
+/// ```
+pub fn is_builtin(name: &str) -> bool {
+    name.starts_with("__")
+}
+
+/// Decodes a string literal as written in source, quotes included, into the
+/// string it denotes.
+///
+/// Returns `None` if `literal` is not a valid string literal, for example
+/// when it contains an unknown escape or `\u{...}` names no character.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::tokens::unescape_string;
+///
+/// assert_eq!(unescape_string(r#""say \"hi\"\n""#).as_deref(), Some("say \"hi\"\n"));
+/// assert_eq!(unescape_string(r#""\u{1F600}""#).as_deref(), Some("\u{1F600}"));
+/// assert_eq!(unescape_string(r#""\q""#), None);
+/// ```
+pub fn unescape_string(literal: &str) -> Option<String> {
+    let body = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => {}
+            _ => {
+                value.push(c);
+                continue;
+            }
+        }
+        value.push(match chars.next()? {
+            '"' => '"',
+            '\'' => '\'',
+            '\\' => '\\',
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (digits, after) = rest.split_once('}')?;
+                if !(1..=6).contains(&digits.len())
+                    || !digits.chars().all(|d| d.is_ascii_hexdigit())
+                {
+                    return None;
+                }
+                chars = after.chars();
+                char::from_u32(u32::from_str_radix(digits, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+    Some(value)
+}
+
+fn token_pairs(input: &str) -> ParseResult<impl Iterator<Item = Pair<'_, Rule>>> {
+    Ok(parse_entry(Rule::token_stream, input)?.filter(|pair| pair.as_rule() != Rule::EOI))
+}
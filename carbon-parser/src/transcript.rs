@@ -0,0 +1,50 @@
+//! Parsing of saved REPL sessions.
+//!
+//! A transcript interleaves input lines, which start with a prompt such as
+//! `>>> `, with the output the REPL printed. [`parse_transcript`] picks out the
+//! input lines and parses each one as a statement of its own.
+
+use crate::ast::Stmt;
+use crate::{parse_entry, ParseResult, Rule};
+
+/// Parses every prompted line of `input` as a standalone statement.
+///
+/// Lines that do not start with `prompt` are treated as REPL output and
+/// skipped, as are prompted lines with nothing after the prompt. As in a
+/// block's final position, an expression may omit its semicolon. The result
+/// has one entry per remaining line, in order, so a syntax error on one line
+/// does not affect the others. Spans in each statement are relative to the
+/// text after the prompt on its line.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::parse_transcript;
+///
+/// let session = "\
+/// >>> var x: i32 = 1;
+/// >>> x += 1;
+/// >>> print(x);
+/// 2
+/// >>> x = ;
+/// ";
+/// let results = parse_transcript(session, ">>> ");
+/// assert_eq!(results.len(), 4);
+/// assert!(results[..3].iter().all(|result| result.is_ok()));
+/// assert!(results[3].is_err());
+/// ```
+pub fn parse_transcript(input: &str, prompt: &str) -> Vec<ParseResult<Stmt>> {
+    input
+        .lines()
+        .filter_map(|line| line.strip_prefix(prompt))
+        .filter(|statement| !statement.trim().is_empty())
+        .map(parse_stmt)
+        .collect()
+}
+
+fn parse_stmt(input: &str) -> ParseResult<Stmt> {
+    let statement = parse_entry(Rule::statement_entry, input)?
+        .next()
+        .expect("the statement rule always produces a single pair");
+    Ok(Stmt::from(statement))
+}
@@ -0,0 +1,61 @@
+//! WebAssembly bindings, enabled by the `wasm` feature.
+//!
+//! [`parse_carbon_wasm`] is exported to JavaScript through `wasm-bindgen` for
+//! running the parser in a browser. Its result is a plain object, either
+//!
+//! ```json
+//! { "ok": true, "tree": { "rule": "program", "span": { ... }, "text": "...", "children": [ ... ] } }
+//! ```
+//!
+//! with the tree in the format of [`to_json`], or
+//!
+//! ```json
+//! { "ok": false, "error": { "code": "E002", "message": "expected `;`", "line": 1, "column": 17 } }
+//! ```
+//!
+//! where `line` and `column` are 1-based, or `null` when the error has no
+//! position.
+
+use crate::json::to_json;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+/// Parses `input` as a program and returns the result object described in
+/// the [module documentation](self).
+#[wasm_bindgen]
+pub fn parse_carbon_wasm(input: &str) -> JsValue {
+    js_sys::JSON::parse(&parse_result_json(input).to_string())
+        .expect("serde_json always produces valid JSON")
+}
+
+/// The object returned by [`parse_carbon_wasm`], as JSON.
+///
+/// Imported JavaScript functions cannot run outside WebAssembly, so native
+/// code and tests use this instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use carbon_parser::wasm::parse_result_json;
+///
+/// let result = parse_result_json("var x: i32 = 1");
+/// assert_eq!(result["ok"], false);
+/// assert_eq!(result["error"]["code"], "E002");
+/// ```
+pub fn parse_result_json(input: &str) -> Value {
+    match to_json(input) {
+        Ok(tree) => json!({ "ok": true, "tree": tree }),
+        Err(error) => {
+            let location = error.location();
+            json!({
+                "ok": false,
+                "error": {
+                    "code": error.code(),
+                    "message": error.summary(),
+                    "line": location.map(|(line, _)| line),
+                    "column": location.map(|(_, column)| column),
+                },
+            })
+        }
+    }
+}
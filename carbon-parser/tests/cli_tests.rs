@@ -0,0 +1,146 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run_parse(source: &str, args: &[&str]) -> Result<Output> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    file.write_all(source.as_bytes())?;
+    let output = Command::new(env!("CARGO_BIN_EXE_carbon-parser"))
+        .arg("parse")
+        .arg(file.path())
+        .args(args)
+        .output()?;
+    Ok(output)
+}
+
+fn child(node: &Value, index: usize) -> &Value {
+    &node["children"][index]
+}
+
+#[test]
+fn test_json_format_is_parse_tree() -> Result<()> {
+    let source = "var x: i32 = 42;";
+    let output = run_parse(source, &["--format", "json"])?;
+    assert!(output.status.success(), "Parsing should succeed");
+
+    let tree: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(tree["rule"], "program");
+    assert_eq!(tree["span"]["start"], 0);
+    assert_eq!(tree["span"]["end"], source.len());
+
+    let decl = child(&tree, 0);
+    assert_eq!(decl["rule"], "var_decl");
+    assert_eq!(decl["text"], source);
+
+    let name = child(decl, 0);
+    assert_eq!(name["rule"], "identifier");
+    assert_eq!(name["text"], "x");
+    assert_eq!(name["children"].as_array().map(Vec::len), Some(0));
+
+    assert_eq!(child(&tree, 1)["rule"], "EOI");
+    Ok(())
+}
+
+#[test]
+fn test_text_format_is_default() -> Result<()> {
+    let source = "fn main() { }";
+    for args in [&[][..], &["--format", "text"][..]] {
+        let output = run_parse(source, args)?;
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout)?;
+        assert!(stdout.starts_with("Parsing file: "));
+        assert!(stdout.contains("Parsing successful!"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_dot_format_is_graph() -> Result<()> {
+    let output = run_parse("fn main() { }", &["--format", "dot"])?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("digraph parse_tree {"));
+    assert!(stdout.contains("n0 -> n1;"));
+    assert!(stdout.trim_end().ends_with('}'));
+    Ok(())
+}
+
+#[test]
+fn test_json_format_reports_errors() -> Result<()> {
+    let output = run_parse("fn main( { }", &["--format", "json"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stdout.is_empty(),
+        "No JSON is printed for invalid input"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_repl_answers_every_line() -> Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_carbon-parser"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(b"x = f(1);\na + * b\n")?;
+    let output = child.wait_with_output()?;
+    assert!(
+        output.status.success(),
+        "A parse error does not end the session"
+    );
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("statement: x = f(1);\n"), "{stdout}");
+    assert!(stdout.contains("  assignment: x = f(1);"), "{stdout}");
+    assert!(
+        stdout.contains("error: expected one of") && stdout.contains("1 │ a + * b"),
+        "The second line is reported as invalid: {stdout}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_no_color_error_output() -> Result<()> {
+    let output = run_parse("fn main() {}\nfn broken {}", &["--no-color"])?;
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.starts_with("error: expected `(`\n"),
+        "The rendered diagnostic comes first: {stderr}"
+    );
+    assert!(
+        stderr.contains("2 │ fn broken {}\n  │           ^\n"),
+        "{stderr}"
+    );
+    assert!(
+        !stderr.contains('\u{1b}'),
+        "No escape codes with --no-color"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_missing_file_reports_path() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("missing.carbon");
+    let output = Command::new(env!("CARGO_BIN_EXE_carbon-parser"))
+        .arg("parse")
+        .arg(&path)
+        .output()?;
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.starts_with("Error: failed to read file '"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("missing.carbon"), "{stderr}");
+    Ok(())
+}
@@ -1,9 +1,56 @@
 use anyhow::Result;
 use carbon_parser::{
-    parse_carbon, parse_expression, parse_function_decl, parse_type_name, parse_var_decl,
+    annotate, build_symbol_table, check_ast, check_program, count_tokens, damage_range,
+    describe_program, has_feature, locals_of, parse_assignment, parse_carbon, parse_carbon_file,
+    parse_carbon_recover, parse_carbon_streaming, parse_class_decl, parse_class_member,
+    parse_expression, parse_expression_ast, parse_expression_with, parse_for_statement,
+    parse_function_decl, parse_if_statement, parse_import_decl, parse_let_decl, parse_package_decl,
+    parse_statement, parse_to_ast, parse_to_ast_with_options, parse_transcript, parse_type_name,
+    parse_type_params, parse_var_decl, parse_while_statement, parse_with_filename,
+    render_diagnostic, render_diagnostic_colored, spans_of_kind, supported_features, tokenize,
+    unescape_string, Edit, LineColumn, LineTokenCache, ParseError, ParseOptions, PrecedenceTable,
+    Rule, RuleCategory, Severity, Span, SpanResolver, SymbolTable, TokenKind,
 };
+
+#[path = "../benches/adversarial/mod.rs"]
+mod adversarial;
+#[path = "../benches/synthetic/mod.rs"]
+mod synthetic;
+
+/// Writes an expression tree with every binary operation and conditional
+/// parenthesized and the source's own parentheses as `[...]`, so trees can be
+/// compared by structure, ignoring spans.
+fn shape(expr: &carbon_parser::ast::Expr) -> String {
+    use carbon_parser::ast::Expr;
+
+    match expr {
+        Expr::Binary(binary) => format!(
+            "({} {} {})",
+            shape(&binary.lhs),
+            binary.op.as_str(),
+            shape(&binary.rhs)
+        ),
+        Expr::Conditional(conditional) => format!(
+            "(if {} then {} else {})",
+            shape(&conditional.condition),
+            shape(&conditional.then_branch),
+            shape(&conditional.else_branch)
+        ),
+        Expr::Paren(paren) => format!("[{}]", shape(&paren.inner)),
+        Expr::Call(call) => format!(
+            "{}({})",
+            call.callee.name,
+            call.args.iter().map(shape).collect::<Vec<_>>().join(", ")
+        ),
+        Expr::Ident(ident) => ident.name.clone(),
+        Expr::Literal(literal) => literal.text.clone(),
+        other => panic!("unexpected expression {other:?}"),
+    }
+}
+
 mod function_decl_tests {
     use super::*;
+    use carbon_parser::ast::{Expr, Item, Stmt};
 
     #[test]
     fn test_simple_function_without_params() -> Result<()> {
@@ -38,6 +85,23 @@ mod function_decl_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_trailing_comma_in_params() -> Result<()> {
+        let code = "fn add(\n    x: i32,\n    y: i32,\n) -> i32 {\n    return x;\n}";
+        let program = parse_to_ast(code)?;
+        let Item::Function(add) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(add.params.len(), 2, "The trailing comma adds no parameter");
+        assert!(parse_function_decl("fn log(format: String, ...,) {}").is_ok());
+        assert!(
+            parse_function_decl("fn f(,) {}").is_err(),
+            "A comma alone is not a parameter list"
+        );
+        assert!(parse_function_decl("fn f(x: i32,,) {}").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_function_without_return_type() -> Result<()> {
         let code = r#"fn print_hello() { return 0; }"#;
@@ -60,6 +124,36 @@ mod function_decl_tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_call_statement_then_return() -> Result<()> {
+        let code = r#"
+            fn main() -> i32 {
+                print(greeting());
+                log.flush();
+                return 0;
+            }
+        "#;
+        assert!(
+            parse_function_decl(code).is_ok(),
+            "Calls ending in `;` are statements"
+        );
+        let program = parse_to_ast(code)?;
+        let Item::Function(main) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert!(matches!(
+            &main.body.stmts[..],
+            [Stmt::Expr(call), Stmt::Expr(_), Stmt::Return(_)]
+                if matches!(&call.expr, Expr::Call(call) if call.callee.name == "print")
+        ));
+        assert!(
+            parse_function_decl("fn main() { print(x) }").is_ok(),
+            "Without `;` the call is the block's tail expression"
+        );
+        assert!(parse_function_decl("fn main() { print(x) return 0; }").is_err());
+        Ok(())
+    }
 }
 
 mod var_decl_tests {
@@ -113,10 +207,23 @@ mod var_decl_tests {
         assert!(result.is_ok(), "A variable with an expression should parse successfully");
         Ok(())
     }
+
+    #[test]
+    fn test_var_with_pointer_type() -> Result<()> {
+        let code = "var p: i32*;";
+        let result = parse_var_decl(code);
+        assert!(
+            result.is_ok(),
+            "A pointer-typed variable should parse successfully"
+        );
+        assert!(parse_var_decl("var name: String* = lookup();").is_ok());
+        Ok(())
+    }
 }
 
 mod expression_tests {
     use super::*;
+    use carbon_parser::ast::{Expr, Item, LiteralKind};
 
     #[test]
     fn test_integer_literal() -> Result<()> {
@@ -126,6 +233,35 @@ mod expression_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hex_binary_and_separated_literals() -> Result<()> {
+        for code in ["0xFF", "0b1010", "1_000", "-0x1F", "0xdead_beef"] {
+            let pair = parse_expression(code)?.next().unwrap();
+            let literal = pair
+                .into_inner()
+                .flatten()
+                .find(|p| p.as_rule() == Rule::integer_literal);
+            assert_eq!(literal.map(|p| p.as_str()), Some(code), "{code}");
+        }
+        for code in ["0xG", "0b102", "1__000", "1_", "0x"] {
+            assert!(parse_expression(code).is_err(), "{code} should be rejected");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_literal_bases_evaluate() -> Result<()> {
+        use carbon_parser::consteval::eval_const_i64;
+        let value = |code: &str| -> Result<Option<i64>> {
+            Ok(eval_const_i64(&parse_expression_ast(code)?))
+        };
+        assert_eq!(value("0xFF")?, Some(255));
+        assert_eq!(value("0b1010 + 1_000")?, Some(1010));
+        assert_eq!(value("-0x10")?, Some(-16));
+        assert_eq!(value("-9_223_372_036_854_775_808")?, Some(i64::MIN));
+        Ok(())
+    }
+
     #[test]
     fn test_float_literal() -> Result<()> {
         let code = "3.14";
@@ -134,6 +270,22 @@ mod expression_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scientific_float_literals() -> Result<()> {
+        for code in ["1.5e10", "2E-3", "6.022e23", ".5", "-1e+9"] {
+            let pair = parse_expression(code)?.next().unwrap();
+            let literal = pair
+                .into_inner()
+                .flatten()
+                .find(|p| p.as_rule() == Rule::float_literal);
+            assert_eq!(literal.map(|p| p.as_str()), Some(code), "{code}");
+        }
+        for code in ["1.2.3", "1e", "2E+", "1.e5"] {
+            assert!(parse_expression(code).is_err(), "{code} should be rejected");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_boolean_literal() -> Result<()> {
         assert!(parse_expression("true").is_ok());
@@ -149,6 +301,29 @@ mod expression_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_char_literal() -> Result<()> {
+        for code in ["'a'", r"'\n'", r"'\''", r"'\u{e9}'", "'\"'"] {
+            let literal = parse_expression(code)?
+                .flatten()
+                .find(|p| p.as_rule() == Rule::char_literal);
+            assert_eq!(literal.map(|p| p.as_str()), Some(code), "{code}");
+        }
+        for code in ["'ab'", "''", "'''", r"'\q'", "'\n'"] {
+            assert!(parse_expression(code).is_err(), "{code} should be rejected");
+        }
+        let program = parse_to_ast(r"var c: char = '\'';")?;
+        let Item::Var(var) = &program.items[0] else {
+            panic!("expected a variable declaration");
+        };
+        let Some(Expr::Literal(literal)) = &var.init else {
+            panic!("expected a literal");
+        };
+        assert_eq!(literal.kind, LiteralKind::Char);
+        assert_eq!(literal.text, r"'\''");
+        Ok(())
+    }
+
     #[test]
     fn test_identifier() -> Result<()> {
         let code = "variable_name";
@@ -184,6 +359,17 @@ mod expression_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_trailing_comma_in_call() -> Result<()> {
+        let Expr::Call(call) = parse_expression_ast("calculate(\n    x,\n    y,\n)")? else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.args.len(), 2, "The trailing comma adds no argument");
+        assert!(parse_expression("point.scale(2,)").is_ok());
+        assert!(parse_expression("calculate(,)").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_comparison_operators() -> Result<()> {
         assert!(parse_expression("x == y").is_ok());
@@ -196,6 +382,7 @@ mod expression_tests {
 
 mod type_name_tests {
     use super::*;
+    use carbon_parser::ast::{Expr, Item};
 
     #[test]
     fn test_integer_types() -> Result<()> {
@@ -204,6 +391,44 @@ mod type_name_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_integer_widths() -> Result<()> {
+        for name in ["i8", "i16", "u8", "u16", "u32", "u64"] {
+            assert!(parse_type_name(name).is_ok(), "{name} should parse");
+            let program = parse_to_ast(&format!("var x: {name} = 1; var t: Type = {name};"))?;
+            let Item::Var(decl) = &program.items[0] else {
+                panic!("expected a variable");
+            };
+            assert_eq!(decl.ty.name, name);
+            let Item::Var(decl) = &program.items[1] else {
+                panic!("expected a variable");
+            };
+            assert!(
+                matches!(&decl.init, Some(Expr::TypeValue(ty)) if ty.name == name),
+                "{name} is a built-in type, not an identifier"
+            );
+        }
+        assert!(parse_type_name("[u8; 4]").is_ok());
+        assert!(parse_type_name("const u16*").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_integer_widths() {
+        for name in ["i17", "u7", "i128", "u0", "f16"] {
+            assert!(
+                parse_type_name(name).is_err(),
+                "{name} is not a supported width and is not a custom type either"
+            );
+        }
+        for name in ["i8x", "u16_t", "int", "u", "Uint8"] {
+            assert!(
+                parse_type_name(name).is_ok(),
+                "{name} is a custom type name"
+            );
+        }
+    }
+
     #[test]
     fn test_float_types() -> Result<()> {
         assert!(parse_type_name("f32").is_ok());
@@ -231,6 +456,142 @@ mod type_name_tests {
         assert!(result.is_ok(), "A custom type should parse successfully");
         Ok(())
     }
+
+    #[test]
+    fn test_array_type() -> Result<()> {
+        let result = parse_type_name("[i32; 4]");
+        assert!(
+            result.is_ok(),
+            "A fixed-size array type should parse successfully"
+        );
+
+        let program = parse_to_ast("var a: [i32 ; 4];")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert_eq!(decl.ty.name, "[i32; 4]");
+        let array = decl.ty.array.as_ref().expect("an array type");
+        assert_eq!(array.element.name, "i32");
+        assert!(matches!(&array.size, Some(Expr::Literal(size)) if size.text == "4"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_array_type() -> Result<()> {
+        let result = parse_type_name("[[i32; 2]; 3]");
+        assert!(
+            result.is_ok(),
+            "A nested array type should parse successfully"
+        );
+
+        let program = parse_to_ast("var grid: [[i32; 2]; 3];")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        let outer = decl.ty.array.as_ref().expect("an array type");
+        let inner = outer.element.array.as_ref().expect("an array element type");
+        assert_eq!(inner.element.name, "i32");
+        assert_eq!(decl.ty.canonical(), "[[i32; 2]; 3]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_slice_type() -> Result<()> {
+        let result = parse_type_name("[String]");
+        assert!(result.is_ok(), "A slice type should parse successfully");
+        assert!(parse_type_name("[[i32; 2]]").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generic_type_single_argument() -> Result<()> {
+        let result = parse_type_name("Vector(i32)");
+        assert!(result.is_ok(), "A generic type should parse successfully");
+
+        let program = parse_to_ast("var v: Vector( i32 );")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert_eq!(decl.ty.name, "Vector");
+        assert_eq!(decl.ty.args.len(), 1);
+        assert_eq!(decl.ty.args[0].name, "i32");
+        assert_eq!(decl.ty.canonical(), "Vector(i32)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_generic_type_multiple_arguments() -> Result<()> {
+        assert!(parse_type_name("HashMap(String, i32)").is_ok());
+        assert!(parse_type_name("HashMap(String, Vector(i32))").is_ok());
+
+        let program = parse_to_ast("var m: HashMap(String,Vector(i32*))*;")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert_eq!(decl.ty.args.len(), 2);
+        assert_eq!(decl.ty.args[1].args[0].pointers.len(), 1);
+        assert_eq!(decl.ty.canonical(), "HashMap(String, Vector(i32*))*");
+        Ok(())
+    }
+
+    #[test]
+    fn test_generic_type_empty_arguments() {
+        assert!(
+            parse_type_name("Vector()").is_err(),
+            "An empty generic argument list should be rejected"
+        );
+        assert!(parse_type_name("HashMap(String,)").is_err());
+        assert!(
+            parse_type_name("i32(bool)").is_err(),
+            "Built-in types take no arguments"
+        );
+    }
+
+    #[test]
+    fn test_pointer_types() -> Result<()> {
+        assert!(parse_type_name("i32*").is_ok());
+        assert!(parse_type_name("String*").is_ok());
+        let result = parse_type_name("CustomType**");
+        assert!(result.is_ok(), "A double pointer should parse successfully");
+
+        let program = parse_to_ast("var p: CustomType * *;")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert_eq!(decl.ty.name, "CustomType");
+        assert_eq!(decl.ty.pointers.len(), 2);
+        assert_eq!(decl.ty.canonical(), "CustomType**");
+        assert!(parse_type_name("*i32").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pointers_and_arrays() -> Result<()> {
+        let program = parse_to_ast("var a: [i32*; 4]; var p: [i32; 4]*;")?;
+        let (Item::Var(array_of_pointers), Item::Var(pointer_to_array)) =
+            (&program.items[0], &program.items[1])
+        else {
+            panic!("expected two variables");
+        };
+        let array = array_of_pointers.ty.array.as_ref().expect("an array type");
+        assert_eq!(array.element.pointers.len(), 1);
+        assert!(array_of_pointers.ty.pointers.is_empty());
+
+        assert!(pointer_to_array.ty.array.is_some());
+        assert_eq!(pointer_to_array.ty.pointers.len(), 1);
+        assert_eq!(pointer_to_array.ty.canonical(), "[i32; 4]*");
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_type_missing_size() {
+        assert!(
+            parse_type_name("[i32;]").is_err(),
+            "An array type with `;` needs a size"
+        );
+        assert!(parse_type_name("[; 4]").is_err());
+        assert!(parse_type_name("[i32; 4").is_err());
+    }
 }
 
 mod program_tests {
@@ -308,6 +669,26 @@ mod program_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_nested_block_comments() -> Result<()> {
+        let code = r#"
+            fn main() -> i32 {
+                /* disabled:
+                   /* old version /* with a note */ */
+                   return 1;
+                */
+                return 0;
+            }
+        "#;
+        let program = parse_to_ast(code)?;
+        assert_eq!(program.to_string(), "fn main() -> i32 {\n  return 0;\n}\n");
+        assert!(
+            parse_carbon("fn main() { /* /* */ }").is_err(),
+            "the inner comment does not close the outer one"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_complex_program() -> Result<()> {
         let code = r#"
@@ -331,6 +712,32 @@ mod program_tests {
 mod error_tests {
     use super::*;
 
+    #[test]
+    fn test_missing_semicolon_code() {
+        let source = "fn main() {\n    var x: i32 = 1\n}";
+        let error = parse_carbon(source).unwrap_err();
+        assert_eq!(error.code(), "E002");
+        assert_eq!(
+            error.with_source(source).code(),
+            "E002",
+            "wrapping keeps the code"
+        );
+        let again = parse_carbon(source).unwrap_err();
+        assert_eq!(again.code(), "E002", "the code is the same on every run");
+    }
+
+    #[test]
+    fn test_error_codes_by_kind() {
+        assert_eq!(
+            parse_carbon("fn f() { while (a) { }").unwrap_err().code(),
+            "E003"
+        );
+        assert_eq!(parse_carbon("fn 1() {}").unwrap_err().code(), "E001");
+        assert_eq!(ParseError::syntax("bad").code(), "E004");
+        let io = std::io::Error::other("disk");
+        assert_eq!(ParseError::Io(io).code(), "E005");
+    }
+
     #[test]
     fn test_invalid_syntax() {
         let code = "fn main( { }";
@@ -360,4 +767,5576 @@ mod error_tests {
             "An invalid identifier should produce an error"
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_error_includes_filename_and_position() {
+        let code = "fn main() {\n    var x: i32 = ;\n}";
+        let err = parse_with_filename(code, "src/main.carbon").unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with("src/main.carbon:2:18:"),
+            "Unexpected error message: {message}"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_filename_success() -> Result<()> {
+        let program = parse_with_filename("fn main() { }", "main.carbon")?;
+        assert_eq!(program.items.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_location_and_expected() {
+        let err = parse_carbon("fn test {").unwrap_err();
+        let (line, _) = err.location().expect("a pest error has a location");
+        assert_eq!(line, 1);
+        assert!(
+            err.expected().contains(&"(".to_string()),
+            "Expected tokens: {:?}",
+            err.expected()
+        );
+    }
+
+    #[test]
+    fn test_expected_tokens_on_many_threads() {
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for _ in 0..20 {
+                        assert!(parse_carbon("fn main() {}").is_ok());
+                        let err = parse_carbon("fn test {").unwrap_err();
+                        assert_eq!(err.expected(), ["("]);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("every thread sees the expected tokens");
+        }
+    }
+
+    #[test]
+    fn test_expected_skips_trivia() {
+        let err = parse_carbon("fn f() { return 1 }").unwrap_err();
+        assert_eq!(err.location(), Some((1, 19)));
+        let expected = err.expected();
+        assert!(expected.contains(&";".to_string()));
+        assert!(expected.contains(&"+".to_string()));
+        assert!(
+            expected
+                .iter()
+                .all(|token| !token.trim().is_empty() && !token.starts_with("//")),
+            "Whitespace and comments are not reported: {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_expected_lists_only_keywords_allowed_there() {
+        let err = parse_carbon("class C {\n  x\n}").unwrap_err();
+        assert_eq!(err.expected(), ["async", "fn", "var", "}"]);
+        let expected = parse_carbon("fn f() { 1 + }").unwrap_err().expected();
+        assert!(expected.contains(&"await".to_string()));
+        assert!(
+            !["var", "let", "throw"]
+                .iter()
+                .any(|keyword| expected.contains(&keyword.to_string())),
+            "Statements cannot start inside an expression: {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_has_no_location() {
+        let err = ParseError::syntax("custom");
+        assert_eq!(err.location(), None);
+        assert!(err.expected().is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error_with_span() {
+        let src = "fn main() {\n    var x: i32 = 1;\n}";
+        let err = ParseError::syntax_at("unused variable", Span::new(20, 21));
+        assert_eq!(err.to_string(), "Syntax error: unused variable at 20..21");
+        assert_eq!(err.code(), "E004");
+        assert!(render_diagnostic(src, &err).contains("2 │     var x: i32 = 1;"));
+
+        let err = err.with_source(src);
+        assert_eq!(err.location(), Some((2, 9)));
+        assert!(err.to_string().starts_with("unused variable\n --> 2:9\n"));
+    }
+
+    #[test]
+    fn test_with_source_snippet() {
+        let src = "fn test {";
+        let err = parse_carbon(src).unwrap_err().with_source(src);
+        assert_eq!(
+            err.to_string(),
+            "expected `(`\n --> 1:9\n  |\n1 | fn test {\n  |         ^"
+        );
+        assert_eq!(err.location(), Some((1, 9)));
+        assert_eq!(err.expected(), vec!["(".to_string()]);
+    }
+
+    #[test]
+    fn test_with_source_snippet_on_later_line() {
+        let src = "fn main() { }\n\nfn f() {\n    return 1\n}";
+        let err = parse_carbon(src).unwrap_err().with_source(src);
+        let message = err.to_string();
+        assert!(
+            message.ends_with(" --> 5:1\n  |\n5 | }\n  | ^"),
+            "Unexpected snippet:\n{message}"
+        );
+        assert!(message.starts_with("expected one of "), "{message}");
+    }
+
+    #[test]
+    fn test_with_source_keeps_syntax_errors() {
+        let err = ParseError::syntax("custom").with_source("fn f() {}");
+        assert_eq!(err.to_string(), "Syntax error: custom");
+    }
+}
+
+mod throw_tests {
+    use super::*;
+
+    #[test]
+    fn test_throw_in_throwing_function() -> Result<()> {
+        let code = "fn fail(code: i32) throws -> i32 { throw code; }";
+        assert!(parse_function_decl(code).is_ok());
+        let errors = check_program(code)?;
+        assert!(
+            errors.is_empty(),
+            "A throw inside a `throws` function should be accepted"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_throw_constructed_error() -> Result<()> {
+        let code = r#"
+            fn open(path: String) throws -> i32 {
+                var fd: i32 = 0;
+                throw IoError(path, 2);
+            }
+        "#;
+        assert!(parse_carbon(code).is_ok());
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_throw_in_non_throwing_function_is_flagged() -> Result<()> {
+        let code = "fn main() -> i32 { throw Error(1); }";
+        assert!(
+            parse_carbon(code).is_ok(),
+            "The throw is syntactically valid"
+        );
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("main"));
+        assert_eq!(
+            &code[errors[0].span.start..errors[0].span.end],
+            "throw Error(1);"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_starting_with_throw_is_not_a_throw() -> Result<()> {
+        let code = "fn f() { throwaway(); }";
+        assert!(
+            check_program(code)?.is_empty(),
+            "throwaway(); is a call, not throw away();"
+        );
+        Ok(())
+    }
+}
+
+mod if_binding_tests {
+    use super::*;
+
+    #[test]
+    fn test_if_binding_scoped_to_then_branch() -> Result<()> {
+        let code = r#"
+            fn first(items: List) -> i32 {
+                if (var x: i32 = head(items)) {
+                    return x;
+                } else {
+                    return 0;
+                }
+            }
+        "#;
+        assert!(
+            parse_carbon(code).is_ok(),
+            "An if with a binding condition should parse successfully"
+        );
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_if_binding_used_in_else_is_flagged() -> Result<()> {
+        let code = r#"
+            fn first(items: List) -> i32 {
+                if (var x: i32 = head(items)) {
+                    return x;
+                } else {
+                    return x;
+                }
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(
+            errors.len(),
+            1,
+            "Using the binding in the else branch is an error"
+        );
+        assert!(errors[0].message.contains("`x`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_if_binding_shadowing_outer_variable() -> Result<()> {
+        let code = r#"
+            fn first(x: i32) -> i32 {
+                if (var x: i32 = head(items)) {
+                    return x;
+                } else {
+                    return x;
+                }
+            }
+        "#;
+        assert!(
+            check_program(code)?.is_empty(),
+            "The else branch refers to the parameter, not the binding"
+        );
+        Ok(())
+    }
+}
+
+mod tail_expr_tests {
+    use super::*;
+    use carbon_parser::ast::Item;
+    use carbon_parser::format::format_carbon;
+
+    fn rules(code: &str) -> Vec<Rule> {
+        parse_function_decl(code)
+            .unwrap()
+            .flatten()
+            .map(|pair| pair.as_rule())
+            .collect()
+    }
+
+    #[test]
+    fn test_block_ending_in_tail_expression() -> Result<()> {
+        let rules = rules("fn id(x: i32) -> i32 { x }");
+        assert!(rules.contains(&Rule::tail_expr));
+        assert!(!rules.contains(&Rule::expression_stmt));
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_ending_in_expression_statement() -> Result<()> {
+        let rules = rules("fn id(x: i32) -> i32 { x; }");
+        assert!(rules.contains(&Rule::expression_stmt));
+        assert!(!rules.contains(&Rule::tail_expr));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tail_expression_span_stops_at_the_expression() -> Result<()> {
+        let code = "fn id(x: i32) -> i32 { x + 1 }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let span = function.body.stmts[0].span();
+        assert_eq!(&code[span.start..span.end], "x + 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatter_keeps_tail_expression() -> Result<()> {
+        let formatted = format_carbon("fn id(x: i32) -> i32 { x }")?;
+        assert_eq!(formatted, "fn id(x: i32) -> i32 {\n  x\n}\n");
+        assert_eq!(format_carbon(&formatted)?, formatted);
+        assert!(rules(&formatted).contains(&Rule::tail_expr));
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatter_keeps_expression_statement() -> Result<()> {
+        let formatted = format_carbon("fn id(x: i32) -> i32 { x; }")?;
+        assert_eq!(formatted, "fn id(x: i32) -> i32 {\n  x;\n}\n");
+        assert_eq!(format_carbon(&formatted)?, formatted);
+        assert!(!rules(&formatted).contains(&Rule::tail_expr));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tail_expression_must_be_last() {
+        assert!(parse_function_decl("fn f() -> i32 { x y; }").is_err());
+        assert!(parse_function_decl("fn f() -> i32 { return x }").is_err());
+    }
+}
+
+mod variadic_tests {
+    use super::*;
+
+    #[test]
+    fn test_variadic_function() -> Result<()> {
+        let code = "fn printf(fmt: String, ...) -> i32 { return 0; }";
+        assert!(
+            parse_function_decl(code).is_ok(),
+            "A trailing `...` parameter should parse successfully"
+        );
+        assert!(check_program(code)?.is_empty());
+
+        let code = "fn sum(args: ...i32) -> i32 { return 0; }";
+        assert!(parse_function_decl(code).is_ok());
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_with_extra_arguments() -> Result<()> {
+        let code = r#"
+            fn printf(fmt: String, ...) -> i32 { return 0; }
+
+            fn main() -> i32 {
+                printf("%d %d %d", 1, 2, 3);
+                return 0;
+            }
+        "#;
+        assert!(parse_carbon(code).is_ok());
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_variadic_not_last_is_flagged() -> Result<()> {
+        let code = "fn bad(args: ...i32, last: bool) -> i32 { return 0; }";
+        assert!(parse_function_decl(code).is_ok());
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1, "A variadic parameter must come last");
+        assert_eq!(
+            &code[errors[0].span.start..errors[0].span.end],
+            "args: ...i32"
+        );
+        Ok(())
+    }
+}
+
+mod damage_range_tests {
+    use super::*;
+
+    const SOURCE: &str = r#"var counter: i32 = 0;
+
+fn increment(x: i32) -> i32 {
+    return x;
+}
+
+fn main() -> i32 {
+    return 0;
+}
+"#;
+
+    fn span_of(text: &str) -> Span {
+        let start = SOURCE.find(text).unwrap();
+        Span::new(start, start + text.len())
+    }
+
+    #[test]
+    fn test_edit_inside_function() -> Result<()> {
+        let at = SOURCE.find("return x").unwrap() + "return ".len();
+        let edit = Edit::new(Span::new(at, at + 1), "x + 1");
+        let expected = span_of("fn increment(x: i32) -> i32 {\n    return x;\n}");
+        assert_eq!(
+            damage_range(SOURCE, edit),
+            expected,
+            "Only the enclosing function should need reparsing"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_in_whitespace_between_declarations() -> Result<()> {
+        let at = SOURCE.find("\n\nfn main").unwrap() + 1;
+        let edit = Edit::new(Span::new(at, at), "\n");
+        let damage = damage_range(SOURCE, edit);
+        assert!(damage.is_empty(), "Whitespace edits should damage nothing");
+        assert_eq!(damage.start, at);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_spanning_two_declarations() -> Result<()> {
+        let start = SOURCE.find("return x").unwrap();
+        let end = SOURCE.find("return 0").unwrap();
+        let damage = damage_range(SOURCE, Edit::new(Span::new(start, end), ""));
+        assert_eq!(damage.start, SOURCE.find("fn increment").unwrap());
+        assert_eq!(damage.end, SOURCE.trim_end().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unparsable_source_damages_everything() -> Result<()> {
+        let source = "fn broken( {";
+        let damage = damage_range(source, Edit::new(Span::new(0, 0), " "));
+        assert_eq!(damage, Span::new(0, source.len()));
+        Ok(())
+    }
+}
+
+mod line_token_cache_tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() -> i32 {
+  var total: i32 = 0;
+  /* add the
+     first values */
+  total += 1;
+  return total;
+}
+";
+
+    /// Applies `edit` to `old_src` and to `cache`, checking that the cache
+    /// then matches one built from scratch.
+    fn edit(
+        cache: &mut LineTokenCache,
+        old_src: &str,
+        edit: Edit,
+    ) -> Result<(String, std::ops::Range<usize>)> {
+        let mut new_src = old_src.to_string();
+        new_src.replace_range(edit.range.start..edit.range.end, &edit.new_text);
+        let lines = cache.update(old_src, &new_src, edit)?;
+        assert_eq!(*cache, LineTokenCache::new(&new_src)?);
+        Ok((new_src, lines))
+    }
+
+    fn span_of(source: &str, text: &str) -> Span {
+        let start = source.find(text).unwrap();
+        Span::new(start, start + text.len())
+    }
+
+    #[test]
+    fn test_single_line_edit() -> Result<()> {
+        let mut cache = LineTokenCache::new(SOURCE)?;
+        assert_eq!(cache.line_count(), 8);
+        assert_eq!(cache.tokens(4).len(), 4);
+
+        let change = Edit::new(span_of(SOURCE, "+= 1"), "-= 2 * 3");
+        let (_, lines) = edit(&mut cache, SOURCE, change)?;
+        assert_eq!(lines, 4..5);
+        assert_eq!(cache.tokens(4).len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inserted_line() -> Result<()> {
+        let mut cache = LineTokenCache::new(SOURCE)?;
+        let at = SOURCE.find("  return").unwrap();
+        let (_, lines) = edit(
+            &mut cache,
+            SOURCE,
+            Edit::new(Span::new(at, at), "  log(total);\n"),
+        )?;
+        // The new line and the line it was inserted at the start of.
+        assert_eq!(lines, 5..7);
+        assert_eq!(cache.line_count(), 9);
+        assert_eq!(cache.tokens(6)[0].kind, TokenKind::Identifier);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_inside_block_comment_widens_to_comment() -> Result<()> {
+        let mut cache = LineTokenCache::new(SOURCE)?;
+        assert!(cache.tokens(2).is_empty() && cache.tokens(3).is_empty());
+
+        let change = Edit::new(span_of(SOURCE, "first"), "next");
+        let (_, lines) = edit(&mut cache, SOURCE, change)?;
+        assert_eq!(lines, 2..4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_opening_a_comment_reaches_its_end() -> Result<()> {
+        let mut cache = LineTokenCache::new(SOURCE)?;
+        // Comment out the declaration together with the comment after it,
+        // which nests inside the new one.
+        let start = SOURCE.find("var total").unwrap();
+        let end = SOURCE.find("values */").unwrap() + "values */".len();
+        let commented_out = format!("/* {} */", &SOURCE[start..end]);
+        let (commented, lines) = edit(
+            &mut cache,
+            SOURCE,
+            Edit::new(Span::new(start, end), commented_out.clone()),
+        )?;
+        assert_eq!(lines, 1..4);
+        assert!(cache.tokens(1).is_empty());
+        assert_eq!(cache.tokens(4).len(), 4);
+
+        let original = SOURCE[start..end].to_string();
+        let range = Span::new(start, start + commented_out.len());
+        let (restored, lines) = edit(&mut cache, &commented, Edit::new(range, original))?;
+        assert_eq!(restored, SOURCE);
+        assert_eq!(lines, 1..4);
+        assert_eq!(cache, LineTokenCache::new(SOURCE)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_line_string_belongs_to_its_first_line() -> Result<()> {
+        let source = "var s: String = \"one\ntwo\";\nvar n: i32 = 1;\n";
+        let mut cache = LineTokenCache::new(source)?;
+        let string = cache.tokens(0)[5];
+        assert_eq!(string.kind, TokenKind::String);
+        assert_eq!(string.span, Span::new(16, 25));
+        assert_eq!(cache.tokens(1).len(), 1);
+
+        let (_, lines) = edit(&mut cache, source, Edit::new(span_of(source, "two"), "2"))?;
+        assert_eq!(lines, 0..2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_comment_spans_lines() -> Result<()> {
+        let source = "/* a\n/* b */\nc */ var x: i32;\n";
+        let mut cache = LineTokenCache::new(source)?;
+        assert!(cache.tokens(1).is_empty());
+        assert_eq!(cache.tokens(2).len(), 5);
+
+        let (_, lines) = edit(&mut cache, source, Edit::new(span_of(source, "b"), "bb"))?;
+        assert_eq!(lines, 0..3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_untokenizable_edit_keeps_cache() -> Result<()> {
+        let mut cache = LineTokenCache::new(SOURCE)?;
+        let at = SOURCE.find("1;").unwrap();
+        let mut new_src = SOURCE.to_string();
+        new_src.insert(at, '"');
+        assert!(cache
+            .update(SOURCE, &new_src, Edit::new(Span::new(at, at), "\""))
+            .is_err());
+        assert_eq!(cache, LineTokenCache::new(SOURCE)?);
+        Ok(())
+    }
+}
+
+mod param_direction_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Param, ParamDirection};
+
+    fn directions(code: &str) -> Result<Vec<(String, ParamDirection)>> {
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        Ok(function
+            .params
+            .iter()
+            .map(|param| match param {
+                Param::Regular {
+                    direction, name, ..
+                } => (name.name.clone(), *direction),
+                other => panic!("unexpected parameter {other:?}"),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_inout_parameter() -> Result<()> {
+        let code = "fn bump(inout counter: i32) { counter += 1; }";
+        assert!(parse_function_decl(code).is_ok());
+        assert_eq!(
+            directions(code)?,
+            [("counter".to_string(), ParamDirection::InOut)]
+        );
+        assert!(
+            check_program(code)?.is_empty(),
+            "an `inout` parameter may be read"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_parameter() -> Result<()> {
+        let code = r#"
+            fn divide(a: i32, b: i32, out quotient: i32, out remainder: i32) {
+                quotient = a / b;
+                remainder = a - quotient * b;
+            }
+        "#;
+        assert_eq!(
+            directions(code)?,
+            [
+                ("a".to_string(), ParamDirection::In),
+                ("b".to_string(), ParamDirection::In),
+                ("quotient".to_string(), ParamDirection::Out),
+                ("remainder".to_string(), ParamDirection::Out),
+            ]
+        );
+        assert!(check_program(code)?.is_empty());
+        assert!(carbon_parser::format::format_carbon(code)?
+            .contains("fn divide(a: i32, b: i32, out quotient: i32, out remainder: i32)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reading_unassigned_out_parameter() -> Result<()> {
+        let code = r#"
+            fn fill(out total: i32, out count: i32) {
+                total += 1;
+                count = total;
+                log(count, total);
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(
+            errors[0].message,
+            "`out` parameter `total` is read before it is assigned"
+        );
+        assert_eq!(errors[0].span.start, code.find("total +=").unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_parameter_is_in() -> Result<()> {
+        assert_eq!(
+            directions("fn show(value: i32) {}")?,
+            [("value".to_string(), ParamDirection::In)]
+        );
+        assert_eq!(ParamDirection::default(), ParamDirection::In);
+        assert_eq!(ParamDirection::In.keyword(), None);
+        assert_eq!(
+            directions("fn f(out: i32, inout: bool) {}")?,
+            [
+                ("out".to_string(), ParamDirection::In),
+                ("inout".to_string(), ParamDirection::In),
+            ],
+            "`out` and `inout` are still valid parameter names"
+        );
+        assert!(parse_function_decl("fn f(outer: i32) {}").is_ok());
+        Ok(())
+    }
+}
+
+mod comptime_param_tests {
+    use super::*;
+
+    #[test]
+    fn test_comptime_parameter() -> Result<()> {
+        let code = "fn zeros(N:! i32) -> Array { return make(N); }";
+        assert!(
+            parse_function_decl(code).is_ok(),
+            "A `:!` compile-time parameter should parse successfully"
+        );
+        let rules: Vec<Rule> = parse_function_decl(code)?
+            .flatten()
+            .map(|p| p.as_rule())
+            .collect();
+        assert!(rules.contains(&Rule::comptime_marker));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mixed_comptime_and_runtime_parameters() -> Result<()> {
+        let code = "fn fill(T:! Type, N:! i32 = 4, value: T, count: i32) { return 0; }";
+        let function = parse_function_decl(code)?.next().unwrap();
+        let markers: Vec<bool> = function
+            .into_inner()
+            .find(|p| p.as_rule() == Rule::parameter_list)
+            .unwrap()
+            .into_inner()
+            .map(|param| {
+                param
+                    .into_inner()
+                    .any(|p| p.as_rule() == Rule::comptime_marker)
+            })
+            .collect();
+        assert_eq!(markers, vec![true, true, false, false]);
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_comptime_parameter_with_runtime_default_is_flagged() -> Result<()> {
+        let code = "fn grow(N:! i32 = size(), x: i32 = size()) { return 0; }";
+        let errors = check_program(code)?;
+        assert_eq!(
+            errors.len(),
+            1,
+            "Only the compile-time parameter's default must be constant"
+        );
+        assert!(errors[0].message.contains("`N`"));
+        Ok(())
+    }
+}
+
+mod ast_tests {
+    use super::*;
+    use carbon_parser::ast::{BinaryOp, Condition, Expr, Item, LiteralKind, Param, Program, Stmt};
+
+    fn function(program: &Program, index: usize) -> &carbon_parser::ast::FunctionDecl {
+        match &program.items[index] {
+            Item::Function(function) => function,
+            other => panic!("expected a function, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_program_shape() -> Result<()> {
+        let code = "var limit: i32 = 10;\nfn add(x: i32, y: i32) -> i32 { return x + y; }";
+        let program = parse_to_ast(code)?;
+        assert_eq!(program.items.len(), 2);
+        assert_eq!(program.span, Span::new(0, code.len()));
+
+        let Item::Var(limit) = &program.items[0] else {
+            panic!("expected a variable declaration first");
+        };
+        assert_eq!(limit.name.name, "limit");
+        assert_eq!(limit.ty.name, "i32");
+        assert_eq!(limit.span, Span::new(0, 20));
+        let Some(Expr::Literal(init)) = &limit.init else {
+            panic!("expected a literal initializer");
+        };
+        assert_eq!(init.kind, LiteralKind::Integer);
+        assert_eq!(init.text, "10");
+        assert_eq!(&code[init.span.start..init.span.end], "10");
+
+        let add = function(&program, 1);
+        assert_eq!(add.name.name, "add");
+        assert_eq!(&code[add.span.start..add.span.end], &code[21..]);
+        assert_eq!(add.params.len(), 2);
+        assert_eq!(
+            add.return_type.as_ref().map(|t| t.name.as_str()),
+            Some("i32")
+        );
+        assert!(!add.throws);
+        assert_eq!(add.body.stmts.len(), 1);
+
+        let Stmt::Return(ret) = &add.body.stmts[0] else {
+            panic!("expected a return statement");
+        };
+        let Some(Expr::Binary(sum)) = &ret.value else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(sum.op, BinaryOp::Add);
+        assert_eq!(&code[sum.span.start..sum.span.end], "x + y");
+        assert!(matches!(sum.lhs.as_ref(), Expr::Ident(x) if x.name == "x"));
+        assert!(matches!(sum.rhs.as_ref(), Expr::Ident(y) if y.name == "y"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_precedence() -> Result<()> {
+        let program = parse_to_ast("var v: i32 = 1 + 2 * 3;")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable declaration");
+        };
+        let Some(Expr::Binary(add)) = &decl.init else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(add.op, BinaryOp::Add);
+        assert!(matches!(add.rhs.as_ref(), Expr::Binary(mul) if mul.op == BinaryOp::Mul));
+        Ok(())
+    }
+
+    #[test]
+    fn test_statement_kinds() -> Result<()> {
+        let code = r#"
+            fn load(N:! i32, path: String, ...) throws -> i32 {
+                if (var fd: i32 = open(path)) {
+                    fd
+                } else {
+                    throw IoError(path);
+                }
+                log(path);
+            }
+        "#;
+        let program = parse_to_ast(code)?;
+        let load = function(&program, 0);
+        assert!(load.throws);
+        assert!(matches!(
+            load.params[0],
+            Param::Regular { comptime: true, .. }
+        ));
+        assert!(matches!(
+            load.params[1],
+            Param::Regular {
+                comptime: false,
+                ..
+            }
+        ));
+        assert!(matches!(load.params[2], Param::Variadic { name: None, .. }));
+
+        let Stmt::If(if_stmt) = &load.body.stmts[0] else {
+            panic!("expected an if statement");
+        };
+        assert!(matches!(&if_stmt.condition, Condition::Binding(b) if b.name.name == "fd"));
+        assert!(matches!(
+            &if_stmt.then_block.stmts[0],
+            Stmt::Expr(tail) if !tail.has_semicolon
+        ));
+        let else_block = if_stmt.final_else().expect("else block");
+        assert!(matches!(else_block.stmts[0], Stmt::Throw(_)));
+        assert!(matches!(
+            &load.body.stmts[1],
+            Stmt::Expr(call) if call.has_semicolon && matches!(call.expr, Expr::Call(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_program_is_error() {
+        assert!(parse_to_ast("fn main( { }").is_err());
+    }
+}
+
+mod match_guard_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Pattern, Stmt};
+
+    fn match_arms(code: &str) -> Vec<carbon_parser::ast::MatchArm> {
+        let program = parse_to_ast(code).unwrap();
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        match &function.body.stmts[0] {
+            Stmt::Match(stmt) => stmt.arms.clone(),
+            other => panic!("expected a match statement, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_guarded_and_unguarded_arms() -> Result<()> {
+        let code = r#"
+            fn sign(value: Option) -> i32 {
+                match (value) {
+                    case Some(x) if x > 0 => { return 1; }
+                    case Some(x) => { return 0; }
+                    default => { return -1; }
+                }
+            }
+        "#;
+        assert!(
+            parse_function_decl(code).is_ok(),
+            "A match with guarded arms should parse successfully"
+        );
+        let arms = match_arms(code);
+        assert_eq!(arms.len(), 2);
+        assert!(arms[0].guard.is_some(), "The first arm has a guard");
+        assert!(arms[1].guard.is_none(), "The second arm has no guard");
+        assert!(
+            matches!(&arms[0].pattern, Pattern::Constructor { name, .. } if name.name == "Some")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_guard_on_literal_and_wildcard_patterns() -> Result<()> {
+        let code = r#"
+            fn check(n: i32, limit: i32) -> i32 {
+                match (n) {
+                    case 0 => { return 0; }
+                    case _ if n > limit => { return limit; }
+                }
+            }
+        "#;
+        let arms = match_arms(code);
+        assert!(matches!(arms[0].pattern, Pattern::Literal(_)));
+        assert!(matches!(arms[1].pattern, Pattern::Wildcard(_)));
+        assert!(arms[1].guard.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guard_without_condition_is_error() {
+        let code = r#"
+            fn sign(value: Option) -> i32 {
+                match (value) {
+                    case Some(x) if => { return 1; }
+                }
+            }
+        "#;
+        assert!(
+            parse_function_decl(code).is_err(),
+            "A guard must have a condition expression"
+        );
+    }
+}
+
+mod fallthrough_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+    use carbon_parser::format::format_carbon;
+
+    #[test]
+    fn test_arm_ending_in_fallthrough() -> Result<()> {
+        let code = r#"
+            fn describe(n: i32) -> i32 {
+                match (n) {
+                    case 0 => { log(n); fallthrough; }
+                    case 1 => { return 1; }
+                    default => { return 2; }
+                }
+            }
+        "#;
+        let program = parse_to_ast(code)?;
+        let Some(Item::Function(function)) = program.items.first() else {
+            panic!("expected a function");
+        };
+        let Stmt::Match(match_stmt) = &function.body.stmts[0] else {
+            panic!("expected a match");
+        };
+        let last = match_stmt.arms[0].body.stmts.last();
+        assert!(matches!(last, Some(Stmt::Fallthrough(_))));
+        assert!(check_program(code)?.is_empty());
+        assert!(format_carbon(code)?.contains("    fallthrough;\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallthrough_not_at_arm_end() -> Result<()> {
+        let code = r#"
+            fn f(n: i32) {
+                match (n) {
+                    case 0 => { fallthrough; log(n); }
+                    case 1 => { if (n > 0) { fallthrough; } }
+                    default => { fallthrough; }
+                }
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        for error in &errors {
+            assert_eq!(
+                error.message,
+                "`fallthrough` must be the last statement of a `case` arm"
+            );
+            assert_eq!(&code[error.span.start..error.span.end], "fallthrough;");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallthrough_outside_match() -> Result<()> {
+        let errors = check_program("fn f() { while (true) { fallthrough; } }")?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "`fallthrough` outside of a `match`");
+        Ok(())
+    }
+}
+
+mod constant_condition_tests {
+    use super::*;
+    use carbon_parser::Severity;
+
+    #[test]
+    fn test_if_true_is_flagged_always_true() -> Result<()> {
+        let code = "fn main() -> i32 { if (true) { return 1; } return 0; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, Severity::Warning);
+        assert!(errors[0].message.contains("always true"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "true");
+        Ok(())
+    }
+
+    #[test]
+    fn test_constant_comparison_is_flagged_always_false() -> Result<()> {
+        let code = "fn main() -> i32 { if (2 < 1 || false) { return 1; } return 0; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("always false"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_constant_condition_is_clean() -> Result<()> {
+        let code = "fn main(x: i32) -> i32 { if (x > 0) { return 1; } return 0; }";
+        assert!(
+            check_program(code)?.is_empty(),
+            "A condition depending on a variable is not constant"
+        );
+        Ok(())
+    }
+}
+
+mod constant_folding_tests {
+    use super::*;
+    use carbon_parser::ast::Item;
+    use carbon_parser::consteval::fold_constants;
+
+    /// Folds `code` as a variable initializer and prints it back.
+    fn folded(code: &str) -> Result<String> {
+        let mut program = parse_to_ast(&format!("var v: i64 = {code};"))?;
+        let Item::Var(decl) = &mut program.items[0] else {
+            unreachable!("a variable declaration");
+        };
+        decl.init = decl.init.take().map(fold_constants);
+        let text = program.to_string();
+        let init = text
+            .trim_end()
+            .strip_prefix("var v: i64 = ")
+            .and_then(|rest| rest.strip_suffix(';'));
+        Ok(init.expect("an initializer").to_string())
+    }
+
+    #[test]
+    fn test_full_folding() -> Result<()> {
+        assert_eq!(folded("2 + 3 * 4")?, "14");
+        assert_eq!(folded("(10 - 4) / 2 % 2")?, "1");
+        assert_eq!(folded("1 < 2 && 3 != 3")?, "false");
+        let expr = fold_constants(parse_expression_ast("  (1 + 1)")?);
+        assert_eq!(
+            expr.span(),
+            Span::new(2, 9),
+            "the literal keeps the folded span"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_folding() -> Result<()> {
+        assert_eq!(folded("x + (1 + 2)")?, "x + 3");
+        assert_eq!(folded("f(2 * 8, y) - 1")?, "f(16, y) - 1");
+        assert_eq!(
+            folded("x + 1 + 2")?,
+            "x + 1 + 2",
+            "(x + 1) + 2 has no constant part"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_is_left_unfolded() -> Result<()> {
+        assert_eq!(
+            folded("9223372036854775807 + 1")?,
+            "9223372036854775807 + 1"
+        );
+        assert_eq!(folded("x + (1 / 0) + (2 * 2)")?, "x + (1 / 0) + 4");
+        assert_eq!(folded("-9223372036854775807 - 1")?, "-9223372036854775808");
+        Ok(())
+    }
+}
+
+mod if_else_tests {
+    use super::*;
+    use carbon_parser::ast::{ElseBranch, IfStmt, Item, Stmt};
+
+    fn first_if(code: &str) -> IfStmt {
+        let program = parse_to_ast(code).unwrap();
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        match &function.body.stmts[0] {
+            Stmt::If(if_stmt) => if_stmt.clone(),
+            other => panic!("expected an if statement, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_if_without_else() -> Result<()> {
+        let result = parse_if_statement("if (x > 0) { return x; }");
+        assert!(
+            result.is_ok(),
+            "An if without else should parse successfully"
+        );
+        let if_stmt = first_if("fn f(x: i32) { if (x > 0) { return x; } }");
+        assert!(if_stmt.else_branch.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_if_with_else() -> Result<()> {
+        let result = parse_if_statement("if (x > 0) { return x; } else { return 0; }");
+        assert!(result.is_ok(), "An if with else should parse successfully");
+        Ok(())
+    }
+
+    #[test]
+    fn test_else_if_chain() -> Result<()> {
+        let code = r#"
+            fn sign(x: i32) -> i32 {
+                if (x < 0) {
+                    return -1;
+                } else if (x == 0) {
+                    return 0;
+                } else {
+                    return 1;
+                }
+            }
+        "#;
+        assert!(parse_carbon(code).is_ok());
+        let if_stmt = first_if(code);
+        assert_eq!(if_stmt.chain().count(), 2);
+        assert!(matches!(if_stmt.else_branch, Some(ElseBranch::If(_))));
+        assert!(if_stmt.final_else().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_if_in_then_branch() -> Result<()> {
+        let code = "fn f(a: bool, b: bool) { if (a) { if (b) { g(); } } }";
+        let outer = first_if(code);
+        assert!(matches!(outer.then_block.stmts[0], Stmt::If(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_else_binds_to_enclosing_if() -> Result<()> {
+        // The else follows the outer block, so it belongs to the outer if.
+        let outer = first_if("fn f(a: bool, b: bool) { if (a) { if (b) { g(); } } else { h(); } }");
+        assert!(outer.final_else().is_some());
+        let Stmt::If(inner) = &outer.then_block.stmts[0] else {
+            panic!("expected a nested if");
+        };
+        assert!(inner.else_branch.is_none());
+
+        // Inside the braces, the else belongs to the inner if.
+        let outer = first_if("fn f(a: bool, b: bool) { if (a) { if (b) { g(); } else { h(); } } }");
+        assert!(outer.else_branch.is_none());
+        let Stmt::If(inner) = &outer.then_block.stmts[0] else {
+            panic!("expected a nested if");
+        };
+        assert!(inner.final_else().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_else_without_block_is_error() {
+        assert!(parse_if_statement("if (x) { return 1; } else return 0;").is_err());
+        assert!(parse_if_statement("if (x) { return 1; } else").is_err());
+    }
+
+    #[test]
+    fn test_binding_hidden_in_else_if() -> Result<()> {
+        let code = r#"
+            fn first(items: List) -> i32 {
+                if (var x: i32 = head(items)) {
+                    return x;
+                } else if (x > 0) {
+                    return 1;
+                }
+                return 0;
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1, "The binding is not visible in else if");
+        Ok(())
+    }
+}
+
+mod while_tests {
+    use super::*;
+    use carbon_parser::ast::{BinaryOp, Expr, Item, Stmt};
+
+    #[test]
+    fn test_while_empty_body() -> Result<()> {
+        let result = parse_while_statement("while (running) { }");
+        assert!(
+            result.is_ok(),
+            "A while loop with an empty body should parse successfully"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_while_single_return() -> Result<()> {
+        let result = parse_while_statement("while (true) { return 1; }");
+        assert!(
+            result.is_ok(),
+            "A while loop containing a return should parse successfully"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_while_comparison_condition() -> Result<()> {
+        let code = "fn f(n: i32) { while (i < n) { step(i); } }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::While(while_stmt) = &function.body.stmts[0] else {
+            panic!("expected a while loop");
+        };
+        assert!(matches!(
+            &while_stmt.condition,
+            Expr::Binary(binary) if binary.op == BinaryOp::Lt
+        ));
+        assert_eq!(while_stmt.body.stmts.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_while_nested_loop_and_var_decl() -> Result<()> {
+        let code = r#"
+            while (i < n) {
+                var j: i32 = 0;
+                while (j < n) {
+                    visit(i, j);
+                }
+            }
+        "#;
+        assert!(
+            parse_while_statement(code).is_ok(),
+            "Nested loops and declarations should parse successfully"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_while_requires_parentheses() {
+        assert!(parse_while_statement("while running { }").is_err());
+    }
+
+    #[test]
+    fn test_constant_while_conditions() -> Result<()> {
+        let never = check_program("fn f() { while (1 > 2) { g(); } }")?;
+        assert_eq!(never.len(), 1);
+        assert!(never[0].message.contains("always false"));
+
+        assert!(
+            check_program("fn f() { while (true) { g(); } }")?.is_empty(),
+            "An infinite loop written as while (true) is not flagged"
+        );
+
+        let always = check_program("fn f() { while (1 < 2) { g(); } }")?;
+        assert_eq!(always.len(), 1, "{always:?}");
+        assert!(always[0].message.contains("always true"));
+        assert_eq!(check_program("fn f() { while ((true)) { g(); } }")?.len(), 1);
+        Ok(())
+    }
+}
+
+mod async_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Item, Stmt};
+
+    #[test]
+    fn test_await_in_async_function() -> Result<()> {
+        let code = "async fn main() -> i32 { var x: i32 = await fetch(1); return x; }";
+        assert!(
+            parse_function_decl(code).is_ok(),
+            "An async function with await should parse successfully"
+        );
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert!(function.is_async);
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_await_then_try() -> Result<()> {
+        let code = "async fn main() throws -> i32 { return await f()?; }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::Return(ret) = &function.body.stmts[0] else {
+            panic!("expected a return");
+        };
+        let Some(Expr::Try(try_expr)) = &ret.value else {
+            panic!("expected `?` to apply to the awaited value");
+        };
+        assert!(matches!(*try_expr.operand, Expr::Await(_)));
+        assert_eq!(&code[try_expr.span.start..try_expr.span.end], "await f()?");
+        Ok(())
+    }
+
+    #[test]
+    fn test_await_covers_the_whole_postfix_chain() -> Result<()> {
+        let code = "await client.fetch(1)?";
+        let Expr::Try(try_expr) = parse_expression_ast(code)? else {
+            panic!("expected `?` outermost");
+        };
+        let Expr::Await(await_expr) = &*try_expr.operand else {
+            panic!("expected `?` to apply to the awaited value");
+        };
+        assert!(matches!(*await_expr.operand, Expr::MethodCall(_)));
+        assert_eq!(
+            &code[await_expr.span.start..await_expr.span.end],
+            "await client.fetch(1)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_await_in_binary_expression() -> Result<()> {
+        assert!(parse_expression("await a() + await b()").is_ok());
+        assert!(parse_expression("x?? * 2").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_await_in_non_async_function_is_flagged() -> Result<()> {
+        let code = "fn main() -> i32 { if (await ready()) { return 1; } return 0; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("async"));
+        assert_eq!(
+            &code[errors[0].span.start..errors[0].span.end],
+            "await ready()"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_starting_with_await_is_not_awaited() -> Result<()> {
+        let code = "fn f(awaitable: i32) -> i32 { return awaitable; }";
+        assert!(
+            check_program(code)?.is_empty(),
+            "awaitable is a name, not await able"
+        );
+        assert!(matches!(parse_expression_ast("awaitable")?, Expr::Ident(_)));
+        Ok(())
+    }
+}
+
+mod for_tests {
+    use super::*;
+    use carbon_parser::ast::{CForStmt, Expr, Item, Stmt};
+    use carbon_parser::format::format_carbon;
+    use carbon_parser::semantics::check_undeclared;
+
+    #[test]
+    fn test_for_over_identifier() -> Result<()> {
+        let result = parse_for_statement("for (var x: i32 in items) { print(x); }");
+        assert!(
+            result.is_ok(),
+            "A for loop over a variable should parse successfully"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_over_call_result() -> Result<()> {
+        let code = "fn main() { for (var i: i64 in range(0, 10)) { print(i); } }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::For(for_stmt) = &function.body.stmts[0] else {
+            panic!("expected a for loop");
+        };
+        assert_eq!(for_stmt.name.name, "i");
+        assert_eq!(for_stmt.ty.name, "i64");
+        assert!(matches!(&for_stmt.iterable, Expr::Call(call) if call.callee.name == "range"));
+        assert_eq!(for_stmt.body.stmts.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_without_in_is_error() {
+        assert!(parse_for_statement("for (var x: i32) { }").is_err());
+        assert!(parse_for_statement("for (var x: i32 items) { }").is_err());
+    }
+
+    #[test]
+    fn test_for_loop_variable_in_body() -> Result<()> {
+        let code = r#"
+            fn sum(items: List) -> i32 {
+                for (var x: i32 in items) {
+                    if (var y: i32 = lookup(x)) { use(y); } else { use(x); }
+                }
+                return 0;
+            }
+        "#;
+        assert!(
+            check_program(code)?.is_empty(),
+            "The loop variable is visible in the whole body"
+        );
+        Ok(())
+    }
+
+    fn c_for(code: &str) -> Result<CForStmt> {
+        let mut program = parse_to_ast(&format!("fn f() {{ {code} }}"))?;
+        let Some(Item::Function(function)) = program.items.pop() else {
+            panic!("expected a function");
+        };
+        let Some(Stmt::CFor(for_stmt)) = function.body.stmts.into_iter().next() else {
+            panic!("expected a C-style for loop: {code}");
+        };
+        Ok(for_stmt)
+    }
+
+    #[test]
+    fn test_c_style_for_with_all_clauses() -> Result<()> {
+        let for_stmt = c_for("for (var i: i32 = 0; i < n; i = i + 1) { print(i); }")?;
+        assert!(matches!(for_stmt.init.as_deref(), Some(Stmt::Var(decl)) if decl.name.name == "i"));
+        assert!(matches!(&for_stmt.condition, Some(Expr::Binary(_))));
+        let Some(Stmt::Assign(update)) = for_stmt.update.as_deref() else {
+            panic!("expected an assignment update");
+        };
+        assert_eq!(update.target.name, "i");
+        assert_eq!(for_stmt.body.stmts.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_c_style_for_without_condition() -> Result<()> {
+        let for_stmt = c_for("for (var i: i32 = 0;; i += 1) { }")?;
+        assert!(for_stmt.init.is_some());
+        assert!(for_stmt.condition.is_none(), "an infinite loop");
+        assert!(for_stmt.update.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_c_style_for_with_empty_clauses() -> Result<()> {
+        let for_stmt = c_for("for (;;) { step(); }")?;
+        assert!(for_stmt.init.is_none());
+        assert!(for_stmt.condition.is_none());
+        assert!(for_stmt.update.is_none());
+
+        let for_stmt = c_for("for (i = 0; i < n; next(i)) { }")?;
+        assert!(matches!(for_stmt.init.as_deref(), Some(Stmt::Assign(_))));
+        assert!(matches!(for_stmt.update.as_deref(), Some(Stmt::Expr(_))));
+        assert!(parse_statement("for (;) { }").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_c_style_for_variable_is_scoped_to_loop() -> Result<()> {
+        let code = r#"
+            fn count(n: i32) {
+                for (var i: i32 = 0; i < n; i += 1) { print(i); }
+                print(i);
+            }
+        "#;
+        let diagnostics = check_undeclared(&parse_to_ast(code)?);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].span.start, code.rfind("i)").unwrap());
+
+        let formatted = format_carbon(code)?;
+        assert!(formatted.contains("for (var i: i32 = 0; i < n; i += 1) {"));
+        assert_eq!(
+            format_carbon("fn f() { for (;;) { } }")?,
+            "fn f() {\n  for (;;) {}\n}\n"
+        );
+        Ok(())
+    }
+}
+
+mod describe_tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_function() -> Result<()> {
+        let code = r#"
+            fn clamp(x: i32, lo: i32, hi: i32) -> i32 {
+                var low: i32 = lo;
+                var high: i32 = hi;
+                if (x < low) { return low; }
+                log(x);
+                return x;
+            }
+        "#;
+        let description = describe_program(&parse_to_ast(code)?);
+        assert_eq!(
+            description,
+            "Function clamp takes x: i32, lo: i32, hi: i32, returns i32; declares 2 locals; \
+             branches on a condition; evaluates an expression; returns an expression."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_one_line_per_declaration() -> Result<()> {
+        let code = r#"
+            var limit: i32 = 10;
+            async fn tick() throws { }
+        "#;
+        let description = describe_program(&parse_to_ast(code)?);
+        let lines: Vec<&str> = description.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "Variable limit of type i32, initialized.");
+        assert_eq!(
+            lines[1],
+            "Async function tick takes no parameters, may throw; has an empty body."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_class() -> Result<()> {
+        let code = "class Stack(T:! type) { var size: i32; fn Push(value: T) { } }";
+        assert_eq!(
+            describe_program(&parse_to_ast(code)?),
+            "Class Stack is generic over T:! type, has 1 field and 1 method."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_empty_program() -> Result<()> {
+        assert_eq!(describe_program(&parse_to_ast("")?), "");
+        Ok(())
+    }
+}
+
+mod class_tests {
+    use super::*;
+    use carbon_parser::ast::{ClassMember, Item};
+
+    #[test]
+    fn test_empty_class() -> Result<()> {
+        let result = parse_class_decl("class Empty { }");
+        assert!(result.is_ok(), "An empty class should parse successfully");
+        Ok(())
+    }
+
+    #[test]
+    fn test_class_with_two_fields() -> Result<()> {
+        let code = "class Point { var x: i32; var y: i32; }";
+        assert!(parse_class_decl(code).is_ok());
+        let program = parse_to_ast(code)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        assert_eq!(class.name.name, "Point");
+        assert!(class.generic_params.is_empty());
+        assert_eq!(class.members.len(), 2);
+        assert!(class
+            .members
+            .iter()
+            .all(|member| matches!(member, ClassMember::Field(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_class_with_method() -> Result<()> {
+        let code = r#"
+            class Counter {
+                fn Next() -> i32 { return 1; }
+            }
+        "#;
+        let program = parse_to_ast(code)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        let ClassMember::Method(method) = &class.members[0] else {
+            panic!("expected a method");
+        };
+        assert_eq!(method.name.name, "Next");
+        Ok(())
+    }
+
+    #[test]
+    fn test_generic_class() -> Result<()> {
+        let code = "class Pair(T:! type, U:! type) { var first: T; var second: U; }";
+        let program = parse_to_ast(code)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        let names: Vec<&str> = class
+            .generic_params
+            .iter()
+            .map(|param| param.name.name.as_str())
+            .collect();
+        assert_eq!(names, ["T", "U"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_class_at_top_level() -> Result<()> {
+        let code = r#"
+            class Point { var x: i32; var y: i32; }
+            fn origin() -> Point { return make(0, 0); }
+        "#;
+        assert!(
+            parse_carbon(code).is_ok(),
+            "A class next to a function should parse successfully"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_methods_are_checked() -> Result<()> {
+        let code = "class File { fn Open() -> i32 { throw IoError(1); } }";
+        assert_eq!(check_program(code)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_class_statement_in_body_is_error() {
+        assert!(parse_class_decl("class Bad { return 1; }").is_err());
+        assert!(parse_class_decl("class Bad(T: type) { }").is_err());
+    }
+
+    #[test]
+    fn test_parse_field_member() -> Result<()> {
+        let member = parse_class_member("  var value: T = 0; // default ")?;
+        let ClassMember::Field(field) = member else {
+            panic!("expected a field");
+        };
+        assert_eq!(field.name.name, "value");
+        assert_eq!(field.ty.name, "T");
+        assert!(field.init.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_method_member() -> Result<()> {
+        let member = parse_class_member("async fn Fetch(self: Self) throws -> i32 { return 1; }")?;
+        let ClassMember::Method(method) = member else {
+            panic!("expected a method");
+        };
+        assert_eq!(method.name.name, "Fetch");
+        assert!(method.is_async);
+        assert!(method.throws);
+
+        let member = parse_class_member("fn (self: Self) [i: i32] -> i32 { return i; }")?;
+        assert!(matches!(member, ClassMember::IndexOperator(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_non_member_is_error() {
+        assert!(parse_class_member("value + 1").is_err());
+        assert!(parse_class_member("return 1;").is_err());
+        assert!(parse_class_member("class Inner { }").is_err());
+        assert!(
+            parse_class_member("var a: i32; var b: i32;").is_err(),
+            "Exactly one member is accepted"
+        );
+    }
+}
+
+mod type_qualifier_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, TypeName};
+
+    fn var_type(code: &str) -> TypeName {
+        let program = parse_to_ast(code).unwrap();
+        match &program.items[0] {
+            Item::Var(decl) => decl.ty.clone(),
+            other => panic!("expected a variable, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_const_variable_type() -> Result<()> {
+        assert!(
+            parse_var_decl("var limit: const i32 = 10;").is_ok(),
+            "A const-qualified type should parse successfully"
+        );
+        let ty = var_type("var limit: const i32 = 10;");
+        assert_eq!(ty.name, "i32");
+        assert!(ty.qualifiers.is_const);
+        assert!(!ty.qualifiers.is_mut);
+        assert!(ty.pointers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_qualified_pointer() -> Result<()> {
+        let ty = var_type("var p: i32*const = addr(x);");
+        assert_eq!(ty.name, "i32");
+        assert!(!ty.qualifiers.is_const);
+        assert_eq!(ty.pointers.len(), 1);
+        assert!(ty.pointers[0].is_const);
+
+        let ty = var_type("var pp: mut i32 * * const;");
+        assert!(ty.qualifiers.is_mut);
+        assert_eq!(ty.pointers.len(), 2);
+        assert!(!ty.pointers[0].is_const);
+        assert!(ty.pointers[1].is_const);
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_qualifier_position() -> Result<()> {
+        assert_eq!(
+            var_type("var a: mut  const Point;").canonical(),
+            "const mut Point"
+        );
+        assert_eq!(var_type("var b: const Point;").canonical(), "const Point");
+        assert_eq!(
+            var_type("var c: i32 * mut const;").canonical(),
+            "i32*const mut"
+        );
+        assert_eq!(var_type("var d: i32;").canonical(), "i32");
+        Ok(())
+    }
+
+    #[test]
+    fn test_qualifier_prefix_is_not_split_from_name() -> Result<()> {
+        let ty = var_type("var c: constant;");
+        assert_eq!(ty.name, "constant");
+        assert!(!ty.qualifiers.is_const);
+
+        let ty = var_type("var m: mutex;");
+        assert_eq!(ty.name, "mutex");
+        Ok(())
+    }
+
+    #[test]
+    fn test_qualifier_without_type_is_error() {
+        assert!(parse_type_name("const").is_err());
+    }
+}
+
+mod assignment_tests {
+    use super::*;
+    use carbon_parser::ast::{AssignOp, AssignStmt, Expr, Item, Stmt};
+
+    fn assignments(code: &str) -> Vec<AssignStmt> {
+        let program = parse_to_ast(code).unwrap();
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        function
+            .body
+            .stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Assign(assign) => Some(assign.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_assignment() -> Result<()> {
+        let result = parse_assignment("x = 42;");
+        assert!(
+            result.is_ok(),
+            "A simple assignment should parse successfully"
+        );
+        let assigns = assignments("fn f() { var x: i32 = 0; x = x + 1; }");
+        assert_eq!(assigns.len(), 1);
+        assert_eq!(assigns[0].target.name, "x");
+        assert_eq!(assigns[0].op, AssignOp::Assign);
+        assert!(matches!(assigns[0].value, Expr::Binary(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compound_assignment() -> Result<()> {
+        let assigns = assignments("fn f() { x += 1; x -= 2; x *= 3; x /= 4; }");
+        let ops: Vec<AssignOp> = assigns.iter().map(|assign| assign.op).collect();
+        assert_eq!(
+            ops,
+            [AssignOp::Add, AssignOp::Sub, AssignOp::Mul, AssignOp::Div]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assignment_from_call() -> Result<()> {
+        let assigns = assignments("fn f() { result = compute(1, 2); }");
+        assert!(matches!(
+            &assigns[0].value,
+            Expr::Call(call) if call.callee.name == "compute"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_comparison_is_not_assignment() -> Result<()> {
+        let code = "fn f() { x == y; }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert!(matches!(function.body.stmts[0], Stmt::Expr(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_assignments() {
+        assert!(parse_assignment("x = 1").is_err());
+        assert!(parse_assignment("x = ;").is_err());
+        assert!(parse_assignment("1 = x;").is_err());
+    }
+}
+
+mod choice_tests {
+    use super::*;
+    use carbon_parser::ast::{ChoiceDecl, Item};
+
+    fn choice(code: &str) -> ChoiceDecl {
+        let program = parse_to_ast(code).unwrap();
+        match &program.items[0] {
+            Item::Choice(choice) => choice.clone(),
+            other => panic!("expected a choice, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_explicit_discriminants() -> Result<()> {
+        let status = choice("choice Status { Ok = 0, Error = 1, Fatal = 2 * 50 }");
+        assert_eq!(status.name.name, "Status");
+        assert_eq!(status.variants.len(), 3);
+        assert_eq!(status.discriminants(), [Some(0), Some(1), Some(100)]);
+        assert!(check_program("choice Status { Ok = 0, Error = 1 }")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_incremented_discriminants() -> Result<()> {
+        let color = choice("choice Color { Red, Green, Blue }");
+        assert_eq!(color.discriminants(), [Some(0), Some(1), Some(2)]);
+
+        let code = "choice Code { A, B = 10, C, D = -1, E }";
+        assert_eq!(
+            choice(code).discriminants(),
+            [Some(0), Some(10), Some(11), Some(-1), Some(0)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_discriminant_is_flagged() -> Result<()> {
+        let code = "choice Status { Ok = 0, Warning, Retry = 1 }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("`Retry`"));
+        assert!(errors[0].message.contains("`Warning`"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "Retry = 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_constant_discriminant_is_flagged() -> Result<()> {
+        let errors = check_program("choice Flags { A = base(), B }")?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("compile-time constant"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_choice_and_invalid_syntax() {
+        assert!(parse_carbon("choice Never { }").is_ok());
+        assert!(parse_carbon("choice Bad { A = }").is_err());
+        assert!(parse_carbon("choice Bad { A B }").is_err());
+    }
+}
+
+mod transcript_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Stmt};
+
+    const SESSION: &str = "\
+Carbon REPL 0.1
+>>> var total: i32 = 0;
+>>> total += add(1, 2);
+>>> total
+3
+>>> if (total > 2) { print(total); }
+3
+>>>
+>>> return total
+";
+
+    #[test]
+    fn test_prompted_lines_are_parsed() -> Result<()> {
+        let results = parse_transcript(SESSION, ">>> ");
+        assert_eq!(
+            results.len(),
+            5,
+            "Output and empty prompt lines are skipped"
+        );
+        assert!(matches!(results[0], Ok(Stmt::Var(_))));
+        assert!(matches!(results[1], Ok(Stmt::Assign(_))));
+        assert!(matches!(
+            &results[2],
+            Ok(Stmt::Expr(stmt)) if !stmt.has_semicolon && matches!(stmt.expr, Expr::Ident(_))
+        ));
+        assert!(matches!(results[3], Ok(Stmt::If(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_are_reported_per_line() -> Result<()> {
+        let results = parse_transcript(SESSION, ">>> ");
+        assert!(
+            results[4].is_err(),
+            "A return without a semicolon is not a statement"
+        );
+        assert!(results[..4].iter().all(|result| result.is_ok()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_prompt() -> Result<()> {
+        let session = "carbon> var x: i32 = 1;\r\n>>> var y: i32 = 2;\r\n";
+        let results = parse_transcript(session, "carbon> ");
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        Ok(())
+    }
+}
+
+mod expr_tree_tests {
+    use super::*;
+    use carbon_parser::ast::Expr;
+
+    #[test]
+    fn test_multiplication_binds_tighter() -> Result<()> {
+        assert_eq!(
+            shape(&parse_expression_ast("1 + 2 * 3")?),
+            "(1 + (2 * 3))"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("1 * 2 + 3")?),
+            "((1 * 2) + 3)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_left_associativity() -> Result<()> {
+        assert_eq!(
+            shape(&parse_expression_ast("10 - 5 - 2")?),
+            "((10 - 5) - 2)"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("a / b % c")?),
+            "((a / b) % c)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_operators() -> Result<()> {
+        assert!(parse_expression("a && b").is_ok());
+        assert_eq!(shape(&parse_expression_ast("a && b")?), "(a && b)");
+        assert_eq!(
+            shape(&parse_expression_ast("a || b && c")?),
+            "(a || (b && c))",
+            "`&&` binds tighter than `||`"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("a && b || c && d || e")?),
+            "(((a && b) || (c && d)) || e)"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("x > 0 && y < 10")?),
+            "((x > 0) && (y < 10))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional_expression() -> Result<()> {
+        assert!(parse_expression("if x > 0 then 1 else -1").is_ok());
+        assert_eq!(
+            shape(&parse_expression_ast("if x > 0 then 1 else -1")?),
+            "(if (x > 0) then 1 else -1)"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("if a then b else c + 1")?),
+            "(if a then b else (c + 1))",
+            "the else branch extends as far as possible"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("(if a then 1 else 2) * 3")?),
+            "([(if a then 1 else 2)] * 3)"
+        );
+        assert!(
+            parse_expression("1 + if a then 1 else 2").is_err(),
+            "a conditional operand needs parentheses"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_conditional_in_else() -> Result<()> {
+        let code = "if x > 0 then 1 else if x < 0 then -1 else 0";
+        assert_eq!(
+            shape(&parse_expression_ast(code)?),
+            "(if (x > 0) then 1 else (if (x < 0) then -1 else 0))"
+        );
+        let Expr::Conditional(outer) = parse_expression_ast(code)? else {
+            panic!("expected a conditional");
+        };
+        assert_eq!(outer.span, Span::new(0, code.len()));
+        assert_eq!(outer.else_branch.span(), Span::new(21, code.len()));
+
+        let program = format!("fn sign(x: i32) -> i32 {{ return {code}; }}");
+        assert!(check_program(&program)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_less_or_greater_equal() -> Result<()> {
+        assert_eq!(shape(&parse_expression_ast("x <= y")?), "(x <= y)");
+        assert_eq!(shape(&parse_expression_ast("x >= y")?), "(x >= y)");
+        assert_eq!(
+            shape(&parse_expression_ast("a <= b == c")?),
+            "((a <= b) == c)",
+            "comparisons bind tighter than equality"
+        );
+        assert_eq!(
+            shape(&parse_expression_ast("i >= 0 && i <= n")?),
+            "((i >= 0) && (i <= n))"
+        );
+        assert!(parse_expression_ast("x < = y").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parentheses_override_precedence() -> Result<()> {
+        assert_eq!(
+            shape(&parse_expression_ast("(1 + 2) * 3")?),
+            "([(1 + 2)] * 3)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_spans_cover_operands() -> Result<()> {
+        let code = "a + b * c";
+        let Expr::Binary(add) = parse_expression_ast(code)? else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(&code[add.span.start..add.span.end], code);
+        assert_eq!(&code[add.rhs.span().start..add.rhs.span().end], "b * c");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_expression_is_error() {
+        assert!(parse_expression_ast("1 +").is_err());
+    }
+}
+
+mod type_value_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Item, VarDecl};
+
+    fn var_decl(code: &str) -> VarDecl {
+        let program = parse_to_ast(code).unwrap();
+        match &program.items[0] {
+            Item::Var(decl) => decl.clone(),
+            other => panic!("expected a variable, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_type_assigned_to_type_variable() -> Result<()> {
+        assert!(
+            parse_var_decl("var t: Type = i32;").is_ok(),
+            "A type value should parse successfully"
+        );
+        let decl = var_decl("var t: Type = i32;");
+        assert_eq!(decl.ty.name, "Type");
+        assert!(matches!(&decl.init, Some(Expr::TypeValue(ty)) if ty.name == "i32"));
+        assert!(check_program("var t: Type = i32;")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_as_call_argument() -> Result<()> {
+        let decl = var_decl("var u: Type = Vector(i32);");
+        let Some(Expr::Call(call)) = &decl.init else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.callee.name, "Vector");
+        assert!(matches!(&call.args[0], Expr::TypeValue(ty) if ty.name == "i32"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_in_arithmetic_is_flagged() -> Result<()> {
+        let code = "var n: i32 = i32 + 1;";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("`i32`"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "i32");
+
+        let code = "fn f(x: i32) -> i32 { return x * (bool); }";
+        assert_eq!(check_program(code)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_comparison_is_allowed() -> Result<()> {
+        let code = "fn is_int(t: Type) -> bool { return t == i32 || t == i64; }";
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_identifier_starting_with_type_name() -> Result<()> {
+        let decl = var_decl("var x: i32 = i32_max;");
+        assert!(matches!(&decl.init, Some(Expr::Ident(ident)) if ident.name == "i32_max"));
+        assert!(parse_type_name("Type").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_primitive_conversions() -> Result<()> {
+        for (code, target, arg) in [("i64(x)", "i64", "x"), ("f64(n + 1)", "f64", "n + 1")] {
+            let Expr::Convert(convert) = parse_expression_ast(code)? else {
+                panic!("{code} should be a conversion");
+            };
+            assert_eq!(convert.ty.name, target);
+            let span = convert.arg.span();
+            assert_eq!(&code[span.start..span.end], arg);
+            assert_eq!(convert.span, Span::new(0, code.len()));
+        }
+        let code = "var d: f64 = f64(count) / 2.0;";
+        assert_eq!(parse_to_ast(code)?.to_string().trim_end(), code);
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_type_call_is_not_a_conversion() -> Result<()> {
+        let Expr::Call(call) = parse_expression_ast("MyType(x)")? else {
+            panic!("MyType(x) should stay a call");
+        };
+        assert_eq!(call.callee.name, "MyType");
+        assert!(matches!(
+            parse_expression_ast("i64_from(x)")?,
+            Expr::Call(_)
+        ));
+        assert!(
+            matches!(parse_expression_ast("i64(a, b)")?, Expr::Call(_)),
+            "a conversion takes exactly one argument"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conversion_is_an_operand() -> Result<()> {
+        let Expr::Try(try_expr) = parse_expression_ast("i64(x)?")? else {
+            panic!("i64(x)? should apply ? to the conversion");
+        };
+        assert!(matches!(*try_expr.operand, Expr::Convert(_)));
+        assert!(matches!(
+            parse_expression_ast("f64(n).abs()")?,
+            Expr::MethodCall(_)
+        ));
+        let Expr::Call(call) = parse_expression_ast("trueCount(x)")? else {
+            panic!("a name starting with true should stay a call");
+        };
+        assert_eq!(call.callee.name, "trueCount");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_tests {
+    use super::*;
+    use carbon_parser::ast::Program;
+
+    #[test]
+    fn test_ast_json_round_trip() -> Result<()> {
+        let code = r#"
+            choice Status { Ok = 0, Error }
+            class Point(T:! type) { var x: const T*; fn Norm() -> T { return x; } }
+            async fn main(args: ...String) throws -> i32 {
+                for (var arg: String in args) {
+                    match (parse(arg)) {
+                        case Some(n: i32) if n > 0 => { total += await f(n)?; }
+                        default => { throw Error(1); }
+                    }
+                }
+                if (var t: Type = i32) { } else if (done) { } else { }
+                while (false) { }
+                0
+            }
+        "#;
+        let program = parse_to_ast(code)?;
+        let json = serde_json::to_string(&program)?;
+        let restored: Program = serde_json::from_str(&json)?;
+        assert_eq!(restored, program);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_serializes_as_start_end() -> Result<()> {
+        let value = serde_json::to_value(Span::new(3, 7))?;
+        assert_eq!(value, serde_json::json!({ "start": 3, "end": 7 }));
+        Ok(())
+    }
+}
+
+#[cfg(feature = "wasm")]
+mod wasm_tests {
+    use super::*;
+    use carbon_parser::wasm::parse_result_json;
+
+    #[test]
+    fn test_known_program() -> Result<()> {
+        let result = parse_result_json("fn main() -> i32 { return 0; }");
+        assert_eq!(result["ok"], true);
+        let tree = &result["tree"];
+        assert_eq!(tree["rule"], "program");
+        assert_eq!(tree["children"][0]["rule"], "function_decl");
+        assert_eq!(tree["children"][0]["span"]["end"], 30);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_object() -> Result<()> {
+        let result = parse_result_json("fn main( {");
+        assert_eq!(result["ok"], false);
+        let error = &result["error"];
+        assert_eq!(
+            error["code"],
+            parse_carbon("fn main( {").unwrap_err().code()
+        );
+        assert_eq!((&error["line"], &error["column"]), (&1.into(), &10.into()));
+        assert!(error["message"]
+            .as_str()
+            .is_some_and(|message| !message.is_empty()));
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn test_js_value() {
+        let value = carbon_parser::wasm::parse_carbon_wasm("fn main() {}");
+        let ok = js_sys::Reflect::get(&value, &"ok".into()).unwrap();
+        assert_eq!(ok.as_bool(), Some(true));
+    }
+}
+
+mod parse_options_tests {
+    use super::*;
+
+    fn limits(identifier: Option<usize>, literal: Option<usize>) -> ParseOptions {
+        ParseOptions {
+            max_identifier_len: identifier,
+            max_literal_len: literal,
+            ..ParseOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_long_identifier_rejected_with_limit() {
+        let name = "x".repeat(300);
+        let code = format!("fn main() {{ var {name}: i32 = 1; }}");
+        let err = parse_to_ast_with_options(&code, &limits(Some(255), None)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Syntax error: identifier is 300 bytes long, the limit is 255 at 16..316"
+        );
+        assert_eq!(err.with_source(&code).location(), Some((1, 17)));
+    }
+
+    #[test]
+    fn test_long_identifier_accepted_without_limit() -> Result<()> {
+        let name = "x".repeat(300);
+        let code = format!("fn main() {{ var {name}: i32 = 1; }}");
+        let program = parse_to_ast_with_options(&code, &ParseOptions::default())?;
+        assert_eq!(program.items.len(), 1);
+        assert!(parse_to_ast_with_options(&code, &limits(Some(300), None)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_literal_rejected_with_limit() {
+        let code = format!("var s: String = \"{}\";", "a".repeat(100));
+        assert!(parse_to_ast_with_options(&code, &limits(None, Some(64))).is_err());
+        assert!(parse_to_ast_with_options(&code, &limits(Some(64), None)).is_ok());
+    }
+
+    #[test]
+    fn test_syntax_errors_still_reported() {
+        let err = parse_to_ast_with_options("fn main( { }", &ParseOptions::default()).unwrap_err();
+        assert!(matches!(err, ParseError::PestError(_)));
+    }
+}
+
+mod span_resolver_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+
+    #[test]
+    fn test_node_on_third_line() -> Result<()> {
+        let code = "// Привіт, світ\r\nfn main() -> i32 {\r\n    var e: String = \"ü\"; return 0;\r\n}\r\n";
+        let program = parse_to_ast(code)?;
+        let Item::Function(main) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let resolver = SpanResolver::new(code);
+
+        let (start, end) = resolver.resolve(main.body.stmts[0].span());
+        assert_eq!(start, LineColumn { line: 3, column: 5 });
+        assert_eq!(
+            end,
+            LineColumn {
+                line: 3,
+                column: 25
+            }
+        );
+
+        let Stmt::Return(ret) = &main.body.stmts[1] else {
+            panic!("expected a return");
+        };
+        assert_eq!(
+            resolver.line_column(ret.span.start),
+            LineColumn {
+                line: 3,
+                column: 26
+            }
+        );
+
+        // The function starts on line 2, after a comment with Cyrillic text.
+        assert_eq!(
+            resolver.line_column(main.span.start),
+            LineColumn { line: 2, column: 1 }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_endings_and_bounds() {
+        let code = "a\r\nb\nc";
+        let resolver = SpanResolver::new(code);
+        assert_eq!(resolver.line_column(1), LineColumn { line: 1, column: 2 });
+        assert_eq!(resolver.line_column(3), LineColumn { line: 2, column: 1 });
+        assert_eq!(resolver.line_column(5), LineColumn { line: 3, column: 1 });
+        assert_eq!(resolver.line_column(100), LineColumn { line: 3, column: 2 });
+        assert_eq!(resolver.line_column(0).to_string(), "1:1");
+    }
+
+    #[test]
+    fn test_offset_inside_multibyte_character() {
+        let resolver = SpanResolver::new("xé");
+        assert_eq!(resolver.line_column(2), LineColumn { line: 1, column: 2 });
+        assert_eq!(resolver.line_column(3), LineColumn { line: 1, column: 3 });
+    }
+}
+
+mod class_modifier_tests {
+    use super::*;
+    use carbon_parser::ast::{ClassModifier, Item};
+
+    #[test]
+    fn test_final_class() -> Result<()> {
+        let code = "final class Circle { var radius: f64; }";
+        assert!(
+            parse_class_decl(code).is_ok(),
+            "A final class should parse successfully"
+        );
+        let program = parse_to_ast(code)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        assert_eq!(class.modifier, Some(ClassModifier::Final));
+        assert!(class.extends.is_none());
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extending_non_final_base_is_clean() -> Result<()> {
+        let code = r#"
+            sealed class Shape { }
+            class Square extends Shape { var side: f64; }
+        "#;
+        let program = parse_to_ast(code)?;
+        let Item::Class(square) = &program.items[1] else {
+            panic!("expected a class");
+        };
+        assert_eq!(square.modifier, None);
+        assert_eq!(
+            square.extends.as_ref().map(|base| base.name.as_str()),
+            Some("Shape")
+        );
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extending_final_base_is_flagged() -> Result<()> {
+        let code = r#"
+            class Ring extends Circle { }
+            final class Circle { }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(
+            errors.len(),
+            1,
+            "The base may be declared after its subclass"
+        );
+        assert!(errors[0].message.contains("`Ring`"));
+        assert!(errors[0].message.contains("`Circle`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_modifier_must_be_a_whole_word() {
+        assert!(parse_carbon("finally class Circle { }").is_err());
+        assert!(parse_carbon("final sealed class Circle { }").is_err());
+    }
+}
+
+mod tuple_index_tests {
+    use super::*;
+    use carbon_parser::ast::Expr;
+
+    #[test]
+    fn test_tuple_index() -> Result<()> {
+        for (code, expected) in [("pair.0", 0), ("pair.1", 1)] {
+            assert!(parse_expression(code).is_ok(), "{code} should parse");
+            let Expr::TupleIndex(access) = parse_expression_ast(code)? else {
+                panic!("{code} should be a tuple index");
+            };
+            assert_eq!(access.index, expected);
+            assert!(matches!(*access.base, Expr::Ident(ref ident) if ident.name == "pair"));
+            assert_eq!(access.span.end, code.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_tuple_index() -> Result<()> {
+        let Expr::TupleIndex(outer) = parse_expression_ast("nested.0.1")? else {
+            panic!("expected a tuple index");
+        };
+        assert_eq!(outer.index, 1);
+        let Expr::TupleIndex(inner) = *outer.base else {
+            panic!("nested.0.1 should index the result of nested.0");
+        };
+        assert_eq!(inner.index, 0);
+        assert_eq!((inner.span.start, inner.span.end), (0, "nested.0".len()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_named_member_access() -> Result<()> {
+        let Expr::Member(access) = parse_expression_ast("obj.field")? else {
+            panic!("obj.field should be a member access");
+        };
+        assert_eq!(access.member.name, "field");
+        assert!(matches!(*access.base, Expr::Ident(ref ident) if ident.name == "obj"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_access_in_expressions() -> Result<()> {
+        assert!(parse_expression("pair.0 + pair.1 * 2").is_ok());
+        assert!(parse_expression("make().0").is_ok());
+        assert!(parse_expression("(a).field.1").is_ok());
+        assert!(
+            parse_expression("pair.01").is_err(),
+            "Indices have no leading zeros"
+        );
+        let code = r#"
+            fn f() -> i32 {
+                if (var p: Pair = get()) { return p.0; } else { return p.1; }
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(
+            errors.len(),
+            1,
+            "The base of a field access is checked for scope"
+        );
+        assert!(errors[0].message.contains("`p`"));
+        Ok(())
+    }
+}
+
+mod index_operator_tests {
+    use super::*;
+    use carbon_parser::ast::{ClassMember, Expr, Item, Param};
+
+    const VECTOR: &str = r#"
+        class IntVector {
+            var data: [i32; 16];
+            fn (self: Self) [index: i32] -> i32 {
+                return self.data[index];
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_index_operator_method() -> Result<()> {
+        assert!(
+            parse_class_decl(VECTOR).is_ok(),
+            "A class with an index operator should parse successfully"
+        );
+        let program = parse_to_ast(VECTOR)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        let ClassMember::IndexOperator(operator) = &class.members[1] else {
+            panic!("expected an index operator");
+        };
+        assert!(matches!(&operator.receiver, Param::Regular { name, ty, .. }
+            if name.name == "self" && ty.name == "Self"));
+        assert_eq!(operator.index_params.len(), 1);
+        assert_eq!(
+            operator.return_type.as_ref().map(|ty| ty.name.as_str()),
+            Some("i32")
+        );
+        assert!(check_program(VECTOR)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_expression() -> Result<()> {
+        assert!(parse_expression("container[i]").is_ok());
+        let Expr::Index(index) = parse_expression_ast("container[i + 1]")? else {
+            panic!("expected an index expression");
+        };
+        assert!(matches!(*index.base, Expr::Ident(ref ident) if ident.name == "container"));
+        assert!(matches!(*index.index, Expr::Binary(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_operator_wrong_parameter_count() -> Result<()> {
+        for params in ["", "row: i32, column: i32"] {
+            let code =
+                format!("class Grid {{ fn (self: Self) [{params}] -> i32 {{ return 0; }} }}");
+            let errors = check_program(&code)?;
+            assert_eq!(errors.len(), 1, "`[{params}]` should be flagged");
+            assert!(errors[0].message.contains("exactly one index parameter"));
+        }
+        Ok(())
+    }
+}
+
+mod token_tests {
+    use super::*;
+
+    fn texts(code: &str, category: RuleCategory) -> Result<Vec<&str>> {
+        Ok(spans_of_kind(code, category)?
+            .into_iter()
+            .map(|span| &code[span.start..span.end])
+            .collect())
+    }
+
+    #[test]
+    fn test_keyword_and_string_spans() -> Result<()> {
+        let code = r#"
+            fn greet(name: String) -> String {
+                if (name == "") { return "hello, world"; }
+                var done: bool = true;
+                while (!done) { fallthrough_count = 'x'; }
+                return name;
+            }
+        "#;
+        assert_eq!(
+            texts(code, RuleCategory::Keyword)?,
+            ["fn", "if", "return", "var", "true", "while", "return"]
+        );
+        assert_eq!(
+            texts(code, RuleCategory::String)?,
+            ["\"\"", "\"hello, world\"", "'x'"]
+        );
+        let keywords = spans_of_kind(code, RuleCategory::Keyword)?;
+        assert_eq!(keywords[0], Span::new(13, 15));
+        assert!(texts(code, RuleCategory::Identifier)?.contains(&"fallthrough_count"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_token_has_one_category() -> Result<()> {
+        let code = "class C { var n: i32 = 0x1F + 2.5; } import Math; __intrinsic(n);";
+        let total: usize = [
+            RuleCategory::Keyword,
+            RuleCategory::Identifier,
+            RuleCategory::Number,
+            RuleCategory::String,
+            RuleCategory::Punctuation,
+        ]
+        .into_iter()
+        .map(|category| spans_of_kind(code, category).map(|spans| spans.len()))
+        .sum::<carbon_parser::ParseResult<usize>>()?;
+        assert_eq!(total, count_tokens(code)?);
+        assert_eq!(
+            texts(code, RuleCategory::Keyword)?,
+            ["class", "var", "import"]
+        );
+        assert_eq!(texts(code, RuleCategory::Number)?, ["0x1F", "2.5"]);
+        assert!(spans_of_kind("var s = \"open", RuleCategory::String).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_tokens_var_decl() -> Result<()> {
+        let code = "var x: i32 = 42;";
+        assert_eq!(count_tokens(code)?, 7, "var, x, :, i32, =, 42 and ;");
+        assert_eq!(count_tokens(code)?, tokenize(code)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trivia_is_skipped() -> Result<()> {
+        let code = "/* header */ fn main() -> i32 {\n    // comment\n    return 0;\n}\n";
+        let texts: Vec<&str> = tokenize(code)?.iter().map(|token| token.text).collect();
+        assert_eq!(
+            texts,
+            ["fn", "main", "(", ")", "->", "i32", "{", "return", "0", ";", "}"]
+        );
+        assert_eq!(count_tokens(code)?, texts.len());
+        assert_eq!(count_tokens("  // nothing here\n")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_kinds_and_spans() -> Result<()> {
+        let code = r#"x-1 += 2.5 "s""#;
+        let tokens = tokenize(code)?;
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Identifier,
+                TokenKind::Punctuation,
+                TokenKind::Integer,
+                TokenKind::Punctuation,
+                TokenKind::Float,
+                TokenKind::String,
+            ]
+        );
+        for token in &tokens {
+            assert_eq!(&code[token.span.start..token.span.end], token.text);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokenize_does_not_check_syntax() -> Result<()> {
+        assert_eq!(count_tokens("var var ; ; )")?, 5);
+        assert!(count_tokens("var x = @;").is_err(), "`@` is not a token");
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_escapes() -> Result<()> {
+        let code = r#"var s: String = "a\"b; \\ \n\t\u{e9}";"#;
+        let strings: Vec<&str> = tokenize(code)?
+            .iter()
+            .filter(|token| token.kind == TokenKind::String)
+            .map(|token| token.text)
+            .collect();
+        assert_eq!(
+            strings,
+            [r#""a\"b; \\ \n\t\u{e9}""#],
+            "the escaped quote does not end it"
+        );
+        assert_eq!(
+            unescape_string(strings[0]).as_deref(),
+            Some("a\"b; \\ \n\t\u{e9}")
+        );
+        assert!(parse_to_ast(code).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_char_tokens() -> Result<()> {
+        let tokens = tokenize(r"x = '\'' + '}';")?;
+        let chars: Vec<&str> = tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Char)
+            .map(|token| token.text)
+            .collect();
+        assert_eq!(chars, [r"'\''", "'}'"]);
+        assert_eq!(unescape_string(r#""it\'s""#).as_deref(), Some("it's"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_string_escapes() {
+        for code in [r#""\q""#, r#""\u{}""#, r#""\u{1234567}""#, r#""end\""#] {
+            assert!(parse_expression(code).is_err(), "{code} should be rejected");
+        }
+        assert_eq!(
+            unescape_string(r#""\u{D800}""#),
+            None,
+            "a surrogate is not a char"
+        );
+        assert_eq!(unescape_string("no quotes"), None);
+    }
+
+    #[test]
+    fn test_builtin_identifiers() -> Result<()> {
+        let code = "var sum: i32 = __builtin_add(_a, b__c);";
+        let builtins: Vec<&str> = tokenize(code)?
+            .iter()
+            .filter(|token| token.kind == TokenKind::Builtin)
+            .map(|token| token.text)
+            .collect();
+        assert_eq!(builtins, ["__builtin_add"]);
+        assert!(parse_to_ast(code).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_declaring_builtin_name_warns() -> Result<()> {
+        let errors = check_program("fn __builtin_add(a: i32, b: i32) -> i32 { return a; }")?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, Severity::Warning);
+        assert!(errors[0].message.contains("`__builtin_add`"));
+
+        let code = "fn add(a: i32) -> i32 { return __builtin_add(a, a); }";
+        assert!(check_program(code)?.is_empty(), "calling a builtin is fine");
+        Ok(())
+    }
+}
+
+mod format_tests {
+    use super::*;
+    use carbon_parser::format::{format_carbon, format_carbon_to, format_range, FormatOptions};
+
+    const EVERYTHING: &str = r#"
+        var limit: const i32 = 10;
+        var p: const i32*mut = addr(limit);
+        var q: i32*const mut* = addr(p);
+        choice Status { Ok = 0, Retry, Error = 10 }
+        final class Ring[T:! type](N:! i32) extends Shape where T impls Copyable {
+            var items: [T*; 4];
+            var count: i32 = 0;
+            fn Size() -> i32 { count }
+            fn (self: Self) [index: i32] -> T { return self.items[index]; }
+        }
+        async fn load(path: String, N:! i32 = 4, rest: ...i32) throws -> Vector(i32) {
+            var data: Vector(i32) = await read(path)?;
+            if (var first: i32 = head(data)) { total += first; } else if (empty) { } else { throw 1; }
+            while (done == false) { step(); }
+            for (var x: i32 in data) { sum = sum + (x * 2); }
+            match (status.0) {
+                case Some(n: i32) if n > 0 => { return data; }
+                case _ => { }
+                default => { return; }
+            }
+            var t: Type = i32;
+            data
+        }
+    "#;
+
+    #[test]
+    fn test_format_is_idempotent() -> Result<()> {
+        let once = format_carbon(EVERYTHING)?;
+        let twice = format_carbon(&once)?;
+        assert_eq!(
+            once, twice,
+            "Formatting formatted code should not change it"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatted_code_is_equivalent() -> Result<()> {
+        let formatted = format_carbon(EVERYTHING)?;
+        assert!(parse_carbon(&formatted).is_ok());
+        assert_eq!(
+            describe_program(&parse_to_ast(&formatted)?),
+            describe_program(&parse_to_ast(EVERYTHING)?)
+        );
+        assert!(
+            formatted.contains("sum = sum + (x * 2);"),
+            "Parentheses are kept"
+        );
+        assert!(formatted.contains("var items: [T*; 4];"));
+        assert!(
+            formatted.contains("var p: const i32* mut = addr(limit);"),
+            "Pointer qualifiers are spaced off the `*`:\n{formatted}"
+        );
+        assert!(formatted.contains("var q: i32* const mut* = addr(p);"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_messy_input_is_reindented() -> Result<()> {
+        let messy = "fn  main ( )->i32{\n\tvar x:i32=1+2*3;\n        if(x>3){return x;}else{x-=1;}\nreturn 0;}\n\n\n\nvar g:bool=true;";
+        let expected = "\
+fn main() -> i32 {
+  var x: i32 = 1 + 2 * 3;
+  if (x > 3) {
+    return x;
+  } else {
+    x -= 1;
+  }
+  return 0;
+}
+
+var g: bool = true;
+";
+        assert_eq!(format_carbon(messy)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_bodies_and_program() -> Result<()> {
+        assert_eq!(format_carbon("")?, "");
+        assert_eq!(
+            format_carbon("fn f(){}class C{}choice E{}")?,
+            "fn f() {}\n\nclass C {}\n\nchoice E {}\n"
+        );
+        assert!(format_carbon("fn f( {").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_output_matches_string() -> Result<()> {
+        let mut buffer = Vec::new();
+        format_carbon_to(EVERYTHING, &mut buffer, &FormatOptions::default())?;
+        assert_eq!(String::from_utf8(buffer)?, format_carbon(EVERYTHING)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_output_uses_indent_width() -> Result<()> {
+        let mut buffer = Vec::new();
+        let options = FormatOptions { indent_width: 4 };
+        format_carbon_to("fn f() { while (x) { step(); } }", &mut buffer, &options)?;
+        assert_eq!(
+            String::from_utf8(buffer)?,
+            "fn f() {\n    while (x) {\n        step();\n    }\n}\n"
+        );
+        assert!(format_carbon_to("fn f(", &mut Vec::new(), &options).is_err());
+        Ok(())
+    }
+
+    const COMMENTED: &str = "\
+// Geometry helpers.
+package Geometry api; // the whole library
+import Math;
+
+/* Adds two numbers.
+   Overflow wraps. */
+fn add(x: i32, y: i32) -> i32 {
+  // Nothing to check.
+  return x + y; // done
+}
+
+class Point {
+  // Coordinates.
+  var x: i32;
+  var y: i32;
+  // Nothing else yet.
+}
+
+choice Color {
+  Red, // warm
+  Blue
+}
+
+fn todo() {
+  // Not written yet.
+}
+// End of file.
+";
+
+    #[test]
+    fn test_comments_round_trip() -> Result<()> {
+        assert_eq!(format_carbon(COMMENTED)?, COMMENTED);
+        let mut buffer = Vec::new();
+        format_carbon_to(COMMENTED, &mut buffer, &FormatOptions::default())?;
+        assert_eq!(String::from_utf8(buffer)?, COMMENTED);
+        Ok(())
+    }
+
+    #[test]
+    fn test_comments_move_with_their_code() -> Result<()> {
+        let code = "fn f(){ // entry\n  var s: String = \"// text\";\ng(1, /* one */ 2);}";
+        let expected = "\
+fn f() { // entry
+  var s: String = \"// text\";
+  g(1, 2); /* one */
+}
+";
+        let formatted = format_carbon(code)?;
+        assert_eq!(formatted, expected);
+        assert_eq!(format_carbon(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pragmas_keep_their_place_in_the_header() -> Result<()> {
+        let code = "package P api;\n//! pragma optimize(2)\nimport Math;\nfn f() {}\n";
+        assert_eq!(format_carbon(code)?, code.replace("\nfn", "\n\nfn"));
+        Ok(())
+    }
+
+    const MESSY: &str = "var  a:i32=1;   // first\n\nfn  f(x:i32)->i32{\nreturn x*2;}\n\n/* keep */ fn g( ){h( );}\n";
+
+    #[test]
+    fn test_format_range_touches_only_the_selected_function() -> Result<()> {
+        let start = MESSY.find("fn  f").expect("f");
+        let end = MESSY.find("/* keep */").expect("g");
+        let formatted = format_range(MESSY, Span::new(start, end), &FormatOptions::default())?;
+        assert_eq!(
+            formatted,
+            "var  a:i32=1;   // first\n\nfn f(x: i32) -> i32 {\n  return x * 2;\n}\n\n/* keep */ fn g( ){h( );}\n"
+        );
+        assert_eq!(formatted[..start], MESSY[..start]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_range_leaves_partial_declarations_alone() -> Result<()> {
+        let options = FormatOptions::default();
+        // From the middle of `var` to the middle of `f`: neither is whole.
+        let range = Span::new(3, MESSY.find("return").expect("body"));
+        assert_eq!(format_range(MESSY, range, &options)?, MESSY);
+        let everything = Span::new(0, MESSY.len());
+        assert_eq!(
+            format_range(MESSY, everything, &FormatOptions { indent_width: 4 })?,
+            "var a: i32 = 1;   // first\n\nfn f(x: i32) -> i32 {\n    return x * 2;\n}\n\n/* keep */ fn g() {\n    h();\n}\n"
+        );
+        assert!(format_range("fn f(", everything, &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_range_keeps_comments_in_the_selection() -> Result<()> {
+        let src = "var  a:i32=1;\nfn f(){\n// why\nreturn g(1, /* one */ 2);}\n// after\n";
+        let start = src.find("fn f").expect("f");
+        let end = src.find("// after").expect("after");
+        assert_eq!(
+            format_range(src, Span::new(start, end), &FormatOptions::default())?,
+            "var  a:i32=1;\nfn f() {\n  // why\n  return g(1, 2); /* one */\n}\n// after\n"
+        );
+        Ok(())
+    }
+}
+
+mod where_clause_tests {
+    use super::*;
+    use carbon_parser::ast::Item;
+
+    #[test]
+    fn test_generic_class_with_constraint() -> Result<()> {
+        let code = r#"
+            class Set[T:! type] where T impls Hashable {
+                var size: i32 = 0;
+                fn Insert(value: T) -> bool { return true; }
+            }
+        "#;
+        assert!(
+            parse_class_decl(code).is_ok(),
+            "A class with a where clause should parse successfully"
+        );
+        let program = parse_to_ast(code)?;
+        let Item::Class(class) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        assert_eq!(class.deduced_params.len(), 1);
+        assert!(class.generic_params.is_empty());
+        assert_eq!(class.constraints.len(), 1);
+        assert_eq!(class.constraints[0].param.name, "T");
+        assert_eq!(class.constraints[0].bound.name, "Hashable");
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_method_uses_type_parameter() -> Result<()> {
+        let code = r#"
+            class Set[T:! Hashable](N:! i32) {
+                fn Get(index: i32) -> T {
+                    var items: [T; N] = load();
+                    for (var item: T in items) { return item; }
+                    return items[0];
+                }
+            }
+        "#;
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_method_uses_undeclared_type_parameter() -> Result<()> {
+        let code = r#"
+            class Set[T:! Hashable] {
+                fn Map(value: T) -> Vector(U) { var other: T = value; return convert(other); }
+            }
+        "#;
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1, "`U` is not declared by `Set`");
+        assert!(errors[0].message.contains("`U`"));
+        assert!(errors[0].message.contains("`Map`"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "U");
+        Ok(())
+    }
+
+    #[test]
+    fn test_constraint_on_undeclared_parameter() -> Result<()> {
+        let code = "class Set[T:! type] where K impls Hashable { }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("`K`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_level_names_are_not_parameters() -> Result<()> {
+        let code = r#"
+            class A { }
+            class Holder { fn Get() -> A { return make(); } }
+        "#;
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+}
+
+mod type_param_tests {
+    use super::*;
+
+    #[test]
+    fn test_single_constrained_param() -> Result<()> {
+        let params = parse_type_params("[T:! Comparable]")?;
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].name.name, "T");
+        assert_eq!(params[0].ty.name, "Comparable");
+        assert_eq!(params[0].span, Span::new(1, 15));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_params() -> Result<()> {
+        let params = parse_type_params("[ T:! Comparable, U, N:! i32 ]")?;
+        let params: Vec<(&str, String)> = params
+            .iter()
+            .map(|param| (param.name.name.as_str(), param.ty.canonical()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("T", "Comparable".to_string()),
+                ("U", "type".to_string()),
+                ("N", "i32".to_string())
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_lists() {
+        for code in ["[]", "[T:! Comparable", "T:! Comparable]", "[T,]", "[T:!]"] {
+            assert!(
+                parse_type_params(code).is_err(),
+                "{code} should be rejected"
+            );
+        }
+    }
+}
+
+mod recover_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+
+    #[test]
+    fn test_two_broken_statements() -> Result<()> {
+        let code = "fn main() {\n    var x: i32 = ;\n    var y: i32 = 2;\n    y = * 3;\n}";
+        let (program, errors) = parse_carbon_recover(code);
+        assert_eq!(
+            errors.len(),
+            2,
+            "Each broken statement is reported: {errors:?}"
+        );
+        assert_eq!(errors[0].location(), Some((2, 18)));
+        assert_eq!(errors[1].location(), Some((4, 9)));
+
+        let program = program.expect("the rest of the program parses");
+        let Item::Function(main) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(main.body.stmts.len(), 1, "Only `var y` survives");
+        let Stmt::Var(decl) = &main.body.stmts[0] else {
+            panic!("expected a variable declaration");
+        };
+        assert_eq!(&code[decl.span.start..decl.span.end], "var y: i32 = 2;");
+        Ok(())
+    }
+
+    #[test]
+    fn test_valid_input_has_no_errors() -> Result<()> {
+        let (program, errors) = parse_carbon_recover("fn main() { return 0; }");
+        assert!(errors.is_empty());
+        assert_eq!(program.map(|p| p.items.len()), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_broken_header_drops_its_body() -> Result<()> {
+        let (program, errors) = parse_carbon_recover("fn broken { return 1; }\nfn ok() { }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(program.map(|p| p.items.len()), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrepairable_input() -> Result<()> {
+        let (program, errors) = parse_carbon_recover("fn main() {");
+        assert!(
+            program.is_none(),
+            "A missing closing brace cannot be skipped"
+        );
+        assert_eq!(errors.len(), 1);
+        Ok(())
+    }
+}
+
+mod visitor_tests {
+    use super::*;
+    use carbon_parser::ast::{walk_function, walk_program, Expr, FunctionDecl, Visitor};
+
+    #[derive(Default)]
+    struct FunctionCounter {
+        count: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for FunctionCounter {
+        fn visit_function(&mut self, function: &'ast FunctionDecl) {
+            self.count += 1;
+            walk_function(self, function);
+        }
+    }
+
+    #[test]
+    fn test_count_function_declarations() -> Result<()> {
+        let program = parse_to_ast(
+            r#"
+            var limit: i32 = 10;
+            fn first() { }
+            class Counter {
+                var value: i32;
+                fn Increment(self: Self) { }
+            }
+            fn second(x: i32) -> i32 { return x; }
+            "#,
+        )?;
+        let mut counter = FunctionCounter::default();
+        walk_program(&mut counter, &program);
+        assert_eq!(counter.count, 3, "Methods count as function declarations");
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct IdentCollector<'ast> {
+        names: Vec<&'ast str>,
+    }
+
+    impl<'ast> Visitor<'ast> for IdentCollector<'ast> {
+        fn visit_expr(&mut self, expr: &'ast Expr) {
+            if let Expr::Ident(ident) = expr {
+                self.names.push(&ident.name);
+            }
+            carbon_parser::ast::walk_expr(self, expr);
+        }
+    }
+
+    #[test]
+    fn test_default_methods_reach_nested_expressions() -> Result<()> {
+        let program = parse_to_ast(
+            r#"
+            fn main(items: [i32; size]) {
+                if (var first: i32 = items[start]) {
+                    while (a < b) { total += (c * d).0; }
+                } else {
+                    match (e) { case x if x > f => { } default => { g; } }
+                }
+            }
+            "#,
+        )?;
+        let mut collector = IdentCollector::default();
+        walk_program(&mut collector, &program);
+        assert_eq!(
+            collector.names,
+            ["size", "items", "start", "a", "b", "c", "d", "e", "x", "f", "g"]
+        );
+        Ok(())
+    }
+
+    struct SkipBodies {
+        exprs: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for SkipBodies {
+        fn visit_function(&mut self, _: &'ast FunctionDecl) {}
+
+        fn visit_expr(&mut self, _: &'ast Expr) {
+            self.exprs += 1;
+        }
+    }
+
+    #[test]
+    fn test_override_without_walk_stops_descent() -> Result<()> {
+        let program = parse_to_ast("var x: i32 = 1; fn f() { return 2 + 3; }")?;
+        let mut visitor = SkipBodies { exprs: 0 };
+        walk_program(&mut visitor, &program);
+        assert_eq!(visitor.exprs, 1, "Only the initializer of `x` is visited");
+        Ok(())
+    }
+}
+
+mod array_size_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Item};
+
+    #[test]
+    fn test_uninitialized_fixed_array() -> Result<()> {
+        let code = "var buf: [u8; 256];";
+        assert!(parse_var_decl(code).is_ok());
+
+        let program = parse_to_ast(code)?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert!(decl.init.is_none());
+        let array = decl.ty.array.as_ref().expect("an array type");
+        assert_eq!(array.element.name, "u8");
+        assert!(matches!(&array.size, Some(Expr::Literal(size)) if size.text == "256"));
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_size_array_is_warned() -> Result<()> {
+        let code = "fn main() { var empty: [u8; 0]; var also: [[i32; 2 - 2]; 3]; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors.iter().all(|e| e.severity == Severity::Warning));
+        assert!(errors[0].message.contains("[u8; 0]"));
+        assert_eq!(
+            &code[errors[1].span.start..errors[1].span.end],
+            "[i32; 2 - 2]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_constant_size_is_an_error() -> Result<()> {
+        let code = "fn main(n: i32) { var buf: [u8; n * 2]; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, Severity::Error);
+        assert!(errors[0].message.contains("compile-time constant"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "n * 2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_time_parameter_sizes() -> Result<()> {
+        let code = r#"
+            fn zeros(N:! i32) -> [i32; N + 1] { var out: [i32; N + 1]; return out; }
+            class Buffer(Size:! i32) { var data: [u8; Size]; }
+        "#;
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative_size_is_an_error() -> Result<()> {
+        let code = "fn main() { var a: [i32; -1]; var b: [i32; 2 - 5]; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors.iter().all(|e| e.severity == Severity::Error));
+        assert!(errors[0]
+            .message
+            .contains("must not be negative, found `-1`"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "-1");
+        assert!(errors[1].message.contains("found `-3`"));
+        assert_eq!(&code[errors[1].span.start..errors[1].span.end], "2 - 5");
+        Ok(())
+    }
+
+    #[test]
+    fn test_float_size_is_an_error() -> Result<()> {
+        let code = "fn main() { var a: [i32; 1.5]; var b: [i32; 1.5 * 2]; }";
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors.iter().all(|e| e.severity == Severity::Error));
+        assert!(errors[0]
+            .message
+            .contains("must be an integer, found `1.5`"));
+        assert_eq!(&code[errors[0].span.start..errors[0].span.end], "1.5");
+        assert_eq!(&code[errors[1].span.start..errors[1].span.end], "1.5 * 2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_positive_integer_size_is_clean() -> Result<()> {
+        let code = "fn main() { var a: [i32; 4]; var b: [i32; 0x10 - 2]; }";
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+}
+
+mod unique_names_tests {
+    use super::*;
+    use carbon_parser::semantics::validate_unique_names;
+
+    fn duplicates(code: &str, allow_overloads: bool) -> Result<Vec<(String, &str)>> {
+        let program = parse_to_ast(code)?;
+        Ok(validate_unique_names(&program, allow_overloads)
+            .into_iter()
+            .map(|error| (error.message, &code[error.span.start..error.span.end]))
+            .collect())
+    }
+
+    #[test]
+    fn test_duplicate_top_level_functions() -> Result<()> {
+        let code = "fn foo() {}\nvar limit: i32 = 1;\nfn foo() {}\nclass limit {}";
+        let errors = duplicates(code, false)?;
+        assert_eq!(
+            errors,
+            [
+                (
+                    "`foo` is already declared at the top level".to_string(),
+                    "foo"
+                ),
+                (
+                    "`limit` is already declared at the top level".to_string(),
+                    "limit"
+                ),
+            ]
+        );
+        let program = parse_to_ast(code)?;
+        let second = validate_unique_names(&program, false)[0].span;
+        assert_eq!(
+            second.start,
+            code.rfind("foo").unwrap(),
+            "the second `foo` is reported"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_class_fields() -> Result<()> {
+        let code = r#"
+            class Point {
+                var x: i32;
+                var y: i32;
+                var x: f64;
+                fn y() -> i32 { return 0; }
+            }
+            choice Color { Red, Green, Red }
+        "#;
+        let errors = duplicates(code, true)?;
+        assert_eq!(
+            errors,
+            [
+                ("`x` is already declared in class `Point`".to_string(), "x"),
+                ("`y` is already declared in class `Point`".to_string(), "y"),
+                (
+                    "`Red` is already declared in choice `Color`".to_string(),
+                    "Red"
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overloads() -> Result<()> {
+        let code = "fn show(x: i32) {}\nfn show(x: f64) {}\nfn show(y: i32) {}";
+        assert_eq!(duplicates(code, false)?.len(), 2);
+        assert_eq!(
+            duplicates(code, true)?,
+            [(
+                "`show` is already declared at the top level".to_string(),
+                "show"
+            )],
+            "overloads need different parameter types"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_names() -> Result<()> {
+        let code = r#"
+            var count: i32 = 0;
+            fn main() { var count: i32 = 1; }
+            class A { var value: i32; fn get() -> i32 { return 0; } }
+            class B { var value: i32; fn get() -> i32 { return 1; } }
+            choice Mode { Fast, Slow }
+        "#;
+        assert!(duplicates(code, false)?.is_empty());
+        Ok(())
+    }
+}
+
+mod symbol_table_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+    use carbon_parser::semantics::SymbolKind;
+
+    fn table(code: &str) -> Result<SymbolTable> {
+        Ok(build_symbol_table(&parse_to_ast(code)?))
+    }
+
+    #[test]
+    fn test_inner_block_variable_not_visible_outside() -> Result<()> {
+        let code = r#"
+            fn main() -> i32 {
+                while (running()) {
+                    var inner: i32 = 1;
+                    step(inner);
+                }
+                return done;
+            }
+        "#;
+        let table = table(code)?;
+        let use_inside = code.find("step(inner)").expect("call");
+        let symbol = table
+            .lookup("inner", use_inside)
+            .expect("visible in its block");
+        assert_eq!(symbol.kind, SymbolKind::Variable);
+        assert_eq!(symbol.ty.as_ref().map(|ty| ty.name.as_str()), Some("i32"));
+        assert_eq!(
+            &code[symbol.span.start..symbol.span.end],
+            "var inner: i32 = 1;"
+        );
+
+        let use_outside = code.find("return done").expect("return");
+        assert!(
+            table.lookup("inner", use_outside).is_none(),
+            "`inner` is out of scope after its block"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shadowing() -> Result<()> {
+        let code = r#"
+            var x: i32 = 0;
+            fn f(x: f64) {
+                var y: i32 = x;
+                while (more()) { var x: bool = true; g(x); }
+                h(x);
+            }
+        "#;
+        let table = table(code)?;
+
+        let kind_at = |needle: &str| {
+            let offset = code.find(needle).expect("needle");
+            table
+                .lookup("x", offset)
+                .map(|symbol| (symbol.kind, symbol.ty.clone()))
+        };
+        let (kind, ty) = kind_at("= x;").expect("the parameter");
+        assert_eq!(kind, SymbolKind::Parameter);
+        assert_eq!(ty.map(|ty| ty.name), Some("f64".to_string()));
+        let (kind, ty) = kind_at("g(x)").expect("the inner variable");
+        assert_eq!(kind, SymbolKind::Variable);
+        assert_eq!(ty.map(|ty| ty.name), Some("bool".to_string()));
+        assert_eq!(
+            kind_at("h(x)").map(|(kind, _)| kind),
+            Some(SymbolKind::Parameter)
+        );
+        assert_eq!(
+            table.lookup("x", 0).map(|s| s.kind),
+            Some(SymbolKind::Variable)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_variable_visible_after_declaration() -> Result<()> {
+        let code = "fn f() { g(a); var a: i32 = a; h(a); }";
+        let table = table(code)?;
+        assert!(table.lookup("a", code.find("g(a)").expect("g")).is_none());
+        assert!(
+            table.lookup("a", code.find("= a").expect("init")).is_none(),
+            "A variable is not in scope in its own initializer"
+        );
+        assert!(table.lookup("a", code.find("h(a)").expect("h")).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_functions_bindings_and_members() -> Result<()> {
+        let code = r#"
+            fn main() { later(); }
+            fn later() -> i32 { return 1; }
+            class Counter {
+                fn Get() -> i32 { return value; }
+                var value: i32;
+            }
+            fn pick(items: List) {
+                for (var item: i32 in items) { use(item); }
+                if (var first: i32 = head(items)) { use(first); } else { none(); }
+                match (items) { case Pair(a, b: i32) => { use(b); } default => { } }
+            }
+        "#;
+        let table = table(code)?;
+        let at = |needle: &str| code.find(needle).expect("needle");
+
+        let later = table
+            .lookup("later", at("later();"))
+            .expect("functions are hoisted");
+        assert_eq!(later.kind, SymbolKind::Function);
+        assert_eq!(later.ty.as_ref().map(|ty| ty.name.as_str()), Some("i32"));
+        assert_eq!(
+            table.lookup("value", at("return value")).map(|s| s.kind),
+            Some(SymbolKind::Field)
+        );
+        assert!(table.lookup("value", at("fn pick")).is_none());
+        assert!(table.lookup("item", at("use(item)")).is_some());
+        assert!(table.lookup("first", at("use(first)")).is_some());
+        assert!(table.lookup("first", at("none()")).is_none());
+        assert!(table.lookup("b", at("use(b)")).is_some());
+        assert!(table.lookup("a", at("use(b)")).is_some());
+        assert!(table.lookup("b", at("default")).is_none());
+        assert_eq!(table.scopes()[0].parent, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inferred_types() -> Result<()> {
+        let code = r#"
+            var x = 42;
+            fn main() {
+                var s = "hi";
+                let flag: auto = (true);
+                var y = f();
+                var ratio: auto = 0.5;
+                done();
+            }
+        "#;
+        let table = table(code)?;
+        let end = code.find("done()").expect("call");
+        let ty = |name: &str| {
+            let symbol = table.lookup(name, end).expect("declared");
+            symbol.ty.as_ref().map(|ty| ty.name.clone())
+        };
+        assert_eq!(ty("x").as_deref(), Some("i32"));
+        assert_eq!(ty("s").as_deref(), Some("String"));
+        assert_eq!(ty("flag").as_deref(), Some("bool"));
+        assert_eq!(ty("ratio").as_deref(), Some("f64"));
+        assert_eq!(ty("y"), None, "a call's type is unknown");
+        Ok(())
+    }
+
+    #[test]
+    fn test_omitted_type_is_auto() -> Result<()> {
+        let program = parse_to_ast("var x = 1; fn f() { let y = x; }")?;
+        assert_eq!(
+            program.to_string(),
+            "var x: auto = 1;\n\nfn f() {\n  let y: auto = x;\n}\n"
+        );
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert!(decl.ty.is_auto());
+        assert_eq!(decl.ty.span, Span::new(5, 5));
+        assert!(
+            parse_to_ast("var x;").is_err(),
+            "an untyped var needs a value"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_names_starting_with_var_or_let() -> Result<()> {
+        let program = parse_to_ast("fn f() { variance = 1; letter = 2; var_count += 1; }")?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let targets: Vec<&str> = function
+            .body
+            .stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::Assign(assign) => assign.target.name.as_str(),
+                other => panic!("expected an assignment, found {other:?}"),
+            })
+            .collect();
+        assert_eq!(targets, ["variance", "letter", "var_count"]);
+        assert!(parse_var_decl("variance = 1;").is_err());
+        assert!(parse_let_decl("letter = 2;").is_err());
+        Ok(())
+    }
+}
+
+mod locals_tests {
+    use super::*;
+    use carbon_parser::ast::{FunctionDecl, Item};
+
+    fn function(code: &str) -> Result<FunctionDecl> {
+        match parse_to_ast(code)?.items.pop() {
+            Some(Item::Function(function)) => Ok(function),
+            other => panic!("expected a function, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_locals_at_each_depth() -> Result<()> {
+        let code = r#"
+            fn walk(items: List, limit: i32) {
+                var total: i32 = 0;
+                let step: i64 = 2;
+                for (var item: Node in items) {
+                    if (var child: Node* = first(item)) {
+                        var seen: bool = true;
+                    }
+                }
+                while (total < limit) { var next: i32 = total + 1; }
+            }
+        "#;
+        let function = function(code)?;
+        let locals: Vec<(String, String, usize)> = locals_of(&function, false)
+            .into_iter()
+            .map(|local| (local.name, local.ty.unwrap().canonical(), local.depth))
+            .collect();
+        let expected = [
+            ("total", "i32", 1),
+            ("step", "i64", 1),
+            ("item", "Node", 2),
+            ("child", "Node*", 3),
+            ("seen", "bool", 3),
+            ("next", "i32", 2),
+        ];
+        assert_eq!(
+            locals,
+            expected.map(|(name, ty, depth)| (name.to_string(), ty.to_string(), depth))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_locals_with_params() -> Result<()> {
+        let code = "fn f(n: i32, rest: ...) { for (var i: i32 = 0; i < n; i += 1) { } }";
+        let function = function(code)?;
+        let locals = locals_of(&function, true);
+        let names: Vec<&str> = locals.iter().map(|local| local.name.as_str()).collect();
+        assert_eq!(names, ["n", "rest", "i"]);
+        assert_eq!(locals[1].ty, None, "an untyped variadic parameter");
+        assert_eq!(locals[2].depth, 2);
+        assert_eq!(&code[locals[0].span.start..locals[0].span.end], "n: i32");
+        assert_eq!(locals_of(&function, false).len(), 1);
+        Ok(())
+    }
+}
+
+mod undeclared_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+    use carbon_parser::semantics::check_undeclared;
+
+    #[test]
+    fn test_undeclared_return_value() -> Result<()> {
+        let code = "fn f(x: i32) -> i32 { return y; }";
+        let diagnostics = check_undeclared(&parse_to_ast(code)?);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert!(diagnostics[0].message.contains("`y`"));
+        assert_eq!(
+            &code[diagnostics[0].span.start..diagnostics[0].span.end],
+            "y"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameters_and_called_functions_are_not_flagged() -> Result<()> {
+        let code = r#"
+            var limit: i32 = 10;
+            fn clamp(x: i32) -> i32 {
+                var result: i32 = min(x, limit);
+                for (var item: i32 in range(result)) { log(item); }
+                return result;
+            }
+        "#;
+        let diagnostics = check_undeclared(&parse_to_ast(code)?);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_use_before_declaration_and_out_of_scope() -> Result<()> {
+        let code = r#"
+            fn f() -> i32 {
+                var a: i32 = b;
+                var b: i32 = 1;
+                while (b > 0) { var inner: i32 = b; }
+                return inner;
+            }
+        "#;
+        let diagnostics = check_undeclared(&parse_to_ast(code)?);
+        let names: Vec<&str> = diagnostics
+            .iter()
+            .map(|d| &code[d.span.start..d.span.end])
+            .collect();
+        assert_eq!(names, ["b", "inner"]);
+        Ok(())
+    }
+
+    fn undeclared_names(code: &str) -> Result<Vec<&str>> {
+        Ok(check_undeclared(&parse_to_ast(code)?)
+            .iter()
+            .map(|d| &code[d.span.start..d.span.end])
+            .collect())
+    }
+
+    #[test]
+    fn test_break_and_continue_are_not_names() -> Result<()> {
+        let code = "fn f() { while (true) { break; } while (ready()) { continue; } }";
+        assert!(undeclared_names(code)?.is_empty());
+        let program = parse_to_ast(code)?;
+        let Item::Function(f) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::While(first) = &f.body.stmts[0] else {
+            panic!("expected a while loop");
+        };
+        assert!(matches!(first.body.stmts[0], Stmt::Break(_)));
+        let Stmt::While(second) = &f.body.stmts[1] else {
+            panic!("expected a while loop");
+        };
+        assert!(matches!(second.body.stmts[0], Stmt::Continue(_)));
+        assert_eq!(undeclared_names("fn f() { breakpoint; }")?, ["breakpoint"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_assignment_targets_are_uses() -> Result<()> {
+        assert_eq!(
+            undeclared_names("fn f() { zz = 1; zz += 2; q, w = g(); }")?,
+            ["zz", "zz", "q", "w"]
+        );
+        assert_eq!(
+            undeclared_names("fn f() { x = 1; var x: i32 = 2; x = 3; }")?,
+            ["x"]
+        );
+        assert_eq!(
+            undeclared_names("fn f() { for (var i: i32 = 0; i < 3; j += 1) { } }")?,
+            ["j"]
+        );
+        Ok(())
+    }
+}
+
+mod pragma_tests {
+    use super::*;
+    use carbon_parser::ast::Item;
+    use carbon_parser::format::format_carbon;
+
+    #[test]
+    fn test_pragma_is_collected_with_arguments() -> Result<()> {
+        let code = "//! pragma optimize(2, fast)\nfn main() { }\n";
+        assert!(parse_carbon(code).is_ok());
+
+        let program = parse_to_ast(code)?;
+        assert_eq!(program.items.len(), 1);
+        assert_eq!(program.pragmas.len(), 1);
+        let pragma = &program.pragmas[0];
+        assert_eq!(pragma.name.name, "optimize");
+        assert_eq!(pragma.args, ["2", "fast"]);
+        assert_eq!(
+            &code[pragma.span.start..pragma.span.end],
+            "//! pragma optimize(2, fast)"
+        );
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordinary_comments_are_not_pragmas() -> Result<()> {
+        let code = r#"
+            // pragma optimize(2)
+            //! pragmatic advice
+            /* //! pragma optimize(2) */
+            fn main() { }
+        "#;
+        let program = parse_to_ast(code)?;
+        assert!(program.pragmas.is_empty());
+        assert_eq!(program.items.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_pragma_parses_with_a_warning() -> Result<()> {
+        let code = "var x: i32 = 1;\n//! pragma inline\nfn main() { }";
+        let program = parse_to_ast(code)?;
+        assert_eq!(program.pragmas[0].name.name, "inline");
+        assert!(program.pragmas[0].args.is_empty());
+
+        let errors = check_program(code)?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, Severity::Warning);
+        assert!(errors[0].message.contains("`inline`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_keeps_pragmas() -> Result<()> {
+        let code = "fn main() { }\n//!   pragma  optimize( 2 )\n";
+        let formatted = format_carbon(code)?;
+        assert_eq!(formatted, "//! pragma optimize(2)\n\nfn main() {}\n");
+        assert_eq!(format_carbon(&formatted)?, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_pragma_is_a_comment() -> Result<()> {
+        for code in [
+            "//! pragma optimize(2\nfn main() { }",
+            "//! pragma see the docs\nfn main() { }",
+        ] {
+            let program = parse_to_ast(code)?;
+            assert!(program.pragmas.is_empty(), "{code}");
+            assert_eq!(program.items.len(), 1, "{code}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pragma_inside_function_is_a_comment() -> Result<()> {
+        let program = parse_to_ast("fn f() {\n  //! pragma optimize(2)\n}")?;
+        assert!(program.pragmas.is_empty());
+        let Item::Function(f) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        assert!(f.body.stmts.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pragma_inside_class_is_a_comment() -> Result<()> {
+        let program = parse_to_ast("class C {\n  //! pragma inline\n  var x: i32;\n}")?;
+        assert!(program.pragmas.is_empty());
+        assert_eq!(program.items.len(), 1);
+        Ok(())
+    }
+}
+
+mod let_decl_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Item, Stmt};
+    use carbon_parser::format::format_carbon;
+
+    #[test]
+    fn test_let_requires_initializer() -> Result<()> {
+        assert!(parse_let_decl("let x: i32 = 5;").is_ok());
+        assert!(
+            parse_let_decl("let x: i32;").is_err(),
+            "A `let` binding without an initializer should be rejected"
+        );
+        assert!(
+            parse_var_decl("var x: i32;").is_ok(),
+            "A `var` without an initializer should still parse"
+        );
+        assert!(parse_carbon("fn main() { let x: i32; }").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_let_at_top_level_and_in_blocks() -> Result<()> {
+        let code = r#"
+            let limit: i32 = 10;
+            fn main() -> i32 { let twice: i32 = limit * 2; return twice; }
+        "#;
+        let program = parse_to_ast(code)?;
+        let Item::Let(decl) = &program.items[0] else {
+            panic!("expected a let binding");
+        };
+        assert_eq!(decl.name.name, "limit");
+        assert_eq!(decl.ty.name, "i32");
+        assert!(matches!(&decl.init, Expr::Literal(value) if value.text == "10"));
+
+        let Item::Function(main) = &program.items[1] else {
+            panic!("expected a function");
+        };
+        assert!(matches!(&main.body.stmts[0], Stmt::Let(decl) if decl.name.name == "twice"));
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_let_is_a_declaration() -> Result<()> {
+        let code = "fn main() -> i32 { let x: i32 = 1; return x; }";
+        let program = parse_to_ast(code)?;
+        assert!(carbon_parser::semantics::check_undeclared(&program).is_empty());
+        assert_eq!(
+            format_carbon(code)?,
+            "fn main() -> i32 {\n  let x: i32 = 1;\n  return x;\n}\n"
+        );
+        assert_eq!(
+            describe_program(&parse_to_ast("let x: i32 = 1;")?),
+            "Constant x of type i32."
+        );
+        Ok(())
+    }
+}
+
+mod feature_tests {
+    use super::*;
+
+    #[test]
+    fn test_features_sorted_and_unique() {
+        let features = supported_features();
+        assert!(!features.is_empty());
+        assert!(
+            features.windows(2).all(|pair| pair[0] < pair[1]),
+            "Features must be strictly sorted: {features:?}"
+        );
+        assert!(features
+            .iter()
+            .all(|name| name.chars().all(|c| c.is_ascii_lowercase() || c == '-')));
+    }
+
+    #[test]
+    fn test_has_feature() {
+        assert!(has_feature("classes"));
+        assert!(has_feature("where-clauses"));
+        assert!(!has_feature("templates"));
+        assert!(!has_feature("Classes"), "Names are case-sensitive");
+        assert!(!has_feature(""));
+    }
+}
+
+mod comparison_chain_tests {
+    use super::*;
+    use carbon_parser::ast::{BinaryOp, Expr, Item, Program};
+
+    const DESUGAR: ParseOptions = ParseOptions {
+        max_identifier_len: None,
+        max_literal_len: None,
+        desugar_comparison_chains: true,
+        script_mode: false,
+    };
+
+    fn init(program: &Program) -> &Expr {
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        decl.init.as_ref().expect("an initializer")
+    }
+
+    #[test]
+    fn test_chain_matches_hand_written_conjunction() -> Result<()> {
+        let chained = parse_to_ast_with_options("var ok: bool = a < b > c;", &DESUGAR)?;
+        let written = parse_to_ast("var ok: bool = a < b && b > c;")?;
+        assert_eq!(shape(init(&chained)), shape(init(&written)));
+        assert_eq!(shape(init(&chained)), "((a < b) && (b > c))");
+
+        let Expr::Binary(and) = init(&chained) else {
+            panic!("expected a conjunction");
+        };
+        assert_eq!(and.op, BinaryOp::And);
+        let (Expr::Binary(first), Expr::Binary(second)) = (&*and.lhs, &*and.rhs) else {
+            panic!("expected two comparisons");
+        };
+        assert_eq!(
+            first.rhs.span(),
+            second.lhs.span(),
+            "Both copies of `b` point at the same source text"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_chains_and_nested_positions() -> Result<()> {
+        let code = r#"
+            var ok: bool = 0 < f(x > y > z) < n + 1 > m;
+            fn main() { while (a < b < c) { } }
+        "#;
+        let program = parse_to_ast_with_options(code, &DESUGAR)?;
+        assert_eq!(
+            shape(init(&program)),
+            "(((0 < f(((x > y) && (y > z)))) && (f(((x > y) && (y > z))) < (n + 1))) && ((n + 1) > m))"
+        );
+
+        let Item::Function(main) = &program.items[1] else {
+            panic!("expected a function");
+        };
+        let carbon_parser::ast::Stmt::While(while_stmt) = &main.body.stmts[0] else {
+            panic!("expected a while loop");
+        };
+        assert_eq!(shape(&while_stmt.condition), "((a < b) && (b < c))");
+        Ok(())
+    }
+
+    #[test]
+    fn test_option_off_leaves_chain() -> Result<()> {
+        let code = "var ok: bool = a < b < c;";
+        let program = parse_to_ast_with_options(code, &ParseOptions::default())?;
+        assert_eq!(shape(init(&program)), "((a < b) < c)");
+        assert_eq!(program, parse_to_ast(code)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parenthesized_and_equality_are_not_chains() -> Result<()> {
+        let program = parse_to_ast_with_options("var ok: bool = (a < b) < c;", &DESUGAR)?;
+        assert_eq!(shape(init(&program)), "([(a < b)] < c)");
+
+        let program = parse_to_ast_with_options("var ok: bool = a == b == c;", &DESUGAR)?;
+        assert_eq!(shape(init(&program)), "((a == b) == c)");
+        Ok(())
+    }
+}
+
+mod member_access_tests {
+    use super::*;
+    use carbon_parser::ast::Expr;
+    use carbon_parser::format::format_carbon;
+
+    #[test]
+    fn test_chained_member_access() -> Result<()> {
+        let Expr::Member(access) = parse_expression_ast("a.b")? else {
+            panic!("a.b should be a member access");
+        };
+        assert_eq!(access.member.name, "b");
+
+        let Expr::Member(outer) = parse_expression_ast("a.b.c")? else {
+            panic!("a.b.c should be a member access");
+        };
+        assert_eq!(outer.member.name, "c");
+        let Expr::Member(inner) = *outer.base else {
+            panic!("a.b.c should access c on a.b");
+        };
+        assert_eq!(inner.member.name, "b");
+        assert_eq!((inner.span.start, inner.span.end), (0, "a.b".len()));
+        assert!(matches!(*inner.base, Expr::Ident(ref ident) if ident.name == "a"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_method_call() -> Result<()> {
+        let code = "obj.compute(x, y)";
+        let Expr::MethodCall(call) = parse_expression_ast(code)? else {
+            panic!("{code} should be a method call");
+        };
+        assert_eq!(call.method.name, "compute");
+        assert!(matches!(*call.receiver, Expr::Ident(ref ident) if ident.name == "obj"));
+        let args: Vec<_> = call
+            .args
+            .iter()
+            .map(|arg| match arg {
+                Expr::Ident(ident) => ident.name.as_str(),
+                other => panic!("unexpected argument {other:?}"),
+            })
+            .collect();
+        assert_eq!(args, ["x", "y"]);
+        assert_eq!(call.span.end, code.len());
+
+        let Expr::MethodCall(empty) = parse_expression_ast("list.clear()")? else {
+            panic!("list.clear() should be a method call");
+        };
+        assert!(empty.args.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_composes_with_calls_and_indexing() -> Result<()> {
+        // get().items[0].name(1).len
+        let Expr::Member(len) = parse_expression_ast("get().items[0].name(1).len")? else {
+            panic!("expected a member access at the top");
+        };
+        assert_eq!(len.member.name, "len");
+        let Expr::MethodCall(name) = *len.base else {
+            panic!("len should be read from the result of name(1)");
+        };
+        let Expr::Index(index) = *name.receiver else {
+            panic!("name(1) should be called on items[0]");
+        };
+        let Expr::Member(items) = *index.base else {
+            panic!("items[0] should index get().items");
+        };
+        assert!(matches!(*items.base, Expr::Call(ref call) if call.callee.name == "get"));
+
+        assert!(parse_expression("a.b(c.d(e)) + f.g * 2").is_ok());
+        assert!(parse_expression("obj.method(x)?").is_ok());
+        assert!(parse_expression("obj.method(x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_method_calls_in_programs() -> Result<()> {
+        let code = r#"
+            fn main() {
+                var total: i32 = shape.area(2) + 1;
+                list.push(total);
+            }
+        "#;
+        assert!(parse_carbon(code).is_ok());
+        assert_eq!(
+            format_carbon(code)?,
+            "fn main() {\n  var total: i32 = shape.area(2) + 1;\n  list.push(total);\n}\n"
+        );
+
+        let errors = check_program("fn f() { list.push(missing); }")?;
+        assert!(
+            errors.is_empty(),
+            "Method names are not looked up as variables: {errors:?}"
+        );
+        Ok(())
+    }
+}
+
+mod free_variable_tests {
+    use super::*;
+
+    fn free(code: &str) -> Result<Vec<String>> {
+        Ok(parse_expression_ast(code)?
+            .free_variables()
+            .into_iter()
+            .collect())
+    }
+
+    #[test]
+    fn test_plain_expression() -> Result<()> {
+        assert_eq!(free("a + b * a - 1")?, ["a", "b"]);
+        assert_eq!(
+            free("(total / count) > limit")?,
+            ["count", "limit", "total"]
+        );
+        assert!(free("1 + 2")?.is_empty(), "Literals reference nothing");
+        assert!(free("i32")?.is_empty(), "Type values are not variables");
+        Ok(())
+    }
+
+    #[test]
+    fn test_names_that_are_not_variables() -> Result<()> {
+        assert_eq!(
+            free("compute(x, y)")?,
+            ["x", "y"],
+            "The callee is a function, not a variable"
+        );
+        assert_eq!(
+            free("point.x + shape.area(scale)")?,
+            ["point", "scale", "shape"],
+            "Member and method names are not variables"
+        );
+        assert_eq!(free("grid[row][col].0")?, ["col", "grid", "row"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_operands() -> Result<()> {
+        assert_eq!(
+            free("(await fetch(url))? + cache[key(id)]")?,
+            ["cache", "id", "url"]
+        );
+        Ok(())
+    }
+}
+
+mod index_expr_tests {
+    use super::*;
+    use carbon_parser::ast::{BinaryOp, Expr};
+
+    #[test]
+    fn test_single_index() -> Result<()> {
+        let Expr::Index(index) = parse_expression_ast("arr[0]")? else {
+            panic!("arr[0] should be an index expression");
+        };
+        assert!(matches!(*index.base, Expr::Ident(ref ident) if ident.name == "arr"));
+        assert!(matches!(*index.index, Expr::Literal(ref literal) if literal.text == "0"));
+        assert_eq!((index.span.start, index.span.end), (0, "arr[0]".len()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_chained_index() -> Result<()> {
+        let Expr::Index(column) = parse_expression_ast("matrix[i][j]")? else {
+            panic!("matrix[i][j] should be an index expression");
+        };
+        assert!(matches!(*column.index, Expr::Ident(ref ident) if ident.name == "j"));
+        let Expr::Index(row) = *column.base else {
+            panic!("matrix[i][j] should index the result of matrix[i]");
+        };
+        assert!(matches!(*row.base, Expr::Ident(ref ident) if ident.name == "matrix"));
+        assert!(matches!(*row.index, Expr::Ident(ref ident) if ident.name == "i"));
+        assert_eq!(row.span.end, "matrix[i]".len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic_index() -> Result<()> {
+        let Expr::Binary(sum) = parse_expression_ast("arr[i + 1] * 2")? else {
+            panic!("the index binds tighter than `*`");
+        };
+        assert_eq!(sum.op, BinaryOp::Mul);
+        let Expr::Index(index) = *sum.lhs else {
+            panic!("expected arr[i + 1] on the left");
+        };
+        assert!(matches!(*index.index, Expr::Binary(ref add) if add.op == BinaryOp::Add));
+        assert!(parse_expression("arr[]").is_err(), "An index is required");
+        assert!(parse_expression("arr[i").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_with_member_access_and_calls() -> Result<()> {
+        let Expr::Member(value) = parse_expression_ast("obj.items[i].value")? else {
+            panic!("obj.items[i].value should read value last");
+        };
+        assert_eq!(value.member.name, "value");
+        let Expr::Index(index) = *value.base else {
+            panic!("value should be read from items[i]");
+        };
+        assert!(matches!(*index.base, Expr::Member(ref items) if items.member.name == "items"));
+
+        let Expr::Index(call) = parse_expression_ast("rows()[0]")? else {
+            panic!("rows()[0] should index the call result");
+        };
+        assert!(matches!(*call.base, Expr::Call(ref call) if call.callee.name == "rows"));
+        assert!(parse_expression("table.row(i)[j].cells[k + 1]").is_ok());
+        assert!(parse_carbon("fn f() -> i32 { return grid[y][x]; }").is_ok());
+        Ok(())
+    }
+}
+
+mod stream_tests {
+    use super::*;
+    use carbon_parser::ast::Item;
+    use std::io::{BufReader, Read};
+
+    fn function_names(source: &str) -> Result<Vec<String>> {
+        parse_carbon_streaming(source.as_bytes())
+            .map(|declaration| match declaration?.item {
+                Item::Function(function) => Ok(function.name.name),
+                other => panic!("unexpected declaration {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_comment_does_not_split() -> Result<()> {
+        let source = "fn a() {}\n/* x /* y */ ; } */\nfn b() {}\n";
+        assert_eq!(function_names(source)?, ["a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_three_concatenated_functions() -> Result<()> {
+        let functions = [
+            "fn first() -> i32 { return 1; }\n",
+            "fn second(x: i32) -> i32 {\n  if (x > 0) { return x; }\n  return 0;\n}\n",
+            "fn third() { var s: String = \"}\"; }\n",
+        ];
+        let source = functions.concat();
+        // A tiny buffer makes the reader hand over the text in small pieces.
+        let reader = BufReader::with_capacity(8, source.as_bytes());
+        let declarations: Vec<_> = parse_carbon_streaming(reader).collect::<Result<_, _>>()?;
+        assert_eq!(declarations.len(), 3, "One item per function");
+
+        let lines: Vec<_> = declarations.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [1, 2, 6]);
+        for declaration in &declarations {
+            let span = declaration.item.span();
+            let text = &source[declaration.offset + span.start..declaration.offset + span.end];
+            assert!(
+                text.starts_with("fn ") && text.ends_with('}'),
+                "Spans are relative to the offset: {text:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_declaration_boundaries() -> Result<()> {
+        let source = r#"
+            //! pragma optimize(2)
+            // A comment with a stray } and ;
+            var sizes: [i32; 2];
+            /* fn commented_out() { */
+            fn a() -> [i32; 3] { while (true) { } } fn b() {}
+            class Point { var x: i32; fn get() -> i32 { return 0; } }
+            choice Color { Red, Green }
+            let limit: i32 = 10;
+        "#;
+        let kinds: Vec<_> = parse_carbon_streaming(source.as_bytes())
+            .map(|declaration| {
+                Ok(match declaration?.item {
+                    Item::Function(_) => "fn",
+                    Item::Class(_) => "class",
+                    Item::Choice(_) => "choice",
+                    Item::Var(_) => "var",
+                    Item::Let(_) => "let",
+                })
+            })
+            .collect::<Result<_>>()?;
+        assert_eq!(kinds, ["var", "fn", "fn", "class", "choice", "let"]);
+        assert_eq!(
+            function_names("fn a() {} fn b() {}")?,
+            ["a", "b"],
+            "Declarations sharing a line are split"
+        );
+        assert!(function_names("  // only a comment\n")?.is_empty());
+        assert_eq!(
+            function_names(r#"fn a() { print("\"}"); } fn b() {}"#)?,
+            ["a", "b"],
+            "An escaped quote does not end a string"
+        );
+        assert_eq!(
+            function_names("fn a() { var c: char = '}'; } fn b() {}")?,
+            ["a", "b"],
+            "A brace in a character literal does not close a block"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_are_reported_and_skipped() -> Result<()> {
+        let source = "fn good() {}\nfn bad( {}\nfn also_good() {}\nfn é() {} fn broken {}";
+        let results: Vec<_> = parse_carbon_streaming(source.as_bytes()).collect();
+        assert_eq!(results.len(), 5);
+        assert!(results[0].is_ok() && results[2].is_ok());
+
+        let Err(error) = &results[1] else {
+            panic!("fn bad( should fail");
+        };
+        assert_eq!(
+            error.location(),
+            parse_carbon("fn good() {}\nfn bad( {}")
+                .unwrap_err()
+                .location(),
+            "Errors are located in the whole input"
+        );
+        let Err(error) = &results[4] else {
+            panic!("fn broken should fail");
+        };
+        assert_eq!(error.location(), Some((4, 11)));
+        assert!(
+            error.to_string().contains("4 |           fn broken {}"),
+            "Earlier declarations on the line are blanked out: {error}"
+        );
+        assert!(results[3].is_err(), "Identifiers are ASCII");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_error_ends_iteration() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk on fire"))
+            }
+        }
+        let reader = BufReader::new(b"fn a() {}\n".chain(Failing));
+        let results: Vec<_> = parse_carbon_streaming(reader).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(&results[1], Err(ParseError::Io(e)) if e.to_string() == "disk on fire"));
+    }
+}
+
+mod file_tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_temp_file() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(b"package Demo api;\nfn main() -> i32 { return 0; }\n")?;
+
+        let mut source = String::new();
+        let program = parse_carbon_file(file.path(), &mut source)?
+            .next()
+            .expect("a program pair");
+        assert_eq!(program.as_rule(), Rule::program);
+        assert!(source.starts_with("package Demo api;"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_syntax_error_in_file() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(b"fn main() {\n  var x: i32 = 1\n}\n")?;
+
+        let error = parse_carbon_file(file.path(), &mut String::new()).unwrap_err();
+        assert_eq!(error.location(), Some((3, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file_is_io_error() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let error =
+            parse_carbon_file(dir.path().join("missing.carbon"), &mut String::new()).unwrap_err();
+        assert!(matches!(error, ParseError::Io(_)));
+        assert_eq!(error.location(), None);
+        Ok(())
+    }
+}
+
+mod backtracking_tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Best of three runs, to keep scheduler noise out of the comparisons.
+    fn parse_time(input: &str) -> Duration {
+        (0..3)
+            .map(|_| {
+                let start = Instant::now();
+                assert!(parse_expression(input).is_ok(), "{input} should parse");
+                start.elapsed()
+            })
+            .min()
+            .expect("at least one run")
+    }
+
+    #[test]
+    fn test_chains_parse_in_linear_time() {
+        // Nesting a few hundred levels deep already exhausts the stack of a
+        // test thread in debug builds, so the sizes stay small.
+        for (name, generate) in adversarial::LINEAR {
+            let small = parse_time(&generate(50));
+            let large = parse_time(&generate(200));
+            // Four times the input should take about four times as long. The
+            // bound is generous but still well below the 16x of quadratic growth.
+            assert!(
+                large < small * 12 + Duration::from_millis(5),
+                "{name}: {small:?} for 50 but {large:?} for 200"
+            );
+        }
+    }
+}
+
+mod synthetic_input_tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_programs_parse() -> Result<()> {
+        // The large program is left to the benchmark; it is slow in debug builds.
+        for &(name, functions) in &synthetic::SIZES[..2] {
+            let source = synthetic::program(functions);
+            let program = parse_to_ast(&source)?;
+            assert_eq!(
+                program.items.len(),
+                functions + 1,
+                "The {name} program has {functions} functions plus main"
+            );
+            assert!(check_program(&source)?.is_empty(), "{name} is valid");
+            assert_eq!(
+                source,
+                synthetic::program(functions),
+                "Generation is deterministic"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_arithmetic() -> Result<()> {
+        assert_eq!(synthetic::nested_arithmetic(0), "1");
+        assert_eq!(synthetic::nested_arithmetic(3), "1 + (2 * (3 - (4)))");
+        assert!(parse_expression(&synthetic::nested_arithmetic(4)).is_ok());
+        Ok(())
+    }
+}
+
+mod precedence_table_tests {
+    use super::*;
+    use carbon_parser::ast::{BinaryOp, Expr};
+
+    fn reversed() -> PrecedenceTable {
+        let table = PrecedenceTable::default();
+        let (sum, product) = (table.level(BinaryOp::Add), table.level(BinaryOp::Mul));
+        table
+            .with_level(BinaryOp::Add, product)
+            .with_level(BinaryOp::Sub, product)
+            .with_level(BinaryOp::Mul, sum)
+            .with_level(BinaryOp::Div, sum)
+            .with_level(BinaryOp::Rem, sum)
+    }
+
+    #[test]
+    fn test_default_table_matches_built_in_precedence() -> Result<()> {
+        for code in [
+            "1 + 2 * 3",
+            "a || b && c == d < e + f * g",
+            "a * b + c * d - e / f % g",
+            "f(a + b * c) - (x + y) * z",
+        ] {
+            let built_in = parse_expression_ast(code)?;
+            let with_default = parse_expression_with(code, &PrecedenceTable::default())?;
+            assert_eq!(with_default, built_in, "{code}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reversed_sum_and_product() -> Result<()> {
+        let table = reversed();
+        let cases = [
+            ("1 + 2 * 3", "((1 + 2) * 3)"),
+            ("1 * 2 + 3", "(1 * (2 + 3))"),
+            ("a - b / c - d", "((a - b) / (c - d))"),
+            ("a + b * c == d", "(((a + b) * c) == d)"),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(
+                shape(&parse_expression_with(code, &table)?),
+                expected,
+                "{code}"
+            );
+        }
+
+        let Expr::Binary(mul) = parse_expression_with("x + y * z", &table)? else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!((mul.span.start, mul.span.end), (0, "x + y * z".len()));
+        assert_eq!(mul.lhs.span().end, "x + y".len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parentheses_and_nested_operands_with_custom_table() -> Result<()> {
+        let table = reversed();
+        assert_eq!(
+            shape(&parse_expression_with("(1 + 2) * 3 + 4", &table)?),
+            "([(1 + 2)] * (3 + 4))",
+            "Parentheses keep their grouping"
+        );
+        assert_eq!(
+            shape(&parse_expression_with("a * (b * c + d)", &table)?),
+            "(a * [(b * (c + d))])"
+        );
+        assert_eq!(
+            shape(&parse_expression_with("f(1 + 2 * 3, 4)", &table)?),
+            "f(((1 + 2) * 3), 4)",
+            "Call arguments use the table too"
+        );
+
+        let equal = PrecedenceTable::default().with_level(BinaryOp::Mul, 5);
+        assert_eq!(
+            shape(&parse_expression_with("1 * 2 + 3 * 4", &equal)?),
+            "(((1 * 2) + 3) * 4)",
+            "Operators on one level associate to the left"
+        );
+        assert!(parse_expression_with("1 +", &table).is_err());
+        Ok(())
+    }
+}
+
+mod ast_mutation_tests {
+    use super::*;
+    use carbon_parser::ast::{Expr, Item};
+    use carbon_parser::format::format_program;
+
+    const SOURCE: &str = r#"
+        var count: i32 = 0;
+        class Counter {
+            var value: i32;
+            fn increment() { value += 1; }
+        }
+        fn helper(x: i32) -> i32 { return x * 2; }
+    "#;
+
+    #[test]
+    fn test_rename_function_and_render() -> Result<()> {
+        let mut program = parse_to_ast(SOURCE)?;
+        for function in program.iter_functions_mut() {
+            if function.name.name == "helper" {
+                function.name.name = "double".to_string();
+            }
+        }
+        let rendered = program.to_string();
+        assert!(
+            rendered.contains("fn double(x: i32) -> i32 {"),
+            "{rendered}"
+        );
+        assert!(!rendered.contains("helper"));
+
+        let reparsed = parse_to_ast(&rendered)?;
+        let Some(Item::Function(double)) = reparsed.items.last() else {
+            panic!("expected the renamed function last");
+        };
+        assert_eq!(double.name.name, "double");
+        assert_eq!(
+            double.name.span.len(),
+            "double".len(),
+            "Spans are fresh again after re-parsing"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mutable_accessors_reach_every_declaration() -> Result<()> {
+        let mut program = parse_to_ast(SOURCE)?;
+        let names: Vec<_> = program
+            .iter_functions_mut()
+            .map(|function| function.name.name.clone())
+            .collect();
+        assert_eq!(names, ["increment", "helper"], "Methods are included");
+
+        for class in program.iter_classes_mut() {
+            class.name.name = "Tally".to_string();
+        }
+        for decl in program.iter_vars_mut() {
+            let Some(Expr::Literal(init)) = &mut decl.init else {
+                panic!("expected a literal initializer");
+            };
+            init.text = "10".to_string();
+        }
+        let stale = program.items[0].span();
+        assert_eq!(
+            &SOURCE[stale.start..stale.end],
+            "var count: i32 = 0;",
+            "Spans still point at the original source"
+        );
+        let rendered = program.to_string();
+        assert!(rendered.starts_with("var count: i32 = 10;\n"), "{rendered}");
+        assert!(rendered.contains("class Tally {"), "{rendered}");
+        assert_eq!(rendered, format_program(&parse_to_ast(&rendered)?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_and_push_items_keep_top_level_in_step() -> Result<()> {
+        let mut program = parse_to_ast(SOURCE)?;
+        let Item::Var(count) = program.remove_item(0) else {
+            panic!("expected the variable first");
+        };
+        assert_eq!(count.name.name, "count");
+        let rendered = program.to_string();
+        assert!(rendered.starts_with("class Counter {"), "{rendered}");
+
+        let helper = program.remove_item(1);
+        program.push_item(helper);
+        program.push_item(Item::Var(count));
+        assert_eq!(program.top_level.len(), program.items.len());
+        let rendered = program.to_string();
+        assert!(
+            rendered.ends_with(
+                "fn helper(x: i32) -> i32 {\n  return x * 2;\n}\n\nvar count: i32 = 0;\n"
+            ),
+            "{rendered}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "top_level refers to declaration 2 but there are only 2")]
+    fn test_removing_from_items_directly_is_caught() {
+        let mut program = parse_to_ast(SOURCE).unwrap();
+        program.items.remove(0);
+        program.to_string();
+    }
+}
+
+mod statement_tests {
+    use super::*;
+
+    /// The rule of the statement kind `input` parses as, and the statement's text.
+    fn kind(input: &str) -> Result<(Rule, String)> {
+        let statement = parse_statement(input)?.next().expect("a statement pair");
+        let text = statement.as_str().to_string();
+        let kind = match statement.as_rule() {
+            Rule::statement => statement
+                .into_inner()
+                .next()
+                .expect("a specific statement")
+                .as_rule(),
+            other => other,
+        };
+        Ok((kind, text))
+    }
+
+    #[test]
+    fn test_every_statement_kind() -> Result<()> {
+        let cases = [
+            ("var x: i32 = 1;", Rule::var_decl),
+            ("let limit: i32 = 10;", Rule::let_decl),
+            ("total += price(item);", Rule::assignment),
+            ("return x * 2;", Rule::return_stmt),
+            ("return;", Rule::return_stmt),
+            ("throw Error(1);", Rule::throw_stmt),
+            (
+                "if (x > 0) { return x; } else { return 0; }",
+                Rule::if_statement,
+            ),
+            ("while (i < n) { i += 1; }", Rule::while_statement),
+            (
+                "for (var item: i32 in items) { print(item); }",
+                Rule::for_statement,
+            ),
+            (
+                "match (v) { case 0 => { } default => { } }",
+                Rule::match_statement,
+            ),
+            ("print(x);", Rule::expression_stmt),
+            ("obj.method(1);", Rule::expression_stmt),
+        ];
+        for (code, expected) in cases {
+            let input = format!("  {code}  // trailing comment\n");
+            let (rule, text) = kind(&input)?;
+            assert_eq!(rule, expected, "{code}");
+            assert_eq!(text, code, "The statement covers exactly its own text");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bare_expression_and_errors() -> Result<()> {
+        assert_eq!(kind("x + 1")?.0, Rule::tail_expr);
+        assert!(parse_statement("").is_err());
+        assert!(parse_statement("x = 1; y = 2;").is_err());
+        assert!(
+            parse_statement("x = 1").is_err(),
+            "Assignments need a semicolon"
+        );
+        assert!(
+            parse_statement("fn main() {}").is_err(),
+            "Functions are declarations, not statements"
+        );
+        Ok(())
+    }
+}
+
+mod multi_assign_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt};
+    use carbon_parser::format::format_carbon;
+
+    /// The statements in the body of the last function in `code`.
+    fn body(code: &str) -> Result<Vec<Stmt>> {
+        let program = parse_to_ast(code)?;
+        match program.items.last() {
+            Some(Item::Function(function)) => Ok(function.body.stmts.clone()),
+            other => panic!("expected a function, got {other:?}"),
+        }
+    }
+
+    /// The rule of the statement kind `input` parses as.
+    fn kind(input: &str) -> Result<Rule> {
+        let statement = parse_statement(input)?.next().expect("a statement pair");
+        Ok(statement
+            .into_inner()
+            .next()
+            .expect("a specific statement")
+            .as_rule())
+    }
+
+    #[test]
+    fn test_two_targets() -> Result<()> {
+        let code = r#"
+            fn swap(a: i32, b: i32) -> (i32, i32) { return a; }
+            fn main() -> i32 {
+                var x: i32 = 1;
+                var y: i32 = 2;
+                x, y = swap(x, y);
+                return x;
+            }
+        "#;
+        let stmts = body(code)?;
+        let Stmt::MultiAssign(assign) = &stmts[2] else {
+            panic!("expected a multi-target assignment, got {:?}", stmts[2]);
+        };
+        let targets: Vec<&str> = assign.targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(targets, ["x", "y"]);
+        assert_eq!(
+            &code[assign.span.start..assign.span.end],
+            "x, y = swap(x, y);"
+        );
+        assert!(
+            check_program(code)?.is_empty(),
+            "Two targets match the two-element tuple returned by `swap`"
+        );
+        assert_eq!(kind("a, b = f();")?, Rule::multi_assignment);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arity_mismatch_is_flagged() -> Result<()> {
+        let code = r#"
+            fn pair() -> (i32, i32) { return 0; }
+            fn one() -> i32 { return 1; }
+            fn main() {
+                var a: i32 = 0;
+                var b: i32 = 0;
+                var c: i32 = 0;
+                a, b, c = pair();
+                a, b = one();
+                a, b = 1;
+                a, b = external();
+            }
+        "#;
+        let errors = check_program(code)?;
+        let flagged: Vec<&str> = errors
+            .iter()
+            .map(|error| &code[error.span.start..error.span.end])
+            .collect();
+        assert_eq!(flagged, ["a, b, c = pair();", "a, b = one();", "a, b = 1;"]);
+        assert!(
+            errors[0].message.contains("returns 2 values"),
+            "{}",
+            errors[0]
+        );
+        assert!(
+            errors[1].message.contains("returns 1 value"),
+            "{}",
+            errors[1]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_target_still_parses() -> Result<()> {
+        let stmts = body("fn main() { var x: i32 = 0; x = 1; x += 2; }")?;
+        assert!(matches!(&stmts[1], Stmt::Assign(_)), "{:?}", stmts[1]);
+        assert!(matches!(&stmts[2], Stmt::Assign(_)), "{:?}", stmts[2]);
+        assert_eq!(kind("x = f();")?, Rule::assignment);
+        assert!(
+            parse_statement("a, b += f();").is_err(),
+            "Compound operators take a single target"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_types_round_trip() -> Result<()> {
+        let code = "fn split(v: (i32, [u8; 4])) -> (i32, bool) { a, b = split(v); }";
+        let formatted = format_carbon(code)?;
+        assert!(formatted.contains("fn split(v: (i32, [u8; 4])) -> (i32, bool) {"));
+        assert!(formatted.contains("a, b = split(v);"), "{formatted}");
+        Ok(())
+    }
+}
+
+mod annotation_tests {
+    use super::*;
+    use carbon_parser::ast::{ClassMember, Item, Stmt};
+
+    #[test]
+    fn test_diagnostic_lands_on_innermost_statement() -> Result<()> {
+        let code = r#"
+            fn main() -> i32 {
+                var x: i32 = 0;
+                while (x < 3) {
+                    if (true) { x = 1; }
+                    x += 1;
+                }
+                return x;
+            }
+        "#;
+        let mut program = parse_to_ast(code)?;
+        let diagnostics = check_ast(&program);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        annotate(&mut program, &diagnostics);
+
+        let Item::Function(main) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::While(while_stmt) = &main.body.stmts[1] else {
+            panic!("expected a while loop");
+        };
+        let Stmt::If(if_stmt) = &while_stmt.body.stmts[0] else {
+            panic!("expected an if statement");
+        };
+        assert_eq!(
+            if_stmt.diagnostics, diagnostics,
+            "The constant condition belongs to the `if`"
+        );
+        assert!(main.diagnostics.is_empty());
+        assert!(while_stmt.diagnostics.is_empty());
+        match &main.body.stmts[0] {
+            Stmt::Var(decl) => assert!(decl.diagnostics.is_empty()),
+            other => panic!("expected a variable, got {other:?}"),
+        }
+        match &if_stmt.then_block.stmts[0] {
+            Stmt::Assign(assign) => assert!(
+                assign.diagnostics.is_empty(),
+                "Nodes inside the `if` do not cover the condition"
+            ),
+            other => panic!("expected an assignment, got {other:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_declaration_level_diagnostics() -> Result<()> {
+        let code = r#"
+            class Grid {
+                var cells: [i32; 0];
+                fn (self: Self) [row: i32, column: i32] -> i32 { return row; }
+            }
+            fn ok() -> i32 { return 0; }
+        "#;
+        let mut program = parse_to_ast(code)?;
+        let diagnostics = check_ast(&program);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        annotate(&mut program, &diagnostics);
+
+        let Item::Class(grid) = &program.items[0] else {
+            panic!("expected a class");
+        };
+        let attached: Vec<_> = grid
+            .members
+            .iter()
+            .map(|member| match member {
+                ClassMember::Field(field) => field.diagnostics.clone(),
+                ClassMember::Method(method) => method.diagnostics.clone(),
+                ClassMember::IndexOperator(operator) => operator.diagnostics.clone(),
+            })
+            .collect();
+        assert_eq!(
+            attached,
+            [vec![diagnostics[0].clone()], vec![diagnostics[1].clone()]]
+        );
+        assert!(grid.diagnostics.is_empty());
+        let Item::Function(ok) = &program.items[1] else {
+            panic!("expected a function");
+        };
+        assert!(ok.diagnostics.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parsing_leaves_diagnostics_empty() -> Result<()> {
+        let program = parse_to_ast("var x: i32 = 1; fn f() { throw 1; }")?;
+        let Item::Var(decl) = &program.items[0] else {
+            panic!("expected a variable");
+        };
+        assert!(decl.diagnostics.is_empty());
+        let mut annotated = program.clone();
+        annotate(&mut annotated, &[]);
+        assert_eq!(annotated, program);
+        Ok(())
+    }
+}
+
+mod render_diagnostic_tests {
+    use super::*;
+
+    /// The column of the caret on the line after the one showing `text`.
+    fn caret_column(rendered: &str, text: &str) -> Option<usize> {
+        let lines: Vec<&str> = rendered.lines().collect();
+        let row = lines.iter().position(|line| line.ends_with(text))?;
+        let text_start = lines[row].len() - text.len();
+        let caret = lines.get(row + 1)?.find('^')?;
+        Some(caret - text_start + 1)
+    }
+
+    #[test]
+    fn test_caret_under_failing_column() -> Result<()> {
+        let source = "fn main() -> i32 {\n    var x: i32 = ;\n}";
+        let error = parse_carbon(source).expect_err("the initializer is missing");
+        let rendered = render_diagnostic(source, &error);
+
+        assert!(rendered.starts_with("error: expected one of"), "{rendered}");
+        assert!(rendered.contains("<input>:2:18"), "{rendered}");
+        assert!(
+            rendered.contains("2 │     var x: i32 = ;"),
+            "The offending line is shown: {rendered}"
+        );
+        assert_eq!(
+            caret_column(&rendered, "    var x: i32 = ;"),
+            Some(18),
+            "The caret sits under the `;`: {rendered}"
+        );
+        assert!(!rendered.contains('\u{1b}'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_at_end_of_input() -> Result<()> {
+        let source = "fn main() {";
+        let error = parse_carbon(source).expect_err("the body is not closed");
+        let rendered = render_diagnostic(source, &error);
+        assert_eq!(caret_column(&rendered, source), Some(12), "{rendered}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_colored_output_and_errors_without_position() -> Result<()> {
+        let source = "fn test {";
+        let error = parse_carbon(source).expect_err("the parameter list is missing");
+        let colored = render_diagnostic_colored(source, &error);
+        assert!(colored.contains('\u{1b}'), "Colors use ANSI escape codes");
+        assert!(colored.contains("fn test"));
+
+        let error = ParseError::syntax("unbalanced braces");
+        assert_eq!(
+            render_diagnostic(source, &error),
+            "error: unbalanced braces\n\n"
+        );
+        Ok(())
+    }
+}
+
+mod script_mode_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Stmt, TopItem};
+
+    const SCRIPT: &str = r#"
+        var total: i32 = 0;
+        fn square(x: i32) -> i32 { return x * x; }
+        for (var n: i32 in items) {
+            total += square(n);
+        }
+        while (total > 100) { total = total / 2; }
+        print(total);
+    "#;
+
+    fn script_options() -> ParseOptions {
+        ParseOptions {
+            script_mode: true,
+            ..ParseOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_top_level_statements_in_script_mode() -> Result<()> {
+        let program = parse_to_ast_with_options(SCRIPT, &script_options())?;
+        assert_eq!(program.items.len(), 2, "`var` and `fn` stay declarations");
+        assert!(matches!(program.items[0], Item::Var(_)));
+        assert!(matches!(program.items[1], Item::Function(_)));
+
+        let kinds: Vec<&str> = program
+            .top_level
+            .iter()
+            .map(|top| match top {
+                TopItem::Item(_) => "item",
+                TopItem::Stmt(stmt) => match **stmt {
+                    Stmt::For(_) => "for",
+                    Stmt::While(_) => "while",
+                    Stmt::Expr(_) => "expr",
+                    ref other => panic!("unexpected statement {other:?}"),
+                },
+            })
+            .collect();
+        assert_eq!(kinds, ["item", "item", "for", "while", "expr"]);
+        assert_eq!(program.top_level[1], TopItem::Item(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_level_statements_fail_without_script_mode() -> Result<()> {
+        assert!(parse_carbon(SCRIPT).is_err());
+        assert!(parse_to_ast_with_options(SCRIPT, &ParseOptions::default()).is_err());
+        assert!(
+            parse_to_ast_with_options("x = 1;", &script_options()).is_ok(),
+            "A script may consist of statements alone"
+        );
+        assert!(parse_to_ast("x = 1;").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_declarations_only_program_is_unchanged() -> Result<()> {
+        let code = "fn main() { } var x: i32 = 1;";
+        let plain = parse_to_ast(code)?;
+        assert_eq!(plain.top_level, [TopItem::Item(0), TopItem::Item(1)]);
+        assert_eq!(parse_to_ast_with_options(code, &script_options())?, plain);
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_is_printed_and_checked_in_order() -> Result<()> {
+        let program = parse_to_ast_with_options(SCRIPT, &script_options())?;
+        let printed = program.to_string();
+        let reparsed = parse_to_ast_with_options(&printed, &script_options())?;
+        assert_eq!(reparsed.to_string(), printed);
+        let square = printed.find("fn square").expect("the function is printed");
+        let loop_start = printed.find("for (").expect("the loop is printed");
+        assert!(square < loop_start, "Source order is kept:\n{printed}");
+
+        let program = parse_to_ast_with_options("while (1 > 2) { }", &script_options())?;
+        let warnings = check_ast(&program);
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].message.contains("always false"));
+        Ok(())
+    }
+}
+
+mod rule_coverage_tests {
+    use super::*;
+    use pest::iterators::Pair;
+
+    /// A sample program for every rule that can appear in the parse tree of a
+    /// program. New grammar rules need an entry here.
+    const COVERAGE: &[(Rule, &str)] = &[
+        (Rule::EOI, "fn main() {}"),
+        (Rule::program, "fn main() {}"),
+        (Rule::package_decl, "package Geometry api;"),
+        (Rule::package_kind, "package Geometry api;"),
+        (Rule::import_decl, "import Math;"),
+        (Rule::library_name, r#"import Geometry library "Shapes";"#),
+        (Rule::pragma, "//! pragma optimize(2)\nfn main() {}"),
+        (Rule::pragma_args, "//! pragma optimize(2)\nfn main() {}"),
+        (Rule::pragma_arg, "//! pragma optimize(2)\nfn main() {}"),
+        (Rule::function_decl, "fn main() {}"),
+        (Rule::throws_marker, "fn f() throws { throw 1; }"),
+        (Rule::async_marker, "async fn f() {}"),
+        (Rule::parameter_list, "fn f(x: i32) {}"),
+        (Rule::parameter, "fn f(x: i32) {}"),
+        (Rule::comptime_marker, "fn zeros(N:! i32) {}"),
+        (Rule::param_direction, "fn bump(inout n: i32) {}"),
+        (Rule::variadic_param, "fn printf(format: String, ...) {}"),
+        (Rule::class_decl, "class Point { var x: i32; }"),
+        (
+            Rule::index_operator_decl,
+            "class Grid { fn (self: Self) [i: i32] -> i32 { return i; } }",
+        ),
+        (Rule::class_modifier, "final class Leaf {}"),
+        (Rule::choice_decl, "choice Status { Ok = 0, Error }"),
+        (Rule::choice_variant, "choice Status { Ok = 0, Error }"),
+        (Rule::deduced_params, "class Set[T:! type] {}"),
+        (Rule::generic_params, "class Stack(T:! type) {}"),
+        (Rule::generic_param, "class Stack(T:! type) {}"),
+        (
+            Rule::where_clause,
+            "class Set[T:! type] where T impls Hash {}",
+        ),
+        (
+            Rule::where_constraint,
+            "class Set[T:! type] where T impls Hash {}",
+        ),
+        (Rule::var_decl, "var x: i32 = 1;"),
+        (Rule::let_decl, "let x: i32 = 1;"),
+        (Rule::block, "fn main() {}"),
+        (Rule::tail_expr, "fn f() -> i32 { 1 }"),
+        (Rule::statement, "fn f() { return; }"),
+        (Rule::return_stmt, "fn f() { return; }"),
+        (Rule::throw_stmt, "fn f() throws { throw 1; }"),
+        (Rule::if_statement, "fn f() { if (x) { } else { } }"),
+        (Rule::while_statement, "fn f() { while (x) { } }"),
+        (Rule::for_statement, "fn f() { for (var x: i32 in xs) { } }"),
+        (Rule::for_c_statement, "fn f() { for (;;) { } }"),
+        (Rule::pattern_param, "fn f(P(x): P) { }"),
+        (Rule::tuple_pattern, "fn f((a, b): (i32, i32)) { }"),
+        (Rule::for_update, "fn f() { for (;; i += 1) { } }"),
+        (Rule::condition, "fn f() { if (x) { } }"),
+        (
+            Rule::binding_condition,
+            "fn f() { if (var x: i32 = g()) { } }",
+        ),
+        (
+            Rule::match_statement,
+            "fn f() { match (v) { default => { } } }",
+        ),
+        (Rule::match_arm, "fn f() { match (v) { case 0 => { } } }"),
+        (
+            Rule::match_guard,
+            "fn f() { match (v) { case x if x > 0 => { } } }",
+        ),
+        (Rule::default_arm, "fn f() { match (v) { default => { } } }"),
+        (Rule::pattern, "fn f() { match (v) { case 0 => { } } }"),
+        (
+            Rule::wildcard_pattern,
+            "fn f() { match (v) { case _ => { } } }",
+        ),
+        (
+            Rule::constructor_pattern,
+            "fn f() { match (v) { case Some(x) => { } } }",
+        ),
+        (
+            Rule::binding_pattern,
+            "fn f() { match (v) { case x: i32 => { } } }",
+        ),
+        (Rule::expression_stmt, "fn f() { g(); }"),
+        (Rule::assignment, "fn f() { x = 1; }"),
+        (Rule::multi_assignment, "fn f() { a, b = g(); }"),
+        (Rule::add_assign, "fn f() { x += 1; }"),
+        (Rule::sub_assign, "fn f() { x -= 1; }"),
+        (Rule::mul_assign, "fn f() { x *= 1; }"),
+        (Rule::div_assign, "fn f() { x /= 1; }"),
+        (Rule::assign, "fn f() { x = 1; }"),
+        (Rule::expression, "var x: i32 = 1;"),
+        (Rule::primary, "var x: i32 = 1 + 2;"),
+        (Rule::type_value, "var t: Type = i32;"),
+        (
+            Rule::fallthrough_stmt,
+            "fn f() { match (x) { case 0 => { fallthrough; } } }",
+        ),
+        (Rule::break_stmt, "fn f() { while (true) { break; } }"),
+        (Rule::continue_stmt, "fn f() { while (true) { continue; } }"),
+        (Rule::conversion, "var d: f64 = f64(1);"),
+        (
+            Rule::conditional_expr,
+            "var s: i32 = if x > 0 then 1 else -1;",
+        ),
+        (Rule::await_op, "async fn f() { await g(); }"),
+        (Rule::try_op, "fn f() throws { g()?; }"),
+        (Rule::operand, "var x: i32 = p.x;"),
+        (Rule::index_suffix, "var x: i32 = a[0];"),
+        (Rule::tuple_index, "var x: i32 = p.0;"),
+        (Rule::add, "var x: i32 = a + b;"),
+        (Rule::sub, "var x: i32 = a - b;"),
+        (Rule::mul, "var x: i32 = a * b;"),
+        (Rule::div, "var x: i32 = a / b;"),
+        (Rule::rem, "var x: i32 = a % b;"),
+        (Rule::eq, "var x: bool = a == b;"),
+        (Rule::ne, "var x: bool = a != b;"),
+        (Rule::lt, "var x: bool = a < b;"),
+        (Rule::gt, "var x: bool = a > b;"),
+        (Rule::le, "var x: bool = a <= b;"),
+        (Rule::ge, "var x: bool = a >= b;"),
+        (Rule::and, "var x: bool = a && b;"),
+        (Rule::or, "var x: bool = a || b;"),
+        (Rule::function_call, "var x: i32 = f(1);"),
+        (Rule::argument_list, "var x: i32 = f(1);"),
+        (Rule::type_name, "var x: i32;"),
+        (Rule::base_type, "var x: i32;"),
+        (Rule::type_args, "var m: HashMap(String, i32);"),
+        (Rule::tuple_type, "fn f() -> (i32, bool) {}"),
+        (Rule::array_type, "var a: [i32; 4];"),
+        (Rule::primitive_type, "var x: i32;"),
+        (Rule::pointer_suffix, "var p: i32*;"),
+        (Rule::type_qualifier, "var c: const i32;"),
+        (Rule::literal, "var x: i32 = 1;"),
+        (Rule::integer_literal, "var x: i32 = 1;"),
+        (Rule::float_literal, "var x: f64 = 1.5;"),
+        (Rule::bool_literal, "var x: bool = true;"),
+        (Rule::string_literal, r#"var s: String = "hi";"#),
+        (Rule::char_literal, "var c: char = 'x';"),
+        (Rule::identifier, "var x: i32;"),
+    ];
+
+    /// Rules that never appear in the parse tree of a program.
+    const NOT_IN_PROGRAMS: &[Rule] = &[
+        // Silent rules produce no pairs.
+        Rule::WHITESPACE,
+        Rule::COMMENT,
+        Rule::line_comment,
+        Rule::block_comment,
+        Rule::pragma_start,
+        Rule::preamble,
+        Rule::top_trivia,
+        Rule::class_member,
+        Rule::assign_op,
+        Rule::binary_op,
+        Rule::postfix_suffix,
+        Rule::token,
+        Rule::float_exponent,
+        Rule::escape_sequence,
+        Rule::type_param,
+        // Only used as lookaheads.
+        Rule::sized_type_literal,
+        Rule::var_keyword,
+        Rule::let_keyword,
+        Rule::throw_keyword,
+        Rule::await_keyword,
+        // Start rules of the other entry points.
+        Rule::script,
+        Rule::token_stream,
+        Rule::punctuation,
+        Rule::comment_stream,
+        Rule::comment,
+        Rule::type_param_list,
+        Rule::function_decl_entry,
+        Rule::class_decl_entry,
+        Rule::class_member_entry,
+        Rule::var_decl_entry,
+        Rule::let_decl_entry,
+        Rule::package_decl_entry,
+        Rule::import_decl_entry,
+        Rule::expression_entry,
+        Rule::type_name_entry,
+        Rule::type_params_entry,
+        Rule::if_statement_entry,
+        Rule::while_statement_entry,
+        Rule::for_statement_entry,
+        Rule::assignment_entry,
+        Rule::statement_entry,
+    ];
+
+    /// Rules the grammar cannot currently produce, with a sample that should
+    /// produce them once it can.
+    const KNOWN_GAPS: &[(Rule, &str)] = &[];
+
+    fn contains_rule(pair: Pair<'_, Rule>, rule: Rule) -> bool {
+        pair.as_rule() == rule || pair.into_inner().any(|inner| contains_rule(inner, rule))
+    }
+
+    fn produces(sample: &str, rule: Rule) -> bool {
+        parse_carbon(sample).is_ok_and(|mut pairs| {
+            pairs
+                .next()
+                .is_some_and(|program| contains_rule(program, rule))
+        })
+    }
+
+    #[test]
+    fn test_every_rule_has_a_sample() -> Result<()> {
+        let missing: Vec<Rule> = Rule::all_rules()
+            .iter()
+            .copied()
+            .filter(|rule| {
+                !COVERAGE.iter().any(|(covered, _)| covered == rule)
+                    && !NOT_IN_PROGRAMS.contains(rule)
+                    && !KNOWN_GAPS.iter().any(|(gap, _)| gap == rule)
+            })
+            .collect();
+        assert!(missing.is_empty(), "Rules without a sample: {missing:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_sample_produces_its_rule() -> Result<()> {
+        for &(rule, sample) in COVERAGE {
+            assert!(
+                parse_carbon(sample).is_ok(),
+                "The sample for {rule:?} must parse: {sample}"
+            );
+            assert!(produces(sample, rule), "{sample} has no {rule:?} node");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_known_gaps_are_still_unreachable() -> Result<()> {
+        for &(rule, sample) in KNOWN_GAPS {
+            assert!(
+                !produces(sample, rule),
+                "{rule:?} is reachable now; move it to COVERAGE"
+            );
+        }
+        Ok(())
+    }
+}
+
+mod package_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, PackageKind};
+    use carbon_parser::format::format_carbon;
+
+    const FILE: &str = r#"
+        package Geometry library "Shapes" api;
+        import Math;
+        import Geometry library "Points";
+
+        fn area(radius: f64) -> f64 {
+            return Pi() * radius * radius;
+        }
+    "#;
+
+    #[test]
+    fn test_package_import_and_function() -> Result<()> {
+        assert!(parse_carbon(FILE).is_ok());
+        let program = parse_to_ast(FILE)?;
+
+        let package = program.package.as_ref().expect("a package declaration");
+        assert_eq!(package.name.name, "Geometry");
+        assert_eq!(package.library.as_deref(), Some("Shapes"));
+        assert_eq!(package.kind, PackageKind::Api);
+
+        let imports: Vec<(&str, Option<&str>)> = program
+            .imports
+            .iter()
+            .map(|import| (import.name.name.as_str(), import.library.as_deref()))
+            .collect();
+        assert_eq!(imports, [("Math", None), ("Geometry", Some("Points"))]);
+
+        assert_eq!(program.items.len(), 1, "Only the function is an item");
+        assert!(matches!(&program.items[0], Item::Function(f) if f.name.name == "area"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_must_come_first() -> Result<()> {
+        assert!(parse_carbon("fn main() {}\npackage Geometry api;").is_err());
+        assert!(parse_carbon("import Math;\npackage Geometry api;").is_err());
+        assert!(
+            parse_carbon("package A api;\npackage B api;").is_err(),
+            "A file belongs to one package"
+        );
+        assert!(
+            parse_carbon("fn main() {}\nimport Math;").is_err(),
+            "Imports come before the other declarations"
+        );
+        assert!(
+            parse_carbon("//! pragma optimize(2)\npackage Geometry impl;").is_ok(),
+            "Pragmas are not declarations"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_points_and_formatting() -> Result<()> {
+        assert!(parse_package_decl("package Geometry impl;").is_ok());
+        assert!(parse_package_decl("package Geometry;").is_err());
+        assert!(parse_import_decl("import Math;").is_ok());
+        assert!(parse_import_decl("import Math api;").is_err());
+
+        let formatted = format_carbon(FILE)?;
+        assert!(
+            formatted.starts_with(
+                "package Geometry library \"Shapes\" api;\nimport Math;\n\
+                 import Geometry library \"Points\";\n\nfn area("
+            ),
+            "{formatted}"
+        );
+        assert_eq!(format_carbon(&formatted)?, formatted);
+        Ok(())
+    }
+}
+
+mod stats_tests {
+    use super::*;
+    use carbon_parser::stats::collect_stats;
+
+    #[test]
+    fn test_counts_functions_and_vars() -> Result<()> {
+        let code = r#"
+            var limit: i32 = 10;
+            fn first() { var a: i32 = 1; }
+            fn second(x: i32) -> i32 {
+                var b: i32 = x * 2;
+                return b;
+            }
+        "#;
+        let stats = collect_stats(code)?;
+        assert_eq!(stats.functions, 2);
+        assert_eq!(stats.vars, 3);
+        assert_eq!(stats.expressions, 4, "10, 1, x * 2 and b");
+        assert!(stats.total_nodes > stats.max_depth);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_depth_follows_nesting() -> Result<()> {
+        let flat = collect_stats("fn f() { }")?;
+        let nested = collect_stats("fn f() { while (a) { if (b) { c(); } } }")?;
+        assert!(nested.max_depth > flat.max_depth);
+        assert!(collect_stats("fn broken(").is_err());
+        Ok(())
+    }
+}
+
+mod ast_hash_tests {
+    use super::*;
+    use carbon_parser::ast::ast_hash;
+    use carbon_parser::format::format_carbon;
+
+    const CODE: &str = r#"
+        // Sums the first n numbers.
+        fn sum(n: i32) -> i32 {
+            var total: i32 = 0;
+            for (var i: i32 in range(n)) { total += i; }
+            return total;
+        }
+    "#;
+
+    #[test]
+    fn test_reformatting_keeps_hash() -> Result<()> {
+        let original = ast_hash(&parse_to_ast(CODE)?);
+        let formatted = ast_hash(&parse_to_ast(&format_carbon(CODE)?)?);
+        assert_eq!(original, formatted);
+        Ok(())
+    }
+
+    #[test]
+    fn test_whitespace_keeps_hash() -> Result<()> {
+        let spaced = CODE.replace(' ', "  ").replace('\n', "\n\n");
+        assert_eq!(
+            ast_hash(&parse_to_ast(CODE)?),
+            ast_hash(&parse_to_ast(&spaced)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_renaming_changes_hash() -> Result<()> {
+        let renamed = CODE.replace("fn sum", "fn total_of");
+        assert_ne!(
+            ast_hash(&parse_to_ast(CODE)?),
+            ast_hash(&parse_to_ast(&renamed)?)
+        );
+        Ok(())
+    }
+}
+
+mod pattern_param_tests {
+    use super::*;
+    use carbon_parser::ast::{Item, Param, Pattern};
+    use carbon_parser::format::format_carbon;
+    use carbon_parser::semantics::check_undeclared;
+
+    #[test]
+    fn test_tuple_pattern_param() -> Result<()> {
+        let code = "fn dist((x, y): (i32, i32)) -> i32 { return x * x + y * y; }";
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[0] else {
+            panic!("expected a function");
+        };
+        let Param::Pattern { pattern, ty, .. } = &function.params[0] else {
+            panic!("expected a pattern parameter");
+        };
+        assert!(matches!(&**pattern, Pattern::Tuple { elements, .. } if elements.len() == 2));
+        assert_eq!(ty.canonical(), "(i32, i32)");
+        assert!(check_program(code)?.is_empty());
+        assert!(
+            check_undeclared(&program).is_empty(),
+            "x and y are declared"
+        );
+        assert_eq!(
+            format_carbon(code)?.lines().next(),
+            Some("fn dist((x, y): (i32, i32)) -> i32 {")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_pattern_param() -> Result<()> {
+        let code = r#"
+            class Point { var x: i32; var y: i32; }
+            fn norm(Point(x, y): Point, scale: i32) -> i32 { return (x + y) * scale; }
+        "#;
+        let program = parse_to_ast(code)?;
+        let Item::Function(function) = &program.items[1] else {
+            panic!("expected a function");
+        };
+        let Param::Pattern { pattern, .. } = &function.params[0] else {
+            panic!("expected a pattern parameter");
+        };
+        assert!(matches!(&**pattern, Pattern::Constructor { args, .. } if args.len() == 2));
+        assert!(check_program(code)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pattern_arity_mismatch() -> Result<()> {
+        let code = r#"
+            class Point { var x: i32; var y: i32; }
+            fn first((a, b, c): (i32, i32)) { }
+            fn scalar((a, b): i32) { }
+            fn nested((a, (b, c)): (i32, (i32, i32, i32))) { }
+            fn corner(Point(x): Point) { }
+        "#;
+        let messages: Vec<String> = check_program(code)?
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "tuple pattern with 3 elements does not match type `(i32, i32)`",
+                "tuple pattern with 2 elements does not match type `i32`",
+                "tuple pattern with 2 elements does not match type `(i32, i32, i32)`",
+                "pattern `Point` does not match the fields of class `Point`: expected 2, found 1",
+            ]
+        );
+        Ok(())
+    }
+}
+
+mod dot_tests {
+    use super::*;
+    use carbon_parser::dot::to_dot;
+
+    #[test]
+    fn test_function_graph() -> Result<()> {
+        let dot = to_dot("fn add(x: i32) -> i32 { return x; }")?;
+        assert!(dot.starts_with("digraph parse_tree {\n"));
+        assert!(dot.contains("n1 [label=\"function_decl\"];"));
+        assert!(
+            dot.contains("label=\"identifier\\nadd\""),
+            "leaves show their text"
+        );
+        let nodes = dot.matches("[label=").count();
+        let edges = dot.matches(" -> ").count();
+        assert_eq!(edges, nodes - 1, "a tree has one edge fewer than nodes");
+        assert!(!dot.contains("EOI"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_labels_are_escaped() -> Result<()> {
+        let dot = to_dot(r#"var s: String = "say \"hi\"";"#)?;
+        assert!(
+            dot.contains(r#"string_literal\n\"say \\\"hi\\\"\""#),
+            "{dot}"
+        );
+        assert!(to_dot("fn broken(").is_err());
+        Ok(())
+    }
+}